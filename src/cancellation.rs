@@ -1,9 +1,14 @@
 use parking_lot::Mutex;
-use std::future::Future;
-use std::pin::Pin;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::task::{Context, Poll};
+use std::collections::HashMap;
+use std::future::{Future, poll_fn};
+use std::pin::{Pin, pin};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+
+use crate::executor::yield_now;
+use crate::timer::TimerEntry;
 
 /// Token for cooperative task cancellation.
 ///
@@ -28,43 +33,376 @@ pub struct CancellationToken {
     inner: Arc<CancellationState>,
 }
 
+type WaiterId = u64;
+
 struct CancellationState {
     is_cancelled: AtomicBool,
-    wakers: Mutex<Vec<std::task::Waker>>,
+    reason: Mutex<Option<Arc<str>>>,
+    wakers: Mutex<HashMap<WaiterId, Waker>>,
+    next_waiter_id: AtomicU64,
+    children: Mutex<Vec<Weak<CancellationState>>>,
+    /// Timer that cancels this state, set by [`CancellationToken::with_timeout`].
+    timeout: Mutex<Option<TimerEntry>>,
+    /// Waker slots this state holds in its inputs, set by
+    /// [`CancellationToken::all`] and released when the state is dropped.
+    subscriptions: Mutex<Vec<(Weak<CancellationState>, WaiterId)>>,
+}
+
+impl CancellationState {
+    fn new() -> Arc<Self> {
+        Arc::new(CancellationState {
+            is_cancelled: AtomicBool::new(false),
+            reason: Mutex::new(None),
+            wakers: Mutex::new(HashMap::new()),
+            next_waiter_id: AtomicU64::new(0),
+            children: Mutex::new(Vec::new()),
+            timeout: Mutex::new(None),
+            subscriptions: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Cancels the state, recording `reason` if this is the first
+    /// cancellation. The flag is flipped under the reason lock so anyone who
+    /// observes it set also observes the reason.
+    fn cancel(&self, reason: Option<Arc<str>>) {
+        let mut stored_reason = self.reason.lock();
+        if self.is_cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        *stored_reason = reason.clone();
+        drop(stored_reason);
+
+        drop(self.timeout.lock().take());
+
+        let wakers = std::mem::take(&mut *self.wakers.lock());
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+
+        let children = std::mem::take(&mut *self.children.lock());
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.cancel(reason.clone());
+        }
+    }
+
+    /// Registers `child` to be cancelled along with this state. The children
+    /// lock is held while checking the flag so a concurrent `cancel` can't
+    /// miss the new child.
+    fn add_child(&self, child: &Arc<CancellationState>) {
+        let mut children = self.children.lock();
+        if self.is_cancelled.load(Ordering::SeqCst) {
+            drop(children);
+            child.cancel(self.reason.lock().clone());
+            return;
+        }
+
+        children.retain(|existing| existing.strong_count() > 0);
+        children.push(Arc::downgrade(child));
+    }
+
+    /// Wakes `waker` once this state is cancelled, or right away if it
+    /// already is. Returns the slot holding the waker, if one was taken.
+    fn subscribe(&self, waker: Waker) -> Option<WaiterId> {
+        let mut wakers = self.wakers.lock();
+        if self.is_cancelled.load(Ordering::SeqCst) {
+            drop(wakers);
+            waker.wake();
+            return None;
+        }
+
+        let waiter_id = self.next_waiter_id.fetch_add(1, Ordering::Relaxed);
+        wakers.insert(waiter_id, waker);
+        Some(waiter_id)
+    }
+}
+
+impl Drop for CancellationState {
+    fn drop(&mut self) {
+        for (input, waiter_id) in self.subscriptions.get_mut().drain(..) {
+            if let Some(input) = input.upgrade() {
+                input.wakers.lock().remove(&waiter_id);
+            }
+        }
+    }
+}
+
+/// Cancels the state it points to when its timer fires.
+struct TimeoutWaker {
+    state: Weak<CancellationState>,
+}
+
+impl Wake for TimeoutWaker {
+    fn wake(self: Arc<Self>) {
+        if let Some(state) = self.state.upgrade() {
+            state.cancel(None);
+        }
+    }
+}
+
+/// Cancels the state it points to once every input it subscribed to has been
+/// cancelled.
+struct AllCancelledWaker {
+    remaining: AtomicUsize,
+    state: Weak<CancellationState>,
+}
+
+impl Wake for AllCancelledWaker {
+    fn wake(self: Arc<Self>) {
+        if self.remaining.fetch_sub(1, Ordering::SeqCst) == 1
+            && let Some(state) = self.state.upgrade()
+        {
+            state.cancel(None);
+        }
+    }
 }
 
 impl CancellationToken {
     pub fn new() -> Self {
         CancellationToken {
-            inner: Arc::new(CancellationState {
-                is_cancelled: AtomicBool::new(false),
-                wakers: Mutex::new(Vec::new()),
-            }),
+            inner: CancellationState::new(),
         }
     }
 
+    /// Creates a token that cancels itself once `duration` has elapsed.
+    ///
+    /// The timer is deregistered if the token is cancelled sooner.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{CancellationToken, Runtime};
+    /// use std::time::Duration;
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let token = CancellationToken::with_timeout(Duration::from_millis(10));
+    ///     token.cancelled().await;
+    ///     assert!(token.is_cancelled());
+    /// });
+    /// ```
+    pub fn with_timeout(duration: Duration) -> Self {
+        let inner = CancellationState::new();
+        let waker = Waker::from(Arc::new(TimeoutWaker {
+            state: Arc::downgrade(&inner),
+        }));
+        let timer = TimerEntry::new(duration, waker);
+
+        let mut timeout = inner.timeout.lock();
+        if !inner.is_cancelled.load(Ordering::SeqCst) {
+            *timeout = Some(timer);
+        }
+        drop(timeout);
+
+        CancellationToken { inner }
+    }
+
     pub fn cancel(&self) {
-        self.inner.is_cancelled.store(true, Ordering::SeqCst);
-        let wakers = std::mem::take(&mut *self.inner.wakers.lock());
-        for waker in wakers {
-            waker.wake();
+        self.inner.cancel(None);
+    }
+
+    /// Returns a future that yields to the scheduler `polls` times and then
+    /// cancels the token.
+    ///
+    /// Meant for tests: spawned next to the code under test, it cancels
+    /// after a fixed amount of scheduler progress instead of after
+    /// wall-clock time. For a time-based trigger without real sleeps, create
+    /// the token with [`with_timeout`](Self::with_timeout) while a
+    /// [`TestClock`](crate::timer::TestClock) is entered, then advance the
+    /// clock.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{CancellationToken, Runtime, yield_now};
+    ///
+    /// let runtime = Runtime::new();
+    /// let token = CancellationToken::new();
+    /// let canceller = runtime.spawner().spawn(token.cancel_after_polls(3)).unwrap();
+    ///
+    /// let worker_token = token.clone();
+    /// let worker = runtime.spawner().spawn(async move {
+    ///     let mut rounds = 0;
+    ///     while !worker_token.is_cancelled() {
+    ///         rounds += 1;
+    ///         yield_now().await;
+    ///     }
+    ///     rounds
+    /// });
+    ///
+    /// let _workers = runtime.run(1);
+    /// canceller.join().unwrap();
+    /// assert!(worker.unwrap().join().unwrap() >= 1);
+    /// ```
+    pub fn cancel_after_polls(&self, polls: usize) -> impl Future<Output = ()> + Send + use<> {
+        let token = self.clone();
+        async move {
+            for _ in 0..polls {
+                yield_now().await;
+            }
+            token.cancel();
         }
     }
 
+    /// Cancels the token, recording why. Only the first cancellation's
+    /// reason is kept; it is also passed down to child tokens.
+    pub fn cancel_with_reason(&self, reason: Arc<str>) {
+        self.inner.cancel(Some(reason));
+    }
+
+    /// Returns the reason given to [`cancel_with_reason`](Self::cancel_with_reason),
+    /// or `None` if the token isn't cancelled or was cancelled without one.
+    pub fn reason(&self) -> Option<Arc<str>> {
+        self.inner.reason.lock().clone()
+    }
+
+    /// Returns whether the token has been cancelled.
+    ///
+    /// Cheap enough to call in tight loops: it is a single `Acquire` load.
+    /// That pairs with the `SeqCst` swap in `cancel`, so anything written
+    /// before cancelling is visible once this returns `true`. The stronger
+    /// ordering used elsewhere only matters for the waker and child
+    /// registration protocols, which check the flag under their own locks.
     pub fn is_cancelled(&self) -> bool {
-        self.inner.is_cancelled.load(Ordering::SeqCst)
+        self.inner.is_cancelled.load(Ordering::Acquire)
     }
 
     pub fn cancelled(&self) -> CancelledFuture {
         CancelledFuture {
+            waiter_id: self.inner.next_waiter_id.fetch_add(1, Ordering::Relaxed),
             inner: self.inner.clone(),
         }
     }
 
-    pub fn child_token(&self) -> CancellationToken {
-        CancellationToken {
-            inner: self.inner.clone(),
+    /// Same as [`cancelled`](Self::cancelled), spelled out for callers that
+    /// store the future: it holds its own reference to the token's state, so
+    /// it is `Send + 'static` and outlives `self`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{CancellationToken, Runtime};
+    /// use std::future::Future;
+    /// use std::pin::Pin;
+    ///
+    /// struct Connection {
+    ///     shutdown: Pin<Box<dyn Future<Output = ()> + Send>>,
+    /// }
+    ///
+    /// let token = CancellationToken::new();
+    /// let connection = Connection { shutdown: Box::pin(token.cancelled_owned()) };
+    ///
+    /// token.cancel();
+    /// Runtime::new().block_on(connection.shutdown);
+    /// ```
+    pub fn cancelled_owned(&self) -> CancelledFuture {
+        self.cancelled()
+    }
+
+    /// Runs `future` until it completes or this token is cancelled.
+    ///
+    /// Returns `None` if cancellation wins, in which case `future` is dropped
+    /// without being polled again.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use runtime::{CancellationToken, sleep};
+    /// use std::time::Duration;
+    ///
+    /// async fn worker(token: CancellationToken) {
+    ///     match token.run_until_cancelled(sleep(Duration::from_secs(5))).await {
+    ///         Some(()) => println!("finished"),
+    ///         None => println!("cancelled"),
+    ///     }
+    /// }
+    /// ```
+    pub async fn run_until_cancelled<F: Future>(&self, future: F) -> Option<F::Output> {
+        let mut future = pin!(future);
+        let mut cancelled = self.cancelled();
+
+        poll_fn(|cx| {
+            if Pin::new(&mut cancelled).poll(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+            future.as_mut().poll(cx).map(Some)
+        })
+        .await
+    }
+
+    /// Creates a token that is cancelled as soon as any of `tokens` is,
+    /// inheriting that token's reason. Cancelling the returned token doesn't
+    /// affect the inputs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::CancellationToken;
+    ///
+    /// let request = CancellationToken::new();
+    /// let shutdown = CancellationToken::new();
+    /// let token = CancellationToken::any(&[request.clone(), shutdown.clone()]);
+    ///
+    /// shutdown.cancel();
+    /// assert!(token.is_cancelled());
+    /// ```
+    pub fn any(tokens: &[CancellationToken]) -> CancellationToken {
+        let inner = CancellationState::new();
+        for token in tokens {
+            token.inner.add_child(&inner);
         }
+        CancellationToken { inner }
+    }
+
+    /// Creates a token that is cancelled once every one of `tokens` is. The
+    /// token for an empty slice starts cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::CancellationToken;
+    ///
+    /// let first = CancellationToken::new();
+    /// let second = CancellationToken::new();
+    /// let token = CancellationToken::all(&[first.clone(), second.clone()]);
+    ///
+    /// first.cancel();
+    /// assert!(!token.is_cancelled());
+    /// second.cancel();
+    /// assert!(token.is_cancelled());
+    /// ```
+    pub fn all(tokens: &[CancellationToken]) -> CancellationToken {
+        let inner = CancellationState::new();
+        if tokens.is_empty() {
+            inner.cancel(None);
+            return CancellationToken { inner };
+        }
+
+        let waker = Waker::from(Arc::new(AllCancelledWaker {
+            remaining: AtomicUsize::new(tokens.len()),
+            state: Arc::downgrade(&inner),
+        }));
+        let subscriptions = tokens
+            .iter()
+            .filter_map(|token| {
+                let waiter_id = token.inner.subscribe(waker.clone())?;
+                Some((Arc::downgrade(&token.inner), waiter_id))
+            })
+            .collect();
+        *inner.subscriptions.lock() = subscriptions;
+        CancellationToken { inner }
+    }
+
+    /// Wraps the token in a guard that cancels it when dropped.
+    pub fn drop_guard(self) -> DropGuard {
+        DropGuard { token: Some(self) }
+    }
+
+    /// Creates a token that is cancelled whenever this one is, but whose own
+    /// cancellation doesn't propagate back to this token.
+    pub fn child_token(&self) -> CancellationToken {
+        let child = CancellationState::new();
+        self.inner.add_child(&child);
+        CancellationToken { inner: child }
     }
 }
 
@@ -82,8 +420,45 @@ impl Clone for CancellationToken {
     }
 }
 
+/// Cancels its token when dropped unless [disarmed](DropGuard::disarm).
+///
+/// # Example
+///
+/// ```
+/// use runtime::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// {
+///     let _guard = token.clone().drop_guard();
+/// }
+/// assert!(token.is_cancelled());
+/// ```
+pub struct DropGuard {
+    token: Option<CancellationToken>,
+}
+
+impl DropGuard {
+    /// Returns the token without cancelling it.
+    pub fn disarm(mut self) -> CancellationToken {
+        self.token.take().expect("drop guard token taken twice")
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            token.cancel();
+        }
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled`].
+///
+/// Each future owns a single waker slot in the token, overwritten on re-poll
+/// and released on drop.
 pub struct CancelledFuture {
     inner: Arc<CancellationState>,
+    waiter_id: WaiterId,
 }
 
 impl Future for CancelledFuture {
@@ -94,7 +469,15 @@ impl Future for CancelledFuture {
             return Poll::Ready(());
         }
 
-        self.inner.wakers.lock().push(cx.waker().clone());
+        let mut wakers = self.inner.wakers.lock();
+        match wakers.get_mut(&self.waiter_id) {
+            Some(stored) if stored.will_wake(cx.waker()) => {}
+            Some(stored) => *stored = cx.waker().clone(),
+            None => {
+                wakers.insert(self.waiter_id, cx.waker().clone());
+            }
+        }
+        drop(wakers);
 
         if self.inner.is_cancelled.load(Ordering::SeqCst) {
             Poll::Ready(())
@@ -104,9 +487,48 @@ impl Future for CancelledFuture {
     }
 }
 
+impl Drop for CancelledFuture {
+    fn drop(&mut self) {
+        self.inner.wakers.lock().remove(&self.waiter_id);
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    // `is_cancelled` uses an `Acquire` load rather than `SeqCst`. A `Relaxed`
+    // load would still eventually observe the flag, but wouldn't order the
+    // canceller's earlier writes before the reader's later reads;
+    // `cancellation_publishes_writes_made_before_cancel` relies on that edge.
+    // Total ordering across different atomics is never needed on this path.
+
     use super::*;
+    use crate::timer::TestClock;
+
+    #[test]
+    fn cancelled_future_is_send_and_static() {
+        fn assert_traits<F: Future<Output = ()> + Send + Unpin + 'static>(_: &F) {}
+        assert_traits(&CancellationToken::new().cancelled_owned());
+    }
+
+    #[test]
+    fn stored_owned_future_outlives_its_token() {
+        struct Listener {
+            shutdown: Pin<Box<dyn Future<Output = ()> + Send>>,
+        }
+
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let token = CancellationToken::new();
+        let mut listener = Listener {
+            shutdown: Box::pin(token.child_token().cancelled_owned()),
+        };
+        assert!(listener.shutdown.as_mut().poll(&mut context).is_pending());
+
+        token.cancel();
+        drop(token);
+
+        assert!(listener.shutdown.as_mut().poll(&mut context).is_ready());
+    }
 
     #[test]
     fn token_starts_not_cancelled() {
@@ -132,13 +554,51 @@ mod tests {
     }
 
     #[test]
-    fn child_token_shares_state() {
+    fn cancelling_parent_cancels_descendants() {
         let parent = CancellationToken::new();
-        let child = parent.child_token();
+        let first = parent.child_token();
+        let second = parent.child_token();
+        let grandchild = first.child_token();
 
         parent.cancel();
 
+        assert!(first.is_cancelled());
+        assert!(second.is_cancelled());
+        assert!(grandchild.is_cancelled());
+    }
+
+    #[test]
+    fn cancelling_child_does_not_propagate_upward() {
+        let parent = CancellationToken::new();
+        let child = parent.child_token();
+        let sibling = parent.child_token();
+        let grandchild = child.child_token();
+
+        child.cancel();
+
         assert!(child.is_cancelled());
+        assert!(grandchild.is_cancelled());
+        assert!(!parent.is_cancelled());
+        assert!(!sibling.is_cancelled());
+    }
+
+    #[test]
+    fn child_of_cancelled_parent_starts_cancelled() {
+        let parent = CancellationToken::new();
+        parent.cancel();
+
+        assert!(parent.child_token().is_cancelled());
+    }
+
+    #[test]
+    fn dropped_children_are_pruned() {
+        let parent = CancellationToken::new();
+        for _ in 0..100 {
+            drop(parent.child_token());
+        }
+        let _child = parent.child_token();
+
+        assert_eq!(parent.inner.children.lock().len(), 1);
     }
 
     #[test]
@@ -157,4 +617,242 @@ mod tests {
         let token = CancellationToken::default();
         assert!(!token.is_cancelled());
     }
+
+    #[test]
+    fn repolling_cancelled_future_keeps_single_waker() {
+        let token = CancellationToken::new();
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let mut future = token.cancelled();
+
+        for _ in 0..10_000 {
+            assert!(Pin::new(&mut future).poll(&mut context).is_pending());
+        }
+
+        assert_eq!(token.inner.wakers.lock().len(), 1);
+    }
+
+    #[test]
+    fn dropping_cancelled_future_releases_waker() {
+        let token = CancellationToken::new();
+        let waker = Waker::noop();
+        let mut future = token.cancelled();
+
+        let _ = Pin::new(&mut future).poll(&mut Context::from_waker(waker));
+        drop(future);
+
+        assert!(token.inner.wakers.lock().is_empty());
+    }
+
+    #[test]
+    fn dropping_guard_cancels_token() {
+        let token = CancellationToken::new();
+        let guard = token.clone().drop_guard();
+
+        drop(guard);
+
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn disarmed_guard_does_not_cancel() {
+        let token = CancellationToken::new();
+        let guard = token.clone().drop_guard();
+
+        let reclaimed = guard.disarm();
+        drop(reclaimed);
+
+        assert!(!token.is_cancelled());
+    }
+
+    #[test]
+    fn cancel_with_reason_is_visible_to_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+
+        token.cancel_with_reason(Arc::from("timeout"));
+
+        assert!(clone.is_cancelled());
+        assert_eq!(clone.reason().as_deref(), Some("timeout"));
+    }
+
+    #[test]
+    fn reason_propagates_to_children() {
+        let parent = CancellationToken::new();
+        let child = parent.child_token();
+
+        parent.cancel_with_reason(Arc::from("shutdown"));
+
+        assert_eq!(child.reason().as_deref(), Some("shutdown"));
+        assert_eq!(parent.child_token().reason().as_deref(), Some("shutdown"));
+    }
+
+    #[test]
+    fn first_cancellation_reason_wins() {
+        let token = CancellationToken::new();
+
+        token.cancel();
+        token.cancel_with_reason(Arc::from("late"));
+
+        assert_eq!(token.reason(), None);
+    }
+
+    #[test]
+    fn cancel_after_polls_cancels_on_the_last_poll() {
+        let token = CancellationToken::new();
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let mut canceller = pin!(token.cancel_after_polls(2));
+
+        for _ in 0..2 {
+            assert!(canceller.as_mut().poll(&mut context).is_pending());
+            assert!(!token.is_cancelled());
+        }
+
+        assert!(canceller.as_mut().poll(&mut context).is_ready());
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn with_timeout_cancels_when_timer_fires() {
+        let clock = TestClock::new();
+        let guard = clock.enter();
+        let token = CancellationToken::with_timeout(Duration::from_secs(5));
+        drop(guard);
+
+        clock.advance(Duration::from_secs(4));
+        assert!(!token.is_cancelled());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(token.is_cancelled());
+        assert_eq!(token.reason(), None);
+    }
+
+    #[test]
+    fn manual_cancel_releases_timeout_timer() {
+        let clock = TestClock::new();
+        let _guard = clock.enter();
+        let token = CancellationToken::with_timeout(Duration::from_secs(5));
+        assert!(token.inner.timeout.lock().is_some());
+
+        token.cancel();
+
+        assert!(token.inner.timeout.lock().is_none());
+    }
+
+    #[test]
+    fn any_cancels_on_first_input() {
+        let first = CancellationToken::new();
+        let second = CancellationToken::new();
+        let token = CancellationToken::any(&[first.clone(), second.clone()]);
+
+        assert!(!token.is_cancelled());
+
+        second.cancel_with_reason(Arc::from("shutdown"));
+        assert!(token.is_cancelled());
+        assert_eq!(token.reason().as_deref(), Some("shutdown"));
+        assert!(!first.is_cancelled());
+
+        first.cancel();
+        assert_eq!(token.reason().as_deref(), Some("shutdown"));
+    }
+
+    #[test]
+    fn any_of_cancelled_input_starts_cancelled() {
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+
+        let token = CancellationToken::any(&[CancellationToken::new(), cancelled]);
+
+        assert!(token.is_cancelled());
+        assert!(!CancellationToken::any(&[]).is_cancelled());
+    }
+
+    #[test]
+    fn all_waits_for_every_input() {
+        let first = CancellationToken::new();
+        let second = CancellationToken::new();
+        let third = CancellationToken::new();
+        let token = CancellationToken::all(&[first.clone(), second.clone(), third.clone()]);
+
+        second.cancel();
+        assert!(!token.is_cancelled());
+        second.cancel();
+        first.cancel();
+        assert!(!token.is_cancelled());
+
+        third.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn all_counts_already_cancelled_inputs() {
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        let pending = CancellationToken::new();
+
+        let token = CancellationToken::all(&[cancelled, pending.clone()]);
+        assert!(!token.is_cancelled());
+
+        pending.cancel();
+        assert!(token.is_cancelled());
+        assert!(CancellationToken::all(&[]).is_cancelled());
+    }
+
+    #[test]
+    fn dropped_all_tokens_release_their_input_wakers() {
+        let input = CancellationToken::new();
+        for _ in 0..100 {
+            drop(CancellationToken::all(std::slice::from_ref(&input)));
+        }
+        let _token = CancellationToken::all(std::slice::from_ref(&input));
+
+        assert_eq!(input.inner.wakers.lock().len(), 1);
+    }
+
+    #[test]
+    fn all_wakes_pending_cancelled_future() {
+        let first = CancellationToken::new();
+        let second = CancellationToken::new();
+        let token = CancellationToken::all(&[first.clone(), second.clone()]);
+        let waker = Waker::noop();
+        let mut future = token.cancelled();
+
+        assert!(
+            Pin::new(&mut future)
+                .poll(&mut Context::from_waker(waker))
+                .is_pending()
+        );
+        first.cancel();
+        second.cancel();
+
+        assert!(
+            Pin::new(&mut future)
+                .poll(&mut Context::from_waker(waker))
+                .is_ready()
+        );
+    }
+
+    #[test]
+    fn cancellation_publishes_writes_made_before_cancel() {
+        for _ in 0..100 {
+            let token = CancellationToken::new();
+            let payload = Arc::new(AtomicUsize::new(0));
+
+            let canceller = {
+                let token = token.clone();
+                let payload = payload.clone();
+                std::thread::spawn(move || {
+                    payload.store(42, Ordering::Relaxed);
+                    token.cancel();
+                })
+            };
+
+            while !token.is_cancelled() {
+                std::hint::spin_loop();
+            }
+            assert_eq!(payload.load(Ordering::Relaxed), 42);
+            canceller.join().unwrap();
+        }
+    }
 }
@@ -0,0 +1,65 @@
+/// Cores the current process is allowed to run on, in ascending order.
+/// Always empty on platforms other than Linux.
+#[cfg(target_os = "linux")]
+pub(super) fn available_cores() -> Vec<usize> {
+    // SAFETY: `cpu_set_t` is a plain bitmask, for which all zeroes is the
+    // empty set.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    // SAFETY: `set` is a valid `cpu_set_t` of exactly the size passed.
+    let result = unsafe { libc::sched_getaffinity(0, size_of::<libc::cpu_set_t>(), &mut set) };
+    if result != 0 {
+        return Vec::new();
+    }
+
+    (0..libc::CPU_SETSIZE as usize)
+        // SAFETY: `core` is below `CPU_SETSIZE`, so within the set.
+        .filter(|&core| unsafe { libc::CPU_ISSET(core, &set) })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(super) fn available_cores() -> Vec<usize> {
+    Vec::new()
+}
+
+/// Restricts the calling thread to `core`. Returns whether it succeeded;
+/// always fails on platforms other than Linux.
+#[cfg(target_os = "linux")]
+pub(super) fn pin_current_thread(core: usize) -> bool {
+    // SAFETY: see `available_cores`.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    // SAFETY: `CPU_SET` bounds-checks `core` against the set's size.
+    unsafe { libc::CPU_SET(core, &mut set) };
+    // SAFETY: `set` is a valid `cpu_set_t` of exactly the size passed, and
+    // pid 0 refers to the calling thread.
+    unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set) == 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(super) fn pin_current_thread(_core: usize) -> bool {
+    false
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_at_least_one_core() {
+        assert!(!available_cores().is_empty());
+    }
+
+    #[test]
+    fn pinned_thread_only_sees_its_core() {
+        let core = available_cores()[0];
+
+        let cores = std::thread::spawn(move || {
+            assert!(pin_current_thread(core));
+            available_cores()
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(cores, vec![core]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use crossbeam_deque::Steal;
+
+use super::scheduler::Scheduler;
+use super::worker::execute_task;
+
+pub(super) struct ThreadWaker {
+    thread: Thread,
+    is_notified: AtomicBool,
+}
+
+impl ThreadWaker {
+    pub(super) fn new() -> Arc<Self> {
+        Arc::new(ThreadWaker {
+            thread: thread::current(),
+            is_notified: AtomicBool::new(true),
+        })
+    }
+
+    pub(super) fn take_notification(&self) -> bool {
+        self.is_notified.swap(false, Ordering::SeqCst)
+    }
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.is_notified.store(true, Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+/// Keeps the current thread registered with the scheduler for the duration
+/// of a `block_on` call.
+struct BlockOnRegistration<'a> {
+    scheduler: &'a Scheduler,
+}
+
+impl<'a> BlockOnRegistration<'a> {
+    fn new(scheduler: &'a Scheduler) -> Self {
+        scheduler.register_block_on_thread();
+        BlockOnRegistration { scheduler }
+    }
+}
+
+impl Drop for BlockOnRegistration<'_> {
+    fn drop(&mut self) {
+        self.scheduler.deregister_block_on_thread();
+    }
+}
+
+pub(super) fn block_on<F: Future>(scheduler: &Scheduler, future: F) -> F::Output {
+    let _registration = BlockOnRegistration::new(scheduler);
+    let mut future = pin!(future);
+    let thread_waker = ThreadWaker::new();
+    let waker = Waker::from(thread_waker.clone());
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        if thread_waker.take_notification()
+            && let Poll::Ready(output) = future.as_mut().poll(&mut context)
+        {
+            return output;
+        }
+
+        match scheduler.steal() {
+            Steal::Success(task) => execute_task(&task),
+            Steal::Retry => continue,
+            // Both our waker and the scheduler, on queueing a task, unpark
+            // this thread.
+            Steal::Empty => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::task::Task;
+
+    #[test]
+    fn thread_waker_starts_notified() {
+        let waker = ThreadWaker::new();
+        assert!(waker.take_notification());
+        assert!(!waker.take_notification());
+    }
+
+    #[test]
+    fn thread_waker_wake_sets_notification() {
+        let thread_waker = ThreadWaker::new();
+        thread_waker.take_notification();
+
+        Waker::from(thread_waker.clone()).wake();
+
+        assert!(thread_waker.take_notification());
+    }
+
+    #[test]
+    fn block_on_returns_ready_output() {
+        let scheduler = Scheduler::new();
+        assert_eq!(block_on(&scheduler, async { 7 }), 7);
+    }
+
+    #[test]
+    fn block_on_runs_tasks_queued_from_other_threads() {
+        let scheduler = Arc::new(Scheduler::new());
+        let (sender, receiver) = crate::sync::oneshot::channel();
+        let submitter = {
+            let scheduler = scheduler.clone();
+            thread::spawn(move || {
+                thread::sleep(std::time::Duration::from_millis(20));
+                let future = Box::pin(async move {
+                    sender.send(5).unwrap();
+                });
+                scheduler.submit(Arc::new(Task::new(future, scheduler.clone())));
+            })
+        };
+
+        assert_eq!(block_on(&scheduler, receiver).unwrap(), 5);
+        submitter.join().unwrap();
+    }
+
+    #[test]
+    fn block_on_deregisters_its_thread() {
+        let scheduler = Scheduler::new();
+        block_on(&scheduler, async {});
+        block_on(&scheduler, async {});
+
+        assert_eq!(scheduler.block_on_threads(), 0);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::join_handle::{JoinError, JoinHandle};
+
+const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Lazily grown pool of threads for running blocking closures outside the
+/// async workers. Idle threads exit after [`KEEP_ALIVE`].
+pub(crate) struct BlockingPool {
+    state: Mutex<PoolState>,
+    condvar: Condvar,
+    max_threads: usize,
+    thread_name: Option<String>,
+}
+
+#[derive(Default)]
+struct PoolState {
+    queue: VecDeque<Job>,
+    num_threads: usize,
+    idle_threads: usize,
+    is_shutdown: bool,
+}
+
+impl BlockingPool {
+    pub(crate) fn new(max_threads: usize, thread_name: Option<String>) -> Arc<Self> {
+        Arc::new(BlockingPool {
+            state: Mutex::new(PoolState::default()),
+            condvar: Condvar::new(),
+            max_threads: max_threads.max(1),
+            thread_name,
+        })
+    }
+
+    pub(crate) fn submit(self: &Arc<Self>, job: Job) {
+        let mut state = self.state.lock();
+        state.queue.push_back(job);
+
+        if state.idle_threads > 0 {
+            self.condvar.notify_one();
+        } else if state.num_threads < self.max_threads {
+            state.num_threads += 1;
+            self.spawn_thread();
+        }
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.state.lock().is_shutdown = true;
+        self.condvar.notify_all();
+    }
+
+    #[cfg(test)]
+    fn num_threads(&self) -> usize {
+        self.state.lock().num_threads
+    }
+
+    fn spawn_thread(self: &Arc<Self>) {
+        let mut builder = thread::Builder::new();
+        if let Some(name) = &self.thread_name {
+            builder = builder.name(name.clone());
+        }
+
+        let pool = self.clone();
+        builder
+            .spawn(move || pool.run_thread())
+            .expect("failed to spawn blocking thread");
+    }
+
+    fn run_thread(&self) {
+        let mut state = self.state.lock();
+
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                drop(state);
+                job();
+                state = self.state.lock();
+                continue;
+            }
+
+            if state.is_shutdown {
+                break;
+            }
+
+            state.idle_threads += 1;
+            let timed_out = self.condvar.wait_for(&mut state, KEEP_ALIVE).timed_out();
+            state.idle_threads -= 1;
+
+            if timed_out && state.queue.is_empty() {
+                break;
+            }
+        }
+
+        state.num_threads -= 1;
+    }
+}
+
+/// Submits blocking closures to a runtime's blocking thread pool, from
+/// async or synchronous code alike. Obtained from
+/// [`Runtime::blocking_handle`](super::Runtime::blocking_handle).
+///
+/// Jobs share the pool used by [`Spawner::spawn_blocking`], so they are
+/// bounded by [`RuntimeBuilder::max_blocking_threads`].
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+///
+/// let runtime = Runtime::new();
+/// let blocking = runtime.blocking_handle();
+///
+/// let handle = blocking.submit(|| std::fs::metadata(".").is_ok());
+/// assert!(handle.join().unwrap());
+/// ```
+///
+/// [`Spawner::spawn_blocking`]: super::Spawner::spawn_blocking
+/// [`RuntimeBuilder::max_blocking_threads`]: super::RuntimeBuilder::max_blocking_threads
+#[derive(Clone)]
+pub struct BlockingHandle {
+    pool: Arc<BlockingPool>,
+}
+
+impl BlockingHandle {
+    pub(super) fn new(pool: Arc<BlockingPool>) -> Self {
+        BlockingHandle { pool }
+    }
+
+    /// Runs `f` on the blocking pool. A panic in `f` is reported through the
+    /// returned handle as [`JoinError::Panicked`].
+    pub fn submit<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (handle, notifier) = JoinHandle::new();
+
+        self.pool.submit(Box::new(move || {
+            let result = catch_unwind(AssertUnwindSafe(f)).map_err(JoinError::panicked);
+            notifier.complete(result);
+        }));
+
+        handle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    #[test]
+    fn pool_starts_without_threads() {
+        let pool = BlockingPool::new(4, None);
+        assert_eq!(pool.num_threads(), 0);
+    }
+
+    #[test]
+    fn pool_runs_submitted_job() {
+        let pool = BlockingPool::new(4, None);
+        let (sender, receiver) = mpsc::channel();
+
+        pool.submit(Box::new(move || sender.send(42).unwrap()));
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(42));
+    }
+
+    #[test]
+    fn pool_never_exceeds_max_threads() {
+        let pool = BlockingPool::new(2, None);
+        let completed = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..6 {
+            let completed = completed.clone();
+            let sender = sender.clone();
+            pool.submit(Box::new(move || {
+                thread::sleep(Duration::from_millis(10));
+                completed.fetch_add(1, Ordering::SeqCst);
+                sender.send(()).unwrap();
+            }));
+        }
+
+        assert!(pool.num_threads() <= 2);
+        for _ in 0..6 {
+            receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        }
+        assert_eq!(completed.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn blocking_handle_reports_panics() {
+        let handle = BlockingHandle::new(BlockingPool::new(1, None));
+
+        let result = handle.submit(|| panic!("disk on fire")).join();
+
+        assert_eq!(result.unwrap_err().panic_message(), Some("disk on fire"));
+    }
+
+    #[test]
+    fn pool_threads_exit_after_shutdown() {
+        let pool = BlockingPool::new(2, None);
+        let (sender, receiver) = mpsc::channel();
+
+        pool.submit(Box::new(move || sender.send(()).unwrap()));
+        receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        pool.shutdown();
+
+        while pool.num_threads() > 0 {
+            thread::yield_now();
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::observer::{DefaultObserver, RuntimeObserver};
+use super::runtime::Runtime;
+use crate::timer::TestClock;
+
+const DEFAULT_WORKER_THREADS: usize = 4;
+const DEFAULT_MAX_BLOCKING_THREADS: usize = 64;
+const DEFAULT_TASK_POLL_BUDGET: usize = 128;
+const DEFAULT_GLOBAL_QUEUE_INTERVAL: usize = 61;
+const DEFAULT_SLOW_POLL_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// What a worker does when it finds no task to run, set with
+/// [`RuntimeBuilder::idle_strategy`].
+///
+/// Staying awake picks up new work sooner but burns CPU while the runtime is
+/// idle; parking saves CPU and power at the cost of a wakeup when work
+/// arrives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleStrategy {
+    /// Busy-loop on the CPU. Lowest latency, but keeps every idle worker at
+    /// full load.
+    Spin,
+    /// Give up the time slice to other threads and check again. Cheaper than
+    /// `Spin` when threads compete for cores, but still never sleeps.
+    Yield,
+    /// Sleep until work is queued. Uses no CPU while idle; every new task
+    /// pays for waking a worker.
+    Park,
+    /// Back off for `spins` rounds, spinning at first and then yielding,
+    /// before parking. Absorbs short gaps between tasks without sleeping.
+    SpinThenPark { spins: u32 },
+}
+
+impl Default for IdleStrategy {
+    fn default() -> Self {
+        IdleStrategy::SpinThenPark { spins: 16 }
+    }
+}
+
+pub(super) struct RuntimeConfig {
+    pub(super) worker_threads: usize,
+    pub(super) thread_name_prefix: Option<String>,
+    pub(super) thread_stack_size: Option<usize>,
+    pub(super) max_blocking_threads: usize,
+    pub(super) task_poll_budget: usize,
+    pub(super) global_queue_interval: usize,
+    pub(super) global_queue_capacity: Option<usize>,
+    pub(super) observer: Arc<dyn RuntimeObserver>,
+    pub(super) slow_poll_threshold: Duration,
+    pub(super) core_affinity: bool,
+    pub(super) idle_strategy: IdleStrategy,
+    pub(super) dedicated_reactor: bool,
+    pub(super) test_clock: Option<TestClock>,
+}
+
+impl RuntimeConfig {
+    pub(super) fn worker_thread_builder(&self, worker_id: usize) -> thread::Builder {
+        let mut builder = thread::Builder::new();
+        if let Some(prefix) = &self.thread_name_prefix {
+            builder = builder.name(format!("{}-{}", prefix, worker_id));
+        }
+        if let Some(stack_size) = self.thread_stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        builder
+    }
+
+    pub(super) fn blocking_thread_name(&self) -> Option<String> {
+        self.thread_name_prefix
+            .as_ref()
+            .map(|prefix| format!("{}-blocking", prefix))
+    }
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        RuntimeConfig {
+            worker_threads: thread::available_parallelism()
+                .map(usize::from)
+                .unwrap_or(DEFAULT_WORKER_THREADS),
+            thread_name_prefix: None,
+            thread_stack_size: None,
+            max_blocking_threads: DEFAULT_MAX_BLOCKING_THREADS,
+            task_poll_budget: DEFAULT_TASK_POLL_BUDGET,
+            global_queue_interval: DEFAULT_GLOBAL_QUEUE_INTERVAL,
+            global_queue_capacity: None,
+            observer: Arc::new(DefaultObserver),
+            slow_poll_threshold: DEFAULT_SLOW_POLL_THRESHOLD,
+            core_affinity: false,
+            idle_strategy: IdleStrategy::default(),
+            dedicated_reactor: false,
+            test_clock: None,
+        }
+    }
+}
+
+/// Configures and builds a [`Runtime`].
+///
+/// # Example
+///
+/// ```
+/// use runtime::RuntimeBuilder;
+///
+/// let runtime = RuntimeBuilder::new()
+///     .worker_threads(2)
+///     .thread_name_prefix("app-worker")
+///     .thread_stack_size(4 * 1024 * 1024)
+///     .build();
+///
+/// let handle = runtime.start();
+/// runtime.shutdown();
+/// handle.wait();
+/// ```
+#[derive(Default)]
+pub struct RuntimeBuilder {
+    config: RuntimeConfig,
+}
+
+impl RuntimeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of workers spawned by [`Runtime::start`]. Defaults to the
+    /// available parallelism of the machine.
+    pub fn worker_threads(mut self, worker_threads: usize) -> Self {
+        self.config.worker_threads = worker_threads;
+        self
+    }
+
+    /// Worker threads are named `<prefix>-<worker id>`. Unnamed by default.
+    pub fn thread_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config.thread_name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Stack size in bytes for worker threads. Uses the platform default
+    /// when unset.
+    pub fn thread_stack_size(mut self, stack_size: usize) -> Self {
+        self.config.thread_stack_size = Some(stack_size);
+        self
+    }
+
+    /// Upper bound on threads used by [`Spawner::spawn_blocking`]. Threads
+    /// are only started on demand.
+    ///
+    /// [`Spawner::spawn_blocking`]: super::Spawner::spawn_blocking
+    pub fn max_blocking_threads(mut self, max_blocking_threads: usize) -> Self {
+        self.config.max_blocking_threads = max_blocking_threads;
+        self
+    }
+
+    /// Number of consecutive polls a worker performs before it looks at the
+    /// global queue and its siblings ahead of its own local queue. Keeps tasks
+    /// that keep waking each other on one worker from starving everything
+    /// else. Defaults to 128.
+    ///
+    /// Tasks that wake themselves while running, as [`yield_now`] does, are
+    /// always sent to the back of the global queue regardless of the budget.
+    ///
+    /// [`yield_now`]: super::yield_now
+    pub fn task_poll_budget(mut self, task_poll_budget: usize) -> Self {
+        self.config.task_poll_budget = task_poll_budget.max(1);
+        self
+    }
+
+    /// Number of tasks a worker takes from its local queue before it checks
+    /// the global queue first, so tasks spawned from outside still run while
+    /// tasks on one worker keep waking each other. Defaults to 61.
+    ///
+    /// Unlike [`task_poll_budget`](Self::task_poll_budget), this only counts
+    /// local pops and only looks at the global queue, not at siblings, so it
+    /// can be kept small cheaply.
+    pub fn global_queue_interval(mut self, global_queue_interval: usize) -> Self {
+        self.config.global_queue_interval = global_queue_interval.max(1);
+        self
+    }
+
+    /// Maximum number of spawned tasks waiting for their first poll before
+    /// [`Spawner::try_spawn`] starts rejecting new ones. Unbounded by default;
+    /// [`Spawner::spawn`] ignores the limit, though its tasks still count
+    /// towards it.
+    ///
+    /// [`Spawner::try_spawn`]: super::Spawner::try_spawn
+    /// [`Spawner::spawn`]: super::Spawner::spawn
+    pub fn global_queue_capacity(mut self, capacity: usize) -> Self {
+        self.config.global_queue_capacity = Some(capacity);
+        self
+    }
+
+    /// Receives worker and task events. Defaults to an observer that ignores
+    /// them; install a [`StderrObserver`](super::StderrObserver) to have
+    /// warnings printed.
+    pub fn observer(mut self, observer: Arc<dyn RuntimeObserver>) -> Self {
+        self.config.observer = observer;
+        self
+    }
+
+    /// Polls taking longer than `threshold` are reported through
+    /// [`RuntimeObserver::on_slow_poll`]. Defaults to 100ms.
+    pub fn slow_poll_threshold(mut self, threshold: Duration) -> Self {
+        self.config.slow_poll_threshold = threshold;
+        self
+    }
+
+    /// Pins each worker started by [`Runtime::run`] to its own CPU core,
+    /// wrapping around when there are more workers than cores. Off by
+    /// default.
+    ///
+    /// Only supported on Linux; elsewhere, or if pinning fails, the workers
+    /// run unpinned and the failure is reported through
+    /// [`RuntimeObserver::on_core_affinity_failed`].
+    pub fn core_affinity(mut self, enabled: bool) -> Self {
+        self.config.core_affinity = enabled;
+        self
+    }
+
+    /// How workers wait for work when they have none. Defaults to
+    /// [`IdleStrategy::SpinThenPark`] with 16 rounds.
+    pub fn idle_strategy(mut self, strategy: IdleStrategy) -> Self {
+        self.config.idle_strategy = strategy;
+        self
+    }
+
+    /// Gives the runtime its own timer reactor and thread instead of the
+    /// process-wide one, so its timers are isolated from other runtimes.
+    /// The reactor stops when the runtime is dropped; timers still pending
+    /// then move to the process-wide reactor. It takes the settings from
+    /// [`set_timer_granularity`] and [`set_max_wakes_per_tick`]. Off by
+    /// default.
+    ///
+    /// Timers use the runtime's reactor when created on its workers or
+    /// inside [`Runtime::block_on`]; timers created on other threads keep
+    /// using the shared reactor.
+    ///
+    /// [`set_timer_granularity`]: crate::timer::set_timer_granularity
+    /// [`set_max_wakes_per_tick`]: crate::timer::set_max_wakes_per_tick
+    pub fn dedicated_reactor(mut self, enabled: bool) -> Self {
+        self.config.dedicated_reactor = enabled;
+        self
+    }
+
+    /// Runs every timer of the runtime on `clock`: timers created on its
+    /// workers, inside [`Runtime::block_on`] and through its spawner's tasks
+    /// only fire as the clock is [advanced](TestClock::advance). Takes
+    /// precedence over [`dedicated_reactor`](Self::dedicated_reactor).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::timer::TestClock;
+    /// use runtime::{RuntimeBuilder, sleep};
+    /// use std::time::Duration;
+    ///
+    /// let clock = TestClock::new();
+    /// let runtime = RuntimeBuilder::new().test_clock(&clock).build();
+    /// let workers = runtime.run(2);
+    ///
+    /// let sleeper = runtime
+    ///     .spawner()
+    ///     .spawn(async { sleep(Duration::from_secs(3600)).await })
+    ///     .unwrap();
+    /// while !sleeper.is_finished() {
+    ///     clock.advance(Duration::from_secs(3600));
+    /// }
+    ///
+    /// runtime.shutdown();
+    /// workers.wait();
+    /// ```
+    pub fn test_clock(mut self, clock: &TestClock) -> Self {
+        self.config.test_clock = Some(clock.clone());
+        self
+    }
+
+    pub fn build(self) -> Runtime {
+        Runtime::from_config(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_defaults_match_plain_threads() {
+        let config = RuntimeConfig::default();
+
+        assert!(config.worker_threads > 0);
+        assert!(config.thread_name_prefix.is_none());
+        assert!(config.thread_stack_size.is_none());
+        assert!(!config.core_affinity);
+        assert!(!config.dedicated_reactor);
+        assert!(config.test_clock.is_none());
+        assert_eq!(config.global_queue_interval, 61);
+    }
+
+    #[test]
+    fn builder_stores_options() {
+        let clock = TestClock::new();
+        let builder = RuntimeBuilder::new()
+            .worker_threads(3)
+            .thread_name_prefix("test")
+            .thread_stack_size(1024 * 1024)
+            .max_blocking_threads(8)
+            .task_poll_budget(16)
+            .global_queue_interval(8)
+            .global_queue_capacity(32)
+            .slow_poll_threshold(Duration::from_millis(5))
+            .core_affinity(true)
+            .idle_strategy(IdleStrategy::Park)
+            .dedicated_reactor(true)
+            .test_clock(&clock);
+
+        assert_eq!(builder.config.worker_threads, 3);
+        assert_eq!(builder.config.thread_name_prefix.as_deref(), Some("test"));
+        assert_eq!(builder.config.thread_stack_size, Some(1024 * 1024));
+        assert_eq!(builder.config.max_blocking_threads, 8);
+        assert_eq!(builder.config.task_poll_budget, 16);
+        assert_eq!(builder.config.global_queue_interval, 8);
+        assert_eq!(builder.config.global_queue_capacity, Some(32));
+        assert_eq!(builder.config.slow_poll_threshold, Duration::from_millis(5));
+        assert!(builder.config.core_affinity);
+        assert_eq!(builder.config.idle_strategy, IdleStrategy::Park);
+        assert!(builder.config.dedicated_reactor);
+        assert!(builder.config.test_clock.is_some());
+    }
+
+    #[test]
+    fn worker_threads_are_named_with_prefix() {
+        let config = RuntimeConfig {
+            thread_name_prefix: Some("pool".to_string()),
+            ..RuntimeConfig::default()
+        };
+
+        let name = config
+            .worker_thread_builder(7)
+            .spawn(|| thread::current().name().map(str::to_string))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(name.as_deref(), Some("pool-7"));
+    }
+}
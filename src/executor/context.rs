@@ -0,0 +1,143 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::spawner::Spawner;
+use crate::cancellation::{CancellationToken, DropGuard};
+
+thread_local! {
+    static CURRENT_SPAWNER: RefCell<Option<Spawner>> = const { RefCell::new(None) };
+    /// Token of the scoped task being polled on this thread, if any.
+    static CURRENT_TOKEN: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// Restores the previously current spawner when dropped.
+pub(super) struct EnterGuard {
+    previous: Option<Spawner>,
+}
+
+impl Drop for EnterGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        let _ = CURRENT_SPAWNER.try_with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Makes `spawner` the current spawner of this thread until the guard drops.
+pub(super) fn enter(spawner: Spawner) -> EnterGuard {
+    let previous = CURRENT_SPAWNER.with(|current| current.borrow_mut().replace(spawner));
+    EnterGuard { previous }
+}
+
+pub(super) fn current_spawner() -> Option<Spawner> {
+    CURRENT_SPAWNER
+        .try_with(|current| current.borrow().clone())
+        .ok()
+        .flatten()
+}
+
+pub(super) fn current_cancellation_token() -> Option<CancellationToken> {
+    CURRENT_TOKEN
+        .try_with(|current| current.borrow().clone())
+        .ok()
+        .flatten()
+}
+
+/// Runs `future` with `token` as the current cancellation token, so scoped
+/// tasks it spawns become children of the token. The token is cancelled if
+/// the future is dropped before completing, e.g. when its task is aborted.
+pub(super) fn scope_cancellation<F: Future>(token: CancellationToken, future: F) -> Scoped<F> {
+    Scoped {
+        token: token.clone(),
+        guard: Some(token.drop_guard()),
+        future: Box::pin(future),
+    }
+}
+
+/// Future returned by [`scope_cancellation`].
+pub(super) struct Scoped<F> {
+    token: CancellationToken,
+    /// Disarmed once the future completes.
+    guard: Option<DropGuard>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let previous = CURRENT_TOKEN.with(|current| current.replace(Some(self.token.clone())));
+        let _restore = RestoreToken { previous };
+
+        let poll = self.future.as_mut().poll(cx);
+        if poll.is_ready()
+            && let Some(guard) = self.guard.take()
+        {
+            guard.disarm();
+        }
+        poll
+    }
+}
+
+/// Restores the previously current token when dropped, even if the poll
+/// panics.
+struct RestoreToken {
+    previous: Option<CancellationToken>,
+}
+
+impl Drop for RestoreToken {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        let _ = CURRENT_TOKEN.try_with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Runtime;
+
+    #[test]
+    fn no_spawner_outside_runtime() {
+        assert!(current_spawner().is_none());
+    }
+
+    #[test]
+    fn enter_sets_and_restores_spawner() {
+        let runtime = Runtime::new();
+
+        {
+            let _guard = enter(runtime.spawner());
+            assert!(current_spawner().is_some());
+        }
+
+        assert!(current_spawner().is_none());
+    }
+
+    #[test]
+    fn scope_sets_token_only_while_polling() {
+        let runtime = Runtime::new();
+        let token = CancellationToken::new();
+
+        let seen = runtime.block_on(scope_cancellation(token.clone(), async {
+            current_cancellation_token()
+        }));
+
+        assert!(seen.is_some());
+        assert!(current_cancellation_token().is_none());
+        assert!(!token.is_cancelled());
+    }
+
+    #[test]
+    fn dropping_unfinished_scope_cancels_token() {
+        let token = CancellationToken::new();
+
+        drop(scope_cancellation(
+            token.clone(),
+            std::future::pending::<()>(),
+        ));
+
+        assert!(token.is_cancelled());
+    }
+}
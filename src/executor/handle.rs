@@ -1,12 +1,53 @@
+use parking_lot::{Condvar, Mutex};
+use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
+
+use super::metrics::WorkerStats;
+
+/// Counts worker threads that have exited, so [`RuntimeHandle::wait_timeout`]
+/// can wait on them with a deadline. `JoinHandle` has no timed join.
+#[derive(Default)]
+pub(super) struct WorkerExits {
+    exited: Mutex<usize>,
+    condvar: Condvar,
+}
+
+impl WorkerExits {
+    /// Returns a guard that records the exit of the worker holding it, even
+    /// if the worker unwinds.
+    pub(super) fn guard(self: &Arc<Self>) -> WorkerExitGuard {
+        WorkerExitGuard {
+            exits: self.clone(),
+        }
+    }
+}
+
+pub(super) struct WorkerExitGuard {
+    exits: Arc<WorkerExits>,
+}
+
+impl Drop for WorkerExitGuard {
+    fn drop(&mut self) {
+        *self.exits.exited.lock() += 1;
+        self.exits.condvar.notify_all();
+    }
+}
 
 pub struct RuntimeHandle {
-    worker_handles: Vec<thread::JoinHandle<()>>,
+    worker_handles: Vec<thread::JoinHandle<WorkerStats>>,
+    exits: Arc<WorkerExits>,
 }
 
 impl RuntimeHandle {
-    pub(super) fn new(worker_handles: Vec<thread::JoinHandle<()>>) -> Self {
-        RuntimeHandle { worker_handles }
+    pub(super) fn new(
+        worker_handles: Vec<thread::JoinHandle<WorkerStats>>,
+        exits: Arc<WorkerExits>,
+    ) -> Self {
+        RuntimeHandle {
+            worker_handles,
+            exits,
+        }
     }
 
     pub fn wait(self) {
@@ -14,24 +55,146 @@ impl RuntimeHandle {
             let _ = handle.join();
         }
     }
+
+    /// Like [`wait`](Self::wait), but returns what each worker did, ordered
+    /// by worker id.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.spawner().spawn(async {}).unwrap();
+    ///
+    /// let workers = runtime.run(2);
+    /// handle.join().unwrap();
+    /// runtime.shutdown();
+    ///
+    /// let stats = workers.wait_with_stats();
+    /// assert_eq!(stats.iter().map(|worker| worker.tasks_executed).sum::<u64>(), 1);
+    /// ```
+    pub fn wait_with_stats(self) -> Vec<WorkerStats> {
+        self.worker_handles
+            .into_iter()
+            .enumerate()
+            .map(|(worker_id, handle)| {
+                handle.join().unwrap_or(WorkerStats {
+                    worker_id,
+                    ..WorkerStats::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Like [`wait`](Self::wait), but gives up after `timeout`. On timeout
+    /// the handle is returned with every worker still unjoined, so the
+    /// caller can wait again or drop it to detach the workers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    /// use std::time::Duration;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.run(2);
+    /// runtime.shutdown();
+    ///
+    /// assert!(handle.wait_timeout(Duration::from_secs(5)).is_ok());
+    /// ```
+    pub fn wait_timeout(self, timeout: Duration) -> Result<(), RuntimeHandle> {
+        let deadline = Instant::now() + timeout;
+        let mut exited = self.exits.exited.lock();
+        while *exited < self.worker_handles.len() {
+            if self
+                .exits
+                .condvar
+                .wait_until(&mut exited, deadline)
+                .timed_out()
+            {
+                drop(exited);
+                return Err(self);
+            }
+        }
+        drop(exited);
+
+        self.wait();
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn spawn_workers(
+        count: usize,
+        exits: &Arc<WorkerExits>,
+    ) -> Vec<thread::JoinHandle<WorkerStats>> {
+        (0..count)
+            .map(|_| {
+                let guard = exits.guard();
+                thread::spawn(move || {
+                    drop(guard);
+                    WorkerStats::default()
+                })
+            })
+            .collect()
+    }
+
     #[test]
     fn runtime_handle_new_creates_with_handles() {
         let handles = vec![];
-        let runtime_handle = RuntimeHandle::new(handles);
+        let runtime_handle = RuntimeHandle::new(handles, Arc::default());
         runtime_handle.wait();
     }
 
     #[test]
     fn runtime_handle_wait_joins_all_threads() {
-        let handles: Vec<thread::JoinHandle<()>> = (0..3).map(|_| thread::spawn(|| {})).collect();
+        let exits = Arc::default();
+        let handles = spawn_workers(3, &exits);
 
-        let runtime_handle = RuntimeHandle::new(handles);
+        let runtime_handle = RuntimeHandle::new(handles, exits);
         runtime_handle.wait();
     }
+
+    #[test]
+    fn wait_with_stats_returns_one_entry_per_worker() {
+        let exits = Arc::default();
+        let handles = spawn_workers(3, &exits);
+
+        let stats = RuntimeHandle::new(handles, exits).wait_with_stats();
+
+        assert_eq!(stats.len(), 3);
+    }
+
+    #[test]
+    fn wait_timeout_joins_exited_workers() {
+        let exits = Arc::default();
+        let handles = spawn_workers(3, &exits);
+
+        let runtime_handle = RuntimeHandle::new(handles, exits);
+        assert!(runtime_handle.wait_timeout(Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn wait_timeout_returns_handle_for_stuck_worker() {
+        let exits = Arc::new(WorkerExits::default());
+        let (release, stuck) = std::sync::mpsc::channel::<()>();
+        let guard = exits.guard();
+        let handles = vec![thread::spawn(move || {
+            let _ = stuck.recv();
+            drop(guard);
+            WorkerStats::default()
+        })];
+
+        let runtime_handle = RuntimeHandle::new(handles, exits);
+        let runtime_handle = runtime_handle
+            .wait_timeout(Duration::from_millis(20))
+            .expect_err("stuck worker should time out");
+
+        release.send(()).unwrap();
+        assert!(runtime_handle.wait_timeout(Duration::from_secs(5)).is_ok());
+    }
 }
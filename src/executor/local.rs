@@ -0,0 +1,241 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::pin::{Pin, pin};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use parking_lot::Mutex;
+
+use super::block_on::ThreadWaker;
+use crate::join_handle::{JoinError, JoinHandle};
+use crate::timer::ReactorRef;
+
+type LocalBoxFuture = Pin<Box<dyn Future<Output = ()> + 'static>>;
+
+struct LocalTask {
+    future: LocalBoxFuture,
+    on_failure: Box<dyn Fn(JoinError)>,
+}
+
+/// Ids of woken tasks, shared with wakers that may fire on other threads.
+struct ReadyQueue {
+    queue: Mutex<VecDeque<usize>>,
+    thread: Thread,
+}
+
+impl ReadyQueue {
+    fn push(&self, task_id: usize) {
+        self.queue.lock().push_back(task_id);
+        self.thread.unpark();
+    }
+
+    fn pop(&self) -> Option<usize> {
+        self.queue.lock().pop_front()
+    }
+}
+
+struct LocalWaker {
+    task_id: usize,
+    ready_queue: Arc<ReadyQueue>,
+}
+
+impl Wake for LocalWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready_queue.push(self.task_id);
+    }
+}
+
+struct LocalShared {
+    tasks: RefCell<HashMap<usize, LocalTask>>,
+    next_task_id: Cell<usize>,
+    ready_queue: Arc<ReadyQueue>,
+    _reactor: ReactorRef,
+}
+
+/// Single-threaded runtime for futures that are not `Send`.
+///
+/// Every task runs on the thread that created the runtime, inside
+/// [`LocalRuntime::block_on`]. The type is `!Send`, so `spawn_local` can't be
+/// called from another thread; clones share the same task set and can be
+/// moved into tasks to spawn more work.
+///
+/// # Example
+///
+/// ```
+/// use runtime::LocalRuntime;
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+///
+/// let runtime = LocalRuntime::new();
+/// let counter = Rc::new(Cell::new(0));
+///
+/// let counter_clone = counter.clone();
+/// let handle = runtime.spawn_local(async move {
+///     counter_clone.set(counter_clone.get() + 1);
+/// });
+///
+/// runtime.block_on(handle).unwrap();
+/// assert_eq!(counter.get(), 1);
+/// ```
+#[derive(Clone)]
+pub struct LocalRuntime {
+    shared: Rc<LocalShared>,
+}
+
+impl Default for LocalRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalRuntime {
+    pub fn new() -> Self {
+        LocalRuntime {
+            shared: Rc::new(LocalShared {
+                tasks: RefCell::new(HashMap::new()),
+                next_task_id: Cell::new(0),
+                ready_queue: Arc::new(ReadyQueue {
+                    queue: Mutex::new(VecDeque::new()),
+                    thread: thread::current(),
+                }),
+                _reactor: ReactorRef::acquire(),
+            }),
+        }
+    }
+
+    /// Spawns a task that runs on this runtime's thread. Local tasks can't be
+    /// aborted; [`JoinHandle::abort`] has no effect on them.
+    pub fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let (handle, notifier) = JoinHandle::new();
+        let failure_notifier = notifier.clone();
+
+        let task = LocalTask {
+            future: Box::pin(async move {
+                let result = future.await;
+                notifier.complete(Ok(result));
+            }),
+            on_failure: Box::new(move |error| failure_notifier.complete(Err(error))),
+        };
+
+        let task_id = self.shared.next_task_id.get();
+        self.shared.next_task_id.set(task_id + 1);
+        self.shared.tasks.borrow_mut().insert(task_id, task);
+        self.shared.ready_queue.push(task_id);
+
+        handle
+    }
+
+    /// Runs local tasks on the current thread until `future` resolves.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = pin!(future);
+        let thread_waker = ThreadWaker::new();
+        let waker = Waker::from(thread_waker.clone());
+        let mut context = Context::from_waker(&waker);
+
+        loop {
+            if thread_waker.take_notification()
+                && let Poll::Ready(output) = future.as_mut().poll(&mut context)
+            {
+                return output;
+            }
+
+            if !self.run_ready_task() {
+                thread::park();
+            }
+        }
+    }
+
+    fn run_ready_task(&self) -> bool {
+        let Some(task_id) = self.shared.ready_queue.pop() else {
+            return false;
+        };
+
+        // Woken again after completing, or woken twice before running.
+        let Some(mut task) = self.shared.tasks.borrow_mut().remove(&task_id) else {
+            return true;
+        };
+
+        let waker = Waker::from(Arc::new(LocalWaker {
+            task_id,
+            ready_queue: self.shared.ready_queue.clone(),
+        }));
+        let mut context = Context::from_waker(&waker);
+
+        match catch_unwind(AssertUnwindSafe(|| task.future.as_mut().poll(&mut context))) {
+            Ok(Poll::Pending) => {
+                self.shared.tasks.borrow_mut().insert(task_id, task);
+            }
+            Ok(Poll::Ready(())) => {}
+            Err(payload) => (task.on_failure)(JoinError::panicked(payload)),
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_on_without_tasks_returns_output() {
+        let runtime = LocalRuntime::new();
+        assert_eq!(runtime.block_on(async { 3 }), 3);
+    }
+
+    #[test]
+    fn spawn_local_runs_non_send_future() {
+        let runtime = LocalRuntime::new();
+        let value = Rc::new(Cell::new(0_u32));
+
+        let value_clone = value.clone();
+        let handle = runtime.spawn_local(async move {
+            value_clone.set(value_clone.get() + 5);
+            value_clone.get()
+        });
+
+        assert_eq!(runtime.block_on(handle).unwrap(), 5);
+        assert_eq!(value.get(), 5);
+    }
+
+    #[test]
+    fn spawn_local_from_inside_task() {
+        let runtime = LocalRuntime::new();
+        let value = Rc::new(Cell::new(0_u32));
+
+        let runtime_clone = runtime.clone();
+        let value_clone = value.clone();
+        let handle = runtime.spawn_local(async move {
+            let inner = runtime_clone.spawn_local(async move {
+                value_clone.set(7);
+            });
+            inner.await.unwrap();
+        });
+
+        runtime.block_on(handle).unwrap();
+        assert_eq!(value.get(), 7);
+    }
+
+    #[test]
+    fn panicking_local_task_reports_panicked() {
+        let runtime = LocalRuntime::new();
+        let handle = runtime.spawn_local(async {
+            panic!("local boom");
+        });
+
+        let result: Result<(), JoinError> = runtime.block_on(handle);
+        assert!(matches!(result, Err(JoinError::Panicked { .. })));
+    }
+}
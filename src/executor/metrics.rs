@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Point-in-time snapshot of runtime counters, returned by
+/// [`Runtime::metrics`](super::Runtime::metrics).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeMetrics {
+    pub tasks_spawned: u64,
+    /// Tasks whose future ran to completion. Panicked and aborted tasks are
+    /// not included.
+    pub tasks_completed: u64,
+    pub tasks_panicked: u64,
+    /// Worker threads currently running their loop, parked or not.
+    pub active_workers: usize,
+    pub global_queue_depth: usize,
+}
+
+/// Work done by a single worker over its lifetime, returned by
+/// [`RuntimeHandle::wait_with_stats`](super::RuntimeHandle::wait_with_stats).
+///
+/// Unlike [`RuntimeMetrics`], these are per worker, which helps spot workers
+/// that did far more or less than their share.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    pub worker_id: usize,
+    /// Times the worker picked up a task and polled it.
+    pub tasks_executed: u64,
+    /// Attempts to steal from sibling workers that took at least one task.
+    pub steals_succeeded: u64,
+    /// Attempts to steal from sibling workers that found them all empty.
+    pub steals_failed: u64,
+    /// Times the worker ran out of work and went to sleep.
+    pub times_parked: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct MetricsCounters {
+    tasks_spawned: AtomicU64,
+    tasks_completed: AtomicU64,
+    tasks_panicked: AtomicU64,
+    active_workers: AtomicUsize,
+}
+
+impl MetricsCounters {
+    pub(crate) fn task_spawned(&self) {
+        self.tasks_spawned.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn task_completed(&self) {
+        self.tasks_completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn task_panicked(&self) {
+        self.tasks_panicked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn worker_started(&self) {
+        self.active_workers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn worker_stopped(&self) {
+        self.active_workers.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self, global_queue_depth: usize) -> RuntimeMetrics {
+        RuntimeMetrics {
+            tasks_spawned: self.tasks_spawned.load(Ordering::Relaxed),
+            tasks_completed: self.tasks_completed.load(Ordering::Relaxed),
+            tasks_panicked: self.tasks_panicked.load(Ordering::Relaxed),
+            active_workers: self.active_workers.load(Ordering::Relaxed),
+            global_queue_depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_start_at_zero() {
+        let counters = MetricsCounters::default();
+        assert_eq!(counters.snapshot(0), RuntimeMetrics::default());
+    }
+
+    #[test]
+    fn snapshot_reflects_counters() {
+        let counters = MetricsCounters::default();
+
+        counters.task_spawned();
+        counters.task_spawned();
+        counters.task_completed();
+        counters.task_panicked();
+        counters.worker_started();
+        counters.worker_started();
+        counters.worker_stopped();
+
+        let metrics = counters.snapshot(3);
+
+        assert_eq!(metrics.tasks_spawned, 2);
+        assert_eq!(metrics.tasks_completed, 1);
+        assert_eq!(metrics.tasks_panicked, 1);
+        assert_eq!(metrics.active_workers, 1);
+        assert_eq!(metrics.global_queue_depth, 3);
+    }
+}
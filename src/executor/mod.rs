@@ -1,9 +1,32 @@
+mod affinity;
+mod block_on;
+mod blocking;
+mod builder;
+mod context;
 mod handle;
+mod local;
+mod metrics;
+mod observer;
+mod ordered;
 mod runtime;
+mod scheduler;
+mod scope;
 mod spawner;
 mod task;
 mod worker;
+mod yield_now;
 
+pub use blocking::BlockingHandle;
+pub use builder::{IdleStrategy, RuntimeBuilder};
 pub use handle::RuntimeHandle;
+pub use local::LocalRuntime;
+pub use metrics::{RuntimeMetrics, WorkerStats};
+pub use observer::{RuntimeObserver, StderrObserver};
 pub use runtime::Runtime;
-pub use spawner::{SpawnError, Spawner};
+pub use scope::Scope;
+pub use spawner::{SpawnError, Spawner, WeakSpawner, is_in_runtime, spawn, try_current};
+pub use task::Priority;
+pub use yield_now::{YieldNow, yield_now};
+
+pub(crate) use task::Task;
+pub(crate) use worker::is_worker_thread;
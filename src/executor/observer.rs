@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+/// Hooks for runtime events, installed with [`RuntimeBuilder::observer`].
+///
+/// Every method defaults to doing nothing, so implementors only override the
+/// events they care about. Methods are called from threads executing
+/// runtime tasks (workers and `block_on` callers) and must not block.
+///
+/// # Example
+///
+/// ```
+/// use runtime::{RuntimeBuilder, RuntimeObserver};
+/// use std::sync::Arc;
+///
+/// struct PanicLogger;
+///
+/// impl RuntimeObserver for PanicLogger {
+///     fn on_task_panicked(&self, task_name: &str) {
+///         eprintln!("task '{}' panicked", task_name);
+///     }
+/// }
+///
+/// let runtime = RuntimeBuilder::new().observer(Arc::new(PanicLogger)).build();
+/// ```
+///
+/// [`RuntimeBuilder::observer`]: super::RuntimeBuilder::observer
+pub trait RuntimeObserver: Send + Sync {
+    fn on_worker_started(&self, _worker_id: usize) {}
+
+    fn on_worker_stopped(&self, _worker_id: usize) {}
+
+    /// `task_name` is the name given to [`Spawner::spawn_named`], or the
+    /// task's numeric id.
+    ///
+    /// [`Spawner::spawn_named`]: super::Spawner::spawn_named
+    fn on_task_panicked(&self, _task_name: &str) {}
+
+    /// A single poll of task `task_name` held its thread for `elapsed`,
+    /// longer than the configured [`slow_poll_threshold`]. Usually a sign of
+    /// blocking code that belongs in [`spawn_blocking`].
+    ///
+    /// [`slow_poll_threshold`]: super::RuntimeBuilder::slow_poll_threshold
+    /// [`spawn_blocking`]: super::Spawner::spawn_blocking
+    fn on_slow_poll(&self, _task_name: &str, _elapsed: Duration) {}
+
+    /// Every worker was parked while `queued_tasks` tasks were waiting in
+    /// the runtime's queues, meaning a wakeup was lost. The runtime recovers
+    /// by waking a worker; this hook exists to surface the bug.
+    fn on_stall_detected(&self, _queued_tasks: usize) {}
+
+    /// Worker `worker_id` could not be pinned to `core` as requested by
+    /// [`core_affinity`], and runs unpinned. `core` is `None` when the
+    /// platform doesn't support affinity at all.
+    ///
+    /// [`core_affinity`]: super::RuntimeBuilder::core_affinity
+    fn on_core_affinity_failed(&self, _worker_id: usize, _core: Option<usize>) {}
+}
+
+/// Observer used when none is configured. Ignores every event.
+pub(super) struct DefaultObserver;
+
+impl RuntimeObserver for DefaultObserver {}
+
+/// Observer that reports suspicious runtime events as warnings on stderr.
+/// Nothing is printed unless it is installed.
+///
+/// # Example
+///
+/// ```
+/// use runtime::{RuntimeBuilder, StderrObserver};
+/// use std::sync::Arc;
+///
+/// let runtime = RuntimeBuilder::new()
+///     .observer(Arc::new(StderrObserver))
+///     .build();
+/// ```
+pub struct StderrObserver;
+
+impl RuntimeObserver for StderrObserver {
+    fn on_slow_poll(&self, task_name: &str, elapsed: Duration) {
+        eprintln!(
+            "warning: task '{}' blocked its worker for {:?}; consider spawn_blocking",
+            task_name, elapsed
+        );
+    }
+
+    fn on_stall_detected(&self, queued_tasks: usize) {
+        eprintln!(
+            "warning: all workers parked with {} queued tasks; waking a worker",
+            queued_tasks
+        );
+    }
+
+    fn on_core_affinity_failed(&self, worker_id: usize, core: Option<usize>) {
+        match core {
+            Some(core) => eprintln!(
+                "warning: failed to pin worker {} to core {}",
+                worker_id, core
+            ),
+            None => eprintln!(
+                "warning: core affinity is not supported on this platform; worker {} is not pinned",
+                worker_id
+            ),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use parking_lot::Mutex;
+use std::collections::{BTreeSet, HashMap};
+use std::future::poll_fn;
+use std::sync::Arc;
+use std::task::{Poll, Waker};
+
+type Ticket = u64;
+
+/// Hands out tickets to tasks spawned with
+/// [`Spawner::spawn_ordered`](super::Spawner::spawn_ordered). A task holds
+/// its ticket until its first poll is done, so tasks start strictly in
+/// ticket order, whichever worker picks them up.
+#[derive(Default)]
+pub(super) struct StartOrder {
+    state: Mutex<StartOrderState>,
+}
+
+#[derive(Default)]
+struct StartOrderState {
+    next_ticket: Ticket,
+    next_to_start: Ticket,
+    /// Tickets dropped before their turn, e.g. because the task was aborted.
+    skipped: BTreeSet<Ticket>,
+    wakers: HashMap<Ticket, Waker>,
+}
+
+impl StartOrderState {
+    /// Passes the turn to the next ticket still waiting, waking it if it
+    /// has already been polled.
+    fn advance(&mut self) -> Option<Waker> {
+        self.next_to_start += 1;
+        while self.skipped.remove(&self.next_to_start) {
+            self.next_to_start += 1;
+        }
+        self.wakers.remove(&self.next_to_start)
+    }
+}
+
+impl StartOrder {
+    /// Takes the next ticket. Tickets must be taken in spawn order.
+    pub(super) fn ticket(self: &Arc<Self>) -> TurnGuard {
+        let mut state = self.state.lock();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+
+        TurnGuard {
+            order: self.clone(),
+            ticket,
+        }
+    }
+}
+
+/// A task's place in the start order. Dropping it passes the turn on, or
+/// gives it up if the turn hasn't come yet, as happens when the task is
+/// aborted while waiting.
+pub(super) struct TurnGuard {
+    order: Arc<StartOrder>,
+    ticket: Ticket,
+}
+
+impl TurnGuard {
+    /// Waits until every earlier ticket has been dropped.
+    pub(super) async fn wait_turn(&self) {
+        poll_fn(|cx| {
+            let mut state = self.order.state.lock();
+            if state.next_to_start == self.ticket {
+                return Poll::Ready(());
+            }
+
+            state.wakers.insert(self.ticket, cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        let mut state = self.order.state.lock();
+        state.wakers.remove(&self.ticket);
+        if state.next_to_start != self.ticket {
+            state.skipped.insert(self.ticket);
+            return;
+        }
+
+        let next = state.advance();
+        drop(state);
+        if let Some(waker) = next {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::Context;
+
+    fn poll_turn(turn: &TurnGuard) -> bool {
+        let waker = Waker::noop();
+        pin!(turn.wait_turn())
+            .poll(&mut Context::from_waker(waker))
+            .is_ready()
+    }
+
+    #[test]
+    fn turn_passes_when_previous_ticket_is_dropped() {
+        let order = Arc::new(StartOrder::default());
+        let first = order.ticket();
+        let second = order.ticket();
+
+        assert!(poll_turn(&first));
+        assert!(!poll_turn(&second));
+
+        drop(first);
+        assert!(poll_turn(&second));
+    }
+
+    #[test]
+    fn dropped_tickets_give_up_their_turn() {
+        let order = Arc::new(StartOrder::default());
+        let first = order.ticket();
+        let second = order.ticket();
+        let third = order.ticket();
+        let fourth = order.ticket();
+
+        drop(second);
+        drop(first);
+        drop(third);
+
+        assert!(poll_turn(&fourth));
+        assert!(order.state.lock().skipped.is_empty());
+    }
+}
@@ -1,12 +1,20 @@
-use crossbeam_deque::Injector;
+use crossbeam_deque::Worker;
+use std::future::Future;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 
-use super::handle::RuntimeHandle;
+use super::affinity;
+use super::block_on::block_on;
+use super::blocking::{BlockingHandle, BlockingPool};
+use super::builder::{RuntimeBuilder, RuntimeConfig};
+use super::context;
+use super::handle::{RuntimeHandle, WorkerExits};
+use super::metrics::{RuntimeMetrics, WorkerStats};
+use super::scheduler::Scheduler;
 use super::spawner::Spawner;
-use super::task::Task;
-use super::worker::run_worker_loop;
+use super::worker::{LocalQueue, TaskStealer, run_worker_loop};
+use crate::timer::ReactorRef;
 
 /// Async runtime with work-stealing executor.
 ///
@@ -29,8 +37,11 @@ use super::worker::run_worker_loop;
 /// handle.wait();
 /// ```
 pub struct Runtime {
-    global_queue: Arc<Injector<Arc<Task>>>,
-    is_shutdown: Arc<AtomicBool>,
+    scheduler: Arc<Scheduler>,
+    blocking_pool: Arc<BlockingPool>,
+    config: RuntimeConfig,
+    is_started: AtomicBool,
+    reactor: ReactorRef,
 }
 
 impl Default for Runtime {
@@ -41,36 +52,442 @@ impl Default for Runtime {
 
 impl Runtime {
     pub fn new() -> Self {
+        RuntimeBuilder::new().build()
+    }
+
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::new()
+    }
+
+    pub(super) fn from_config(config: RuntimeConfig) -> Self {
+        let reactor = if let Some(clock) = &config.test_clock {
+            ReactorRef::borrowed(clock.reactor().clone())
+        } else if config.dedicated_reactor {
+            ReactorRef::dedicated()
+        } else {
+            ReactorRef::acquire()
+        };
+
         Runtime {
-            global_queue: Arc::new(Injector::new()),
-            is_shutdown: Arc::new(AtomicBool::new(false)),
+            scheduler: Arc::new(Scheduler::from_config(&config)),
+            blocking_pool: BlockingPool::new(
+                config.max_blocking_threads,
+                config.blocking_thread_name(),
+            ),
+            config,
+            is_started: AtomicBool::new(false),
+            reactor,
         }
     }
 
+    /// Returns the spawner of the runtime executing the current task.
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside a task running on a runtime worker or
+    /// inside [`Runtime::block_on`]. Use [`crate::spawn`] to get an error
+    /// instead.
+    pub fn current() -> Spawner {
+        context::current_spawner().expect("Runtime::current() called outside of a runtime task")
+    }
+
     pub fn spawner(&self) -> Spawner {
-        Spawner::new(self.global_queue.clone(), self.is_shutdown.clone())
+        Spawner::new(self.scheduler.clone(), self.blocking_pool.clone())
+    }
+
+    /// Returns a handle for running blocking closures on this runtime's
+    /// blocking pool without going through a task.
+    pub fn blocking_handle(&self) -> BlockingHandle {
+        BlockingHandle::new(self.blocking_pool.clone())
     }
 
+    /// Starts `num_workers` worker threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime's workers have already been started by `run`,
+    /// [`start`](Self::start) or [`run_blocking`](Self::run_blocking).
     pub fn run(&self, num_workers: usize) -> RuntimeHandle {
-        let global_queue = self.global_queue.clone();
-        let is_shutdown = self.is_shutdown.clone();
-
-        let worker_handles: Vec<thread::JoinHandle<()>> = (0..num_workers)
-            .map(|worker_id| {
-                let queue = global_queue.clone();
-                let shutdown = is_shutdown.clone();
-                thread::spawn(move || run_worker_loop(worker_id, queue, shutdown))
+        assert!(
+            !self.is_started.swap(true, Ordering::SeqCst),
+            "Runtime::run() called on a runtime that is already running"
+        );
+        self.scheduler.set_worker_count(num_workers);
+
+        let local_queues: Vec<LocalQueue> = (0..num_workers).map(|_| Worker::new_fifo()).collect();
+        let stealers: Arc<[TaskStealer]> = local_queues.iter().map(Worker::stealer).collect();
+        let exits = Arc::new(WorkerExits::default());
+        let cores = self.worker_cores();
+
+        let worker_handles: Vec<thread::JoinHandle<WorkerStats>> = local_queues
+            .into_iter()
+            .enumerate()
+            .map(|(worker_id, local_queue)| {
+                let spawner = self.spawner();
+                let stealers = stealers.clone();
+                let exit_guard = exits.guard();
+                let core = (!cores.is_empty()).then(|| cores[worker_id % cores.len()]);
+                let pin = self.config.core_affinity;
+                let observer = self.config.observer.clone();
+                let reactor = self.reactor.entry();
+                self.config
+                    .worker_thread_builder(worker_id)
+                    .spawn(move || {
+                        let _exit_guard = exit_guard;
+                        let _reactor = reactor.enter();
+                        if pin && !core.is_some_and(affinity::pin_current_thread) {
+                            observer.on_core_affinity_failed(worker_id, core);
+                        }
+                        run_worker_loop(worker_id, spawner, local_queue, stealers)
+                    })
+                    .expect("failed to spawn worker thread")
             })
             .collect();
 
-        RuntimeHandle::new(worker_handles)
+        RuntimeHandle::new(worker_handles, exits)
+    }
+
+    /// Cores to pin workers to, or none if affinity is disabled or not
+    /// supported here.
+    fn worker_cores(&self) -> Vec<usize> {
+        if !self.config.core_affinity {
+            return Vec::new();
+        }
+
+        affinity::available_cores()
+    }
+
+    /// Drives `future` to completion on the calling thread and returns its
+    /// output.
+    ///
+    /// While waiting, the calling thread also executes tasks from the global
+    /// queue, so spawned tasks make progress even if no workers are running.
+    /// Returns as soon as `future` resolves, regardless of other pending tasks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{Runtime, sleep};
+    /// use std::time::Duration;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.spawner().spawn(async { 21 * 2 }).unwrap();
+    ///
+    /// let result = runtime.block_on(async {
+    ///     sleep(Duration::from_millis(10)).await;
+    ///     handle.await.unwrap()
+    /// });
+    /// assert_eq!(result, 42);
+    /// ```
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let _guard = context::enter(self.spawner());
+        let _reactor = self.reactor.entry().enter();
+        block_on(&self.scheduler, future)
+    }
+
+    pub fn metrics(&self) -> RuntimeMetrics {
+        self.scheduler.metrics_snapshot()
+    }
+
+    /// Number of worker threads started by [`run`](Self::run), or zero if
+    /// the runtime hasn't been started.
+    pub fn num_workers(&self) -> usize {
+        self.scheduler.worker_count()
+    }
+
+    /// Number of spawned tasks that haven't finished yet, whether they are
+    /// queued, being polled or waiting to be woken. Tasks run by
+    /// [`spawn_blocking`](Spawner::spawn_blocking) are not included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.spawner().spawn(async {}).unwrap();
+    /// assert_eq!(runtime.active_tasks(), 1);
+    ///
+    /// runtime.block_on(handle).unwrap();
+    /// assert_eq!(runtime.active_tasks(), 0);
+    /// ```
+    pub fn active_tasks(&self) -> usize {
+        self.scheduler.live_tasks()
+    }
+
+    /// Starts the number of workers configured by
+    /// [`RuntimeBuilder::worker_threads`].
+    pub fn start(&self) -> RuntimeHandle {
+        self.run(self.config.worker_threads)
     }
 
     pub fn run_blocking(&self, num_workers: usize) {
         self.run(num_workers).wait();
     }
 
-    pub fn shutdown(self) {
-        self.is_shutdown.store(true, Ordering::SeqCst);
+    /// Runs `num_workers` workers until every spawned task has finished,
+    /// then stops the runtime and returns. Tasks may keep spawning more
+    /// tasks in the meantime; a task waiting on a timer still counts as
+    /// running, so pending sleeps keep the workers alive.
+    ///
+    /// Meant for programs that spawn a finite task graph and exit. A task
+    /// that never finishes, e.g. one waiting on a channel nobody sends to,
+    /// makes this wait forever.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{Runtime, sleep};
+    /// use std::time::Duration;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime
+    ///     .spawner()
+    ///     .spawn(async { sleep(Duration::from_millis(10)).await })
+    ///     .unwrap();
+    ///
+    /// runtime.run_until_idle(2);
+    /// assert!(handle.is_finished());
+    /// ```
+    pub fn run_until_idle(&self, num_workers: usize) {
+        let handle = self.run(num_workers);
+        self.scheduler.shutdown_graceful();
+        handle.wait();
+        self.blocking_pool.shutdown();
+    }
+
+    /// Stops the runtime once every spawned task has finished, including
+    /// tasks still waiting on timers. Workers keep executing queued tasks
+    /// until then, and new tasks may be spawned while draining.
+    pub fn shutdown_graceful(self) {
+        self.scheduler.shutdown_graceful();
+        self.blocking_pool.shutdown();
+    }
+
+    /// Stops the runtime without waiting for spawned tasks. Tasks still
+    /// waiting in the runtime's queues are dropped and their handles resolve
+    /// to [`JoinError::Cancelled`](crate::JoinError::Cancelled); returns how
+    /// many of them had never started. Tasks that were already polled and
+    /// are queued again after a wakeup are dropped too but not counted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{JoinError, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.spawner().spawn(async { 42 }).unwrap();
+    ///
+    /// assert_eq!(runtime.shutdown(), 1);
+    /// assert!(matches!(handle.join(), Err(JoinError::Cancelled)));
+    /// ```
+    pub fn shutdown(self) -> usize {
+        self.shutdown_background();
+        self.scheduler.drain_queued()
+    }
+
+    /// Aborts every spawned task, then stops the runtime once workers have
+    /// dropped them. Handles of aborted tasks resolve to
+    /// [`JoinError::Cancelled`](crate::JoinError::Cancelled) immediately. A
+    /// task stuck inside a single poll still delays the stop until that
+    /// poll returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{JoinError, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// let workers = runtime.run(2);
+    /// let handle = runtime
+    ///     .spawner()
+    ///     .spawn(std::future::pending::<()>())
+    ///     .unwrap();
+    ///
+    /// runtime.shutdown_forceful();
+    /// assert!(matches!(handle.join(), Err(JoinError::Cancelled)));
+    /// workers.wait();
+    /// ```
+    pub fn shutdown_forceful(self) {
+        self.scheduler.abort_all();
+        self.shutdown_graceful();
+    }
+
+    /// Signals workers and the blocking pool to stop without consuming the
+    /// runtime, so it can still be inspected while the workers wind down.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.run(2);
+    ///
+    /// runtime.shutdown_background();
+    /// handle.wait();
+    /// assert_eq!(runtime.metrics().active_workers, 0);
+    /// ```
+    pub fn shutdown_background(&self) {
+        self.scheduler.shutdown();
+        self.blocking_pool.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::RuntimeObserver;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of a runtime task")]
+    fn current_panics_outside_runtime() {
+        let _ = Runtime::current();
+    }
+
+    #[test]
+    fn current_is_available_inside_block_on() {
+        let runtime = Runtime::new();
+        let result =
+            runtime.block_on(async { Runtime::current().spawn(async { 1 }).unwrap().await });
+        assert_eq!(result.unwrap(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "already running")]
+    fn second_run_is_rejected() {
+        let runtime = Runtime::new();
+        let _handle = runtime.run(1);
+
+        let _ = runtime.run(1);
+    }
+
+    #[test]
+    fn run_until_idle_without_tasks_stops_at_once() {
+        let runtime = Runtime::new();
+
+        runtime.run_until_idle(2);
+
+        assert!(runtime.scheduler.is_shutdown());
+        assert!(runtime.spawner().spawn(async {}).is_err());
+    }
+
+    #[test]
+    fn shutdown_forceful_drops_every_task_before_stopping() {
+        let runtime = Runtime::new();
+        let scheduler = runtime.scheduler.clone();
+        let workers = runtime.run(2);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                runtime
+                    .spawner()
+                    .spawn(std::future::pending::<()>())
+                    .unwrap()
+            })
+            .collect();
+
+        runtime.shutdown_forceful();
+        workers.wait();
+
+        assert_eq!(scheduler.live_tasks(), 0);
+        for handle in handles {
+            assert!(matches!(handle.join(), Err(crate::JoinError::Cancelled)));
+        }
+    }
+
+    #[test]
+    fn shutdown_drops_tasks_that_never_started() {
+        let runtime = Runtime::new();
+        let scheduler = runtime.scheduler.clone();
+        let dropped_futures = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let guard = DropCounter(dropped_futures.clone());
+                runtime
+                    .spawner()
+                    .spawn(async move {
+                        let _guard = guard;
+                    })
+                    .unwrap()
+            })
+            .collect();
+
+        assert_eq!(runtime.shutdown(), 5);
+
+        assert_eq!(dropped_futures.load(Ordering::SeqCst), 5);
+        assert_eq!(scheduler.live_tasks(), 0);
+        for handle in handles {
+            assert!(matches!(handle.join(), Err(crate::JoinError::Cancelled)));
+        }
+    }
+
+    #[test]
+    fn core_affinity_failures_go_to_the_observer() {
+        #[derive(Default)]
+        struct AffinityRecorder {
+            failures: parking_lot::Mutex<Vec<(usize, Option<usize>)>>,
+        }
+
+        impl RuntimeObserver for AffinityRecorder {
+            fn on_core_affinity_failed(&self, worker_id: usize, core: Option<usize>) {
+                self.failures.lock().push((worker_id, core));
+            }
+        }
+
+        let recorder = Arc::new(AffinityRecorder::default());
+        let runtime = RuntimeBuilder::new()
+            .core_affinity(true)
+            .observer(recorder.clone())
+            .build();
+
+        let workers = runtime.run(2);
+        runtime.shutdown();
+        workers.wait();
+
+        let failures = recorder.failures.lock().len();
+        assert_eq!(failures, if cfg!(target_os = "linux") { 0 } else { 2 });
+    }
+
+    #[test]
+    fn idle_workers_park_instead_of_spinning() {
+        let runtime = Runtime::new();
+        runtime.spawner().spawn(async {}).unwrap();
+
+        let handle = runtime.run(2);
+        thread::sleep(IDLE_WAIT);
+
+        assert_eq!(runtime.scheduler.sleeping_workers(), 2);
+
+        runtime.shutdown();
+        handle.wait();
+    }
+
+    #[test]
+    fn parked_workers_wake_for_new_tasks() {
+        let runtime = Runtime::new();
+        let spawner = runtime.spawner();
+
+        let handle = runtime.run(2);
+        thread::sleep(IDLE_WAIT);
+
+        let task = spawner.spawn(async { 5 }).unwrap();
+        thread::sleep(IDLE_WAIT);
+
+        assert!(task.is_finished());
+
+        runtime.shutdown();
+        handle.wait();
     }
 }
@@ -0,0 +1,586 @@
+use crossbeam_deque::{Injector, Steal};
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+use std::sync::atomic::{self, AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use super::builder::{IdleStrategy, RuntimeConfig};
+use super::metrics::{MetricsCounters, RuntimeMetrics};
+use super::observer::RuntimeObserver;
+use super::ordered::StartOrder;
+use super::task::{Priority, Task};
+use super::worker::push_to_local_queue;
+use crate::join_handle::JoinError;
+
+/// How long a parked worker sleeps before checking whether the whole runtime
+/// has stalled with work still queued.
+const STALL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of independently locked shards in the live task registry. Task ids
+/// are sequential, so consecutive spawns land on different shards and rarely
+/// contend.
+const TASK_REGISTRY_SHARDS: usize = 64;
+
+type TaskShard = Mutex<HashMap<u64, Weak<Task>>>;
+
+pub(crate) struct Scheduler {
+    global_queue: Injector<Arc<Task>>,
+    high_priority_queue: Injector<Arc<Task>>,
+    is_shutdown: AtomicBool,
+    is_draining: AtomicBool,
+    live_tasks: AtomicUsize,
+    /// Spawned tasks that haven't been polled yet; this is what
+    /// `global_queue_capacity` bounds.
+    unstarted_tasks: AtomicUsize,
+    /// Every live task by id, so they can all be aborted at once. Sharded
+    /// by id to keep spawning and finishing off a single shared lock.
+    tasks: Box<[TaskShard]>,
+    idle_lock: Mutex<()>,
+    idle_condvar: Condvar,
+    sleeping_workers: AtomicUsize,
+    /// Threads inside `block_on`, unparked whenever a task is queued so they
+    /// can pick it up instead of polling the queues on a timer.
+    block_on_threads: Mutex<Vec<Thread>>,
+    block_on_thread_count: AtomicUsize,
+    worker_count: AtomicUsize,
+    metrics: MetricsCounters,
+    poll_budget: usize,
+    global_queue_interval: usize,
+    idle_strategy: IdleStrategy,
+    global_queue_capacity: Option<usize>,
+    observer: Arc<dyn RuntimeObserver>,
+    slow_poll_threshold: Duration,
+    start_order: Arc<StartOrder>,
+}
+
+impl Scheduler {
+    #[cfg(test)]
+    pub(crate) fn new() -> Self {
+        Self::from_config(&RuntimeConfig::default())
+    }
+
+    pub(super) fn from_config(config: &RuntimeConfig) -> Self {
+        Scheduler {
+            global_queue: Injector::new(),
+            high_priority_queue: Injector::new(),
+            is_shutdown: AtomicBool::new(false),
+            is_draining: AtomicBool::new(false),
+            live_tasks: AtomicUsize::new(0),
+            unstarted_tasks: AtomicUsize::new(0),
+            tasks: (0..TASK_REGISTRY_SHARDS)
+                .map(|_| Mutex::new(HashMap::new()))
+                .collect(),
+            idle_lock: Mutex::new(()),
+            idle_condvar: Condvar::new(),
+            sleeping_workers: AtomicUsize::new(0),
+            block_on_threads: Mutex::new(Vec::new()),
+            block_on_thread_count: AtomicUsize::new(0),
+            worker_count: AtomicUsize::new(0),
+            metrics: MetricsCounters::default(),
+            poll_budget: config.task_poll_budget,
+            global_queue_interval: config.global_queue_interval,
+            idle_strategy: config.idle_strategy,
+            global_queue_capacity: config.global_queue_capacity,
+            observer: config.observer.clone(),
+            slow_poll_threshold: config.slow_poll_threshold,
+            start_order: Arc::default(),
+        }
+    }
+
+    pub(crate) fn global_queue(&self) -> &Injector<Arc<Task>> {
+        &self.global_queue
+    }
+
+    pub(crate) fn high_priority_queue(&self) -> &Injector<Arc<Task>> {
+        &self.high_priority_queue
+    }
+
+    /// Takes a task from the high-priority queue, falling back to the global
+    /// queue.
+    pub(crate) fn steal(&self) -> Steal<Arc<Task>> {
+        self.high_priority_queue
+            .steal()
+            .or_else(|| self.global_queue.steal())
+    }
+
+    /// Registers a freshly spawned task and queues it on the global queue.
+    pub(crate) fn submit(&self, task: Arc<Task>) {
+        self.unstarted_tasks.fetch_add(1, Ordering::SeqCst);
+        self.enqueue_new(task);
+    }
+
+    /// Like [`submit`](Self::submit), but hands the task back instead of
+    /// queueing it when `global_queue_capacity` tasks are already waiting
+    /// for their first poll. The slot is reserved before the task is queued,
+    /// so concurrent callers can't overshoot the capacity.
+    pub(crate) fn try_submit(&self, task: Arc<Task>) -> Result<(), Arc<Task>> {
+        if let Some(capacity) = self.global_queue_capacity {
+            let reserved = self.unstarted_tasks.fetch_update(
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+                |unstarted| (unstarted < capacity).then_some(unstarted + 1),
+            );
+            if reserved.is_err() {
+                return Err(task);
+            }
+        } else {
+            self.unstarted_tasks.fetch_add(1, Ordering::SeqCst);
+        }
+        self.enqueue_new(task);
+        Ok(())
+    }
+
+    fn enqueue_new(&self, task: Arc<Task>) {
+        task.mark_awaiting_first_poll();
+        self.live_tasks.fetch_add(1, Ordering::SeqCst);
+        self.task_shard(task.id())
+            .lock()
+            .insert(task.id(), Arc::downgrade(&task));
+        self.metrics.task_spawned();
+        self.push(task);
+    }
+
+    /// Called right before a task's first poll, releasing the capacity slot
+    /// it took when it was submitted. Returns whether this call released it,
+    /// i.e. whether the task had never started before.
+    pub(crate) fn task_started(&self, task: &Task) -> bool {
+        let first_start = task.take_awaiting_first_poll();
+        if first_start {
+            self.unstarted_tasks.fetch_sub(1, Ordering::SeqCst);
+        }
+        first_start
+    }
+
+    /// Called once a task's future has been dropped, whether it completed,
+    /// panicked or was aborted.
+    pub(crate) fn task_finished(&self, task: &Task) {
+        self.task_started(task);
+        self.task_shard(task.id()).lock().remove(&task.id());
+        let remaining = self.live_tasks.fetch_sub(1, Ordering::SeqCst) - 1;
+        if remaining == 0 && self.is_draining.load(Ordering::SeqCst) {
+            self.shutdown();
+        }
+    }
+
+    fn task_shard(&self, task_id: u64) -> &TaskShard {
+        &self.tasks[task_id as usize % self.tasks.len()]
+    }
+
+    /// Tasks spawned whose future hasn't been dropped yet, whether queued,
+    /// running or waiting to be woken.
+    pub(crate) fn live_tasks(&self) -> usize {
+        self.live_tasks.load(Ordering::SeqCst)
+    }
+
+    fn queued_tasks(&self) -> usize {
+        self.global_queue.len() + self.high_priority_queue.len()
+    }
+
+    /// Queues `task` on the injector matching its priority.
+    pub(crate) fn push(&self, task: Arc<Task>) {
+        match task.priority() {
+            Priority::High => self.high_priority_queue.push(task),
+            Priority::Normal => self.global_queue.push(task),
+        }
+        self.notify_one();
+        self.unpark_block_on_threads();
+    }
+
+    /// Registers the calling thread to be unparked whenever a task is queued.
+    /// Must be called before the thread first checks the queues.
+    pub(super) fn register_block_on_thread(&self) {
+        self.block_on_threads.lock().push(thread::current());
+        self.block_on_thread_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub(super) fn deregister_block_on_thread(&self) {
+        let current = thread::current().id();
+        let mut threads = self.block_on_threads.lock();
+        if let Some(index) = threads.iter().position(|thread| thread.id() == current) {
+            threads.swap_remove(index);
+            self.block_on_thread_count.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn unpark_block_on_threads(&self) {
+        // Pairs with the registration in `register_block_on_thread`: either
+        // this load sees the thread, or the thread's later steal sees the
+        // task.
+        atomic::fence(Ordering::SeqCst);
+        if self.block_on_thread_count.load(Ordering::SeqCst) > 0 {
+            for thread in self.block_on_threads.lock().iter() {
+                thread.unpark();
+            }
+        }
+    }
+
+    /// Re-queues a woken task, preferring the local queue of the worker that
+    /// last ran it when the wakeup happens on that worker's thread.
+    /// High-priority tasks always go back to the high-priority queue so they
+    /// keep jumping ahead of normal work.
+    pub(crate) fn schedule(&self, task: Arc<Task>) {
+        if task.priority() == Priority::High {
+            self.push(task);
+        } else if let Err(task) = push_to_local_queue(task) {
+            self.push(task);
+        }
+    }
+
+    pub(crate) fn poll_budget(&self) -> usize {
+        self.poll_budget
+    }
+
+    pub(crate) fn global_queue_interval(&self) -> usize {
+        self.global_queue_interval
+    }
+
+    pub(crate) fn idle_strategy(&self) -> IdleStrategy {
+        self.idle_strategy
+    }
+
+    pub(crate) fn slow_poll_threshold(&self) -> Duration {
+        self.slow_poll_threshold
+    }
+
+    pub(super) fn start_order(&self) -> &Arc<StartOrder> {
+        &self.start_order
+    }
+
+    pub(crate) fn observer(&self) -> &dyn RuntimeObserver {
+        &*self.observer
+    }
+
+    pub(crate) fn metrics(&self) -> &MetricsCounters {
+        &self.metrics
+    }
+
+    pub(crate) fn metrics_snapshot(&self) -> RuntimeMetrics {
+        self.metrics.snapshot(self.queued_tasks())
+    }
+
+    /// Aborts every live task. Their handles resolve to
+    /// [`JoinError::Cancelled`](crate::JoinError::Cancelled) right away and
+    /// workers drop their futures the next time they dequeue them.
+    pub(crate) fn abort_all(&self) {
+        for shard in &self.tasks {
+            let tasks: Vec<Arc<Task>> = shard.lock().values().filter_map(Weak::upgrade).collect();
+            for task in tasks {
+                task.abort();
+            }
+        }
+    }
+
+    /// Removes every task still waiting in the global and high-priority
+    /// queues, drops its future and completes its handle with
+    /// [`JoinError::Cancelled`](crate::JoinError::Cancelled). Returns how many
+    /// of them had never been polled; tasks that were re-queued after
+    /// starting are dropped too but not counted.
+    pub(crate) fn drain_queued(&self) -> usize {
+        let mut dropped = 0;
+        for queue in [&self.high_priority_queue, &self.global_queue] {
+            loop {
+                match queue.steal() {
+                    Steal::Success(task) => {
+                        if self.drop_queued(&task) {
+                            dropped += 1;
+                        }
+                    }
+                    Steal::Empty => break,
+                    Steal::Retry => {}
+                }
+            }
+        }
+        dropped
+    }
+
+    /// Drops the future of a task taken off a queue unless it has already
+    /// finished, returning whether the task had never been polled.
+    fn drop_queued(&self, task: &Task) -> bool {
+        // A task woken during its own poll is queued while the worker still
+        // holds its future. Wait for that poll to return: nothing will queue
+        // the task again, so skipping it here would leak the future.
+        let mut future_slot = task.future_slot().lock();
+        if future_slot.take().is_none() {
+            return false;
+        }
+        drop(future_slot);
+
+        let never_polled = self.task_started(task);
+        self.task_finished(task);
+        task.fail(JoinError::Cancelled);
+        never_polled
+    }
+
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.is_shutdown.load(Ordering::SeqCst)
+    }
+
+    /// Lets workers keep running until every live task has finished, then
+    /// shuts down. Tasks may still be spawned while draining.
+    pub(crate) fn shutdown_graceful(&self) {
+        self.is_draining.store(true, Ordering::SeqCst);
+        if self.live_tasks.load(Ordering::SeqCst) == 0 {
+            self.shutdown();
+        }
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+        let _guard = self.idle_lock.lock();
+        self.idle_condvar.notify_all();
+    }
+
+    pub(crate) fn set_worker_count(&self, worker_count: usize) {
+        self.worker_count.store(worker_count, Ordering::SeqCst);
+    }
+
+    pub(crate) fn worker_count(&self) -> usize {
+        self.worker_count.load(Ordering::SeqCst)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn sleeping_workers(&self) -> usize {
+        self.sleeping_workers.load(Ordering::SeqCst)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn block_on_threads(&self) -> usize {
+        self.block_on_threads.lock().len()
+    }
+
+    /// Blocks the calling worker until new work is signalled or the runtime
+    /// shuts down. `has_work` is re-checked after the worker is registered as
+    /// sleeping, so a push racing with the park is never missed.
+    ///
+    /// As a safety net against lost wakeups, the worker also returns if every
+    /// worker is parked while tasks are still queued, after reporting the
+    /// stall to the observer.
+    pub(crate) fn park_worker(&self, has_work: impl Fn() -> bool) {
+        let mut guard = self.idle_lock.lock();
+        self.sleeping_workers.fetch_add(1, Ordering::SeqCst);
+        atomic::fence(Ordering::SeqCst);
+
+        // A notification racing with the timeout is lost, so both conditions
+        // are re-checked after every timed out wait.
+        while !has_work() && !self.is_shutdown() {
+            let timed_out = self
+                .idle_condvar
+                .wait_for(&mut guard, STALL_CHECK_INTERVAL)
+                .timed_out();
+            if !timed_out {
+                break;
+            }
+            if self.is_stalled() {
+                self.observer.on_stall_detected(self.queued_tasks());
+                break;
+            }
+        }
+
+        self.sleeping_workers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Whether every worker is parked even though tasks are queued, which
+    /// means a wakeup was lost.
+    fn is_stalled(&self) -> bool {
+        let worker_count = self.worker_count();
+        worker_count > 0
+            && self.sleeping_workers.load(Ordering::SeqCst) >= worker_count
+            && self.queued_tasks() > 0
+    }
+
+    fn notify_one(&self) {
+        atomic::fence(Ordering::SeqCst);
+        if self.sleeping_workers.load(Ordering::SeqCst) > 0 {
+            let _guard = self.idle_lock.lock();
+            self.idle_condvar.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduler_starts_running_and_empty() {
+        let scheduler = Scheduler::new();
+        assert!(!scheduler.is_shutdown());
+        assert!(scheduler.global_queue().is_empty());
+    }
+
+    #[test]
+    fn scheduler_shutdown_sets_flag() {
+        let scheduler = Scheduler::new();
+        scheduler.shutdown();
+        assert!(scheduler.is_shutdown());
+    }
+
+    #[test]
+    fn graceful_shutdown_waits_for_live_tasks() {
+        let scheduler = Arc::new(Scheduler::new());
+        let task = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+        scheduler.submit(task.clone());
+
+        scheduler.shutdown_graceful();
+        assert!(!scheduler.is_shutdown());
+
+        scheduler.task_finished(&task);
+        assert!(scheduler.is_shutdown());
+    }
+
+    #[test]
+    fn abort_all_aborts_only_live_tasks() {
+        let scheduler = Arc::new(Scheduler::new());
+        let live = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+        let finished = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+        scheduler.submit(live.clone());
+        scheduler.submit(finished.clone());
+        scheduler.task_finished(&finished);
+
+        scheduler.abort_all();
+
+        assert!(live.is_aborted());
+        assert!(!finished.is_aborted());
+        let registered: usize = scheduler.tasks.iter().map(|shard| shard.lock().len()).sum();
+        assert_eq!(registered, 1);
+    }
+
+    #[test]
+    fn drain_queued_counts_only_tasks_that_never_started() {
+        let scheduler = Arc::new(Scheduler::new());
+        let requeued = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+        let unstarted = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+        scheduler.submit(requeued.clone());
+        scheduler.submit(unstarted.clone());
+        // As if a worker had polled it and it woke itself back into the queue.
+        scheduler.task_started(&requeued);
+
+        assert_eq!(scheduler.drain_queued(), 1);
+
+        assert!(requeued.future_slot().lock().is_none());
+        assert!(unstarted.future_slot().lock().is_none());
+        assert_eq!(scheduler.live_tasks(), 0);
+    }
+
+    #[test]
+    fn drain_queued_waits_for_an_in_flight_poll() {
+        let scheduler = Arc::new(Scheduler::new());
+        let task = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+        scheduler.submit(task.clone());
+        scheduler.task_started(&task);
+
+        let (locked_sender, locked) = std::sync::mpsc::channel();
+        let poller = {
+            let task = task.clone();
+            std::thread::spawn(move || {
+                let _polling = task.future_slot().lock();
+                locked_sender.send(()).unwrap();
+                std::thread::sleep(Duration::from_millis(50));
+            })
+        };
+        locked.recv().unwrap();
+
+        assert_eq!(scheduler.drain_queued(), 0);
+
+        assert!(task.future_slot().lock().is_none());
+        assert_eq!(scheduler.live_tasks(), 0);
+        poller.join().unwrap();
+    }
+
+    #[test]
+    fn consecutive_tasks_register_in_different_shards() {
+        let scheduler = Arc::new(Scheduler::new());
+        let first = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+        let second = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+
+        assert!(!std::ptr::eq(
+            scheduler.task_shard(first.id()),
+            scheduler.task_shard(second.id())
+        ));
+    }
+
+    #[test]
+    fn graceful_shutdown_without_tasks_is_immediate() {
+        let scheduler = Scheduler::new();
+        scheduler.shutdown_graceful();
+        assert!(scheduler.is_shutdown());
+    }
+
+    #[test]
+    fn park_worker_returns_immediately_with_work() {
+        let scheduler = Scheduler::new();
+        scheduler.park_worker(|| true);
+        assert_eq!(scheduler.sleeping_workers(), 0);
+    }
+
+    #[test]
+    fn push_wakes_parked_worker() {
+        let scheduler = Arc::new(Scheduler::new());
+        let parker = {
+            let scheduler = scheduler.clone();
+            std::thread::spawn(move || {
+                scheduler.park_worker(|| !scheduler.global_queue().is_empty())
+            })
+        };
+
+        while scheduler.sleeping_workers() == 0 {
+            std::thread::yield_now();
+        }
+        scheduler.push(Arc::new(Task::new(Box::pin(async {}), scheduler.clone())));
+
+        parker.join().unwrap();
+    }
+
+    #[test]
+    fn shutdown_wakes_parked_worker() {
+        let scheduler = Arc::new(Scheduler::new());
+        let parker = {
+            let scheduler = scheduler.clone();
+            std::thread::spawn(move || scheduler.park_worker(|| false))
+        };
+
+        while scheduler.sleeping_workers() == 0 {
+            std::thread::yield_now();
+        }
+        scheduler.shutdown();
+
+        parker.join().unwrap();
+    }
+
+    #[test]
+    fn park_worker_breaks_stall_with_queued_task() {
+        struct StallRecorder(AtomicUsize);
+
+        impl RuntimeObserver for StallRecorder {
+            fn on_stall_detected(&self, queued_tasks: usize) {
+                self.0.store(queued_tasks, Ordering::SeqCst);
+            }
+        }
+
+        let recorder = Arc::new(StallRecorder(AtomicUsize::new(0)));
+        let scheduler = Arc::new(Scheduler::from_config(&RuntimeConfig {
+            observer: recorder.clone(),
+            ..RuntimeConfig::default()
+        }));
+        scheduler.set_worker_count(1);
+        // Queue a task without notifying, as a lost wakeup would.
+        let task = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+        scheduler.global_queue().push(task);
+
+        scheduler.park_worker(|| false);
+
+        assert_eq!(recorder.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn schedule_outside_worker_uses_global_queue() {
+        let scheduler = Arc::new(Scheduler::new());
+        let task = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+
+        scheduler.schedule(task);
+
+        assert_eq!(scheduler.global_queue().len(), 1);
+    }
+}
@@ -0,0 +1,215 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::{Future, poll_fn};
+use std::sync::Arc;
+use std::task::{Poll, Waker};
+
+use super::spawner::{SpawnError, Spawner};
+use crate::join_handle::{AbortHandle, JoinHandle};
+
+/// Handle for spawning children of a [`Spawner::scope`].
+///
+/// Every task spawned through a `Scope` (or any of its clones) is waited for
+/// before the scope resolves. Children still have to be `'static`: the scope
+/// future could be leaked with `mem::forget`, so nothing stops it from
+/// outliving borrowed data. Share state with `Arc` instead.
+#[derive(Clone)]
+pub struct Scope {
+    spawner: Spawner,
+    state: Arc<ScopeState>,
+}
+
+#[derive(Default)]
+struct ScopeState {
+    children: Mutex<Children>,
+}
+
+#[derive(Default)]
+struct Children {
+    running: usize,
+    next_id: u64,
+    /// One entry per running child, filled in once its task is spawned and
+    /// removed when the child's future is dropped.
+    abort_handles: HashMap<u64, Option<AbortHandle>>,
+    waker: Option<Waker>,
+}
+
+impl Scope {
+    pub(super) fn new(spawner: Spawner) -> Self {
+        Scope {
+            spawner,
+            state: Arc::default(),
+        }
+    }
+
+    /// Spawns `future` as a child of the scope. The returned handle can be
+    /// awaited or aborted as usual; the scope waits for the child either
+    /// way.
+    pub fn spawn<F, T>(&self, future: F) -> Result<JoinHandle<T>, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let child = ChildGuard::new(self.state.clone());
+        let id = child.id;
+        let handle = self.spawner.spawn(async move {
+            let _child = child;
+            future.await
+        })?;
+
+        // The child may already have finished and removed its entry.
+        if let Some(slot) = self.state.children.lock().abort_handles.get_mut(&id) {
+            *slot = Some(handle.abort_handle());
+        }
+        Ok(handle)
+    }
+
+    /// Waits until no child is running.
+    pub(super) async fn wait_children(&self) {
+        poll_fn(|cx| {
+            let mut children = self.state.children.lock();
+            if children.running == 0 {
+                return Poll::Ready(());
+            }
+
+            children.waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Returns a guard that aborts every child still running when dropped,
+    /// so a scope that is cancelled doesn't leak its children.
+    pub(super) fn abort_on_drop(&self) -> AbortChildren {
+        AbortChildren {
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Counts a child as running from spawn until its future is dropped, which
+/// happens whether it completes, panics or is aborted.
+struct ChildGuard {
+    state: Arc<ScopeState>,
+    id: u64,
+}
+
+impl ChildGuard {
+    fn new(state: Arc<ScopeState>) -> Self {
+        let mut children = state.children.lock();
+        let id = children.next_id;
+        children.next_id += 1;
+        children.running += 1;
+        children.abort_handles.insert(id, None);
+        drop(children);
+
+        ChildGuard { state, id }
+    }
+}
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        let mut children = self.state.children.lock();
+        children.running -= 1;
+        children.abort_handles.remove(&self.id);
+        if children.running == 0
+            && let Some(waker) = children.waker.take()
+        {
+            drop(children);
+            waker.wake();
+        }
+    }
+}
+
+pub(super) struct AbortChildren {
+    state: Arc<ScopeState>,
+}
+
+impl Drop for AbortChildren {
+    fn drop(&mut self) {
+        let abort_handles = std::mem::take(&mut self.state.children.lock().abort_handles);
+        for abort_handle in abort_handles.into_values().flatten() {
+            abort_handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::executor::Runtime;
+    use crate::join_handle::JoinError;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn scope_without_children_returns_body_output() {
+        let runtime = Runtime::new();
+
+        let output = runtime.block_on(runtime.spawner().scope(|_| async { 7 }));
+
+        assert_eq!(output, 7);
+    }
+
+    #[test]
+    fn children_may_spawn_into_the_same_scope() {
+        let runtime = Runtime::new();
+        let _workers = runtime.run(2);
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        let counter = finished.clone();
+        runtime.block_on(runtime.spawner().scope(|scope| async move {
+            let inner = scope.clone();
+            scope
+                .spawn(async move {
+                    inner
+                        .spawn(async move {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                        })
+                        .unwrap();
+                })
+                .unwrap();
+        }));
+
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+        runtime.shutdown();
+    }
+
+    #[test]
+    fn finished_children_release_their_abort_handles() {
+        let runtime = Runtime::new();
+        let _workers = runtime.run(2);
+        let spawner = runtime.spawner();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        runtime.block_on(spawner.scope(|scope| async move {
+            for _ in 0..100 {
+                scope.spawn(async {}).unwrap().await.unwrap();
+            }
+            sender
+                .send(scope.state.children.lock().abort_handles.len())
+                .unwrap();
+        }));
+
+        assert_eq!(receiver.recv().unwrap(), 0);
+        runtime.shutdown();
+    }
+
+    #[test]
+    fn dropping_scope_aborts_children() {
+        let runtime = Runtime::new();
+        let _workers = runtime.run(1);
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let scope = runtime.spawner().scope(|scope| async move {
+            let child = scope.spawn(std::future::pending::<()>()).unwrap();
+            sender.send(child).unwrap();
+            std::future::pending::<()>().await
+        });
+        let handle = runtime.spawner().spawn(scope).unwrap();
+        let child = receiver.recv().unwrap();
+        handle.abort();
+
+        assert!(matches!(child.join(), Err(JoinError::Cancelled)));
+        runtime.shutdown();
+    }
+}
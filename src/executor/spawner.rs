@@ -1,63 +1,652 @@
-use crossbeam_deque::Injector;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::future::poll_fn;
+use std::pin::pin;
+use std::sync::{Arc, Weak};
+use std::task::Poll;
+use std::time::Duration;
 
-use super::task::Task;
+use super::blocking::{BlockingHandle, BlockingPool};
+use super::context;
+use super::scheduler::Scheduler;
+use super::scope::Scope;
+use super::task::{Priority, Task};
+use crate::cancellation::CancellationToken;
 #[allow(unused_imports)]
-use crate::join_handle::{JoinHandle, JoinNotifier};
+use crate::join_handle::{FlattenedJoinHandle, JoinError, JoinHandle, JoinNotifier};
+use crate::timer::sleep;
 
 const RUNTIME_STOPPED_MESSAGE: &str = "runtime has been stopped";
+const NO_RUNTIME_MESSAGE: &str = "no runtime is running on the current thread";
+const QUEUE_FULL_MESSAGE: &str = "global task queue is at capacity";
 
 #[derive(Clone)]
 pub struct Spawner {
-    global_queue: Arc<Injector<Arc<Task>>>,
-    is_shutdown: Arc<AtomicBool>,
+    scheduler: Arc<Scheduler>,
+    blocking_pool: Arc<BlockingPool>,
 }
 
 impl Spawner {
-    pub(super) fn new(
-        global_queue: Arc<Injector<Arc<Task>>>,
-        is_shutdown: Arc<AtomicBool>,
-    ) -> Self {
+    pub(super) fn new(scheduler: Arc<Scheduler>, blocking_pool: Arc<BlockingPool>) -> Self {
         Spawner {
-            global_queue,
-            is_shutdown,
+            scheduler,
+            blocking_pool,
         }
     }
 
+    pub(super) fn scheduler(&self) -> &Arc<Scheduler> {
+        &self.scheduler
+    }
+
+    /// Returns a handle that can only spawn while the runtime is running,
+    /// without keeping the runtime's scheduler alive.
+    pub fn weak(&self) -> WeakSpawner {
+        WeakSpawner {
+            scheduler: Arc::downgrade(&self.scheduler),
+            blocking_pool: Arc::downgrade(&self.blocking_pool),
+        }
+    }
+
+    /// Spawns `future` on the runtime and returns a handle to its result.
+    ///
+    /// New tasks are queued on the runtime's FIFO global queue, so tasks
+    /// spawned one after another are picked up roughly in spawn order. With
+    /// more than one worker, two tasks picked up at nearly the same time may
+    /// still start in either order; use [`spawn_ordered`](Self::spawn_ordered)
+    /// when the order matters.
     pub fn spawn<F, T>(&self, future: F) -> Result<JoinHandle<T>, SpawnError>
     where
         F: Future<Output = T> + Send + 'static,
         T: Send + 'static,
     {
-        if self.is_shutdown.load(Ordering::SeqCst) {
+        self.spawn_task(None, Priority::Normal, future)
+    }
+
+    /// Spawns every future in `futures` and returns their handles in the same
+    /// order.
+    ///
+    /// Stops at the first future that fails to spawn and returns its error.
+    /// Futures spawned before the failure are not rolled back: their handles
+    /// are dropped, so they keep running detached, and the remaining futures
+    /// are dropped unpolled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handles = runtime
+    ///     .spawner()
+    ///     .spawn_all((0..3).map(|i| async move { i * 10 }))
+    ///     .unwrap();
+    ///
+    /// let _workers = runtime.run(2);
+    /// let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    /// assert_eq!(results, [0, 10, 20]);
+    /// ```
+    pub fn spawn_all<I, F, T>(&self, futures: I) -> Result<Vec<JoinHandle<T>>, SpawnError>
+    where
+        I: IntoIterator<Item = F>,
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        futures
+            .into_iter()
+            .map(|future| self.spawn(future))
+            .collect()
+    }
+
+    /// Like [`spawn`](Self::spawn), for a fallible future. Awaiting the
+    /// returned handle yields the future's own `Result` instead of a
+    /// `Result` nested in another: a [`JoinError`] (the task panicked or was
+    /// aborted) is converted into `E`, which must implement
+    /// `From<JoinError>`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{JoinError, Runtime};
+    ///
+    /// #[derive(Debug)]
+    /// enum FetchError {
+    ///     NotFound,
+    ///     Join(JoinError),
+    /// }
+    ///
+    /// impl From<JoinError> for FetchError {
+    ///     fn from(error: JoinError) -> Self {
+    ///         FetchError::Join(error)
+    ///     }
+    /// }
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime
+    ///     .spawner()
+    ///     .spawn_fallible(async { Err::<u32, _>(FetchError::NotFound) })
+    ///     .unwrap();
+    ///
+    /// assert!(matches!(runtime.block_on(handle), Err(FetchError::NotFound)));
+    /// ```
+    pub fn spawn_fallible<F, T, E>(
+        &self,
+        future: F,
+    ) -> Result<FlattenedJoinHandle<T, E>, SpawnError>
+    where
+        F: Future<Output = Result<T, E>> + Send + 'static,
+        T: Send + 'static,
+        E: From<JoinError> + Send + 'static,
+    {
+        self.spawn(future).map(JoinHandle::flatten)
+    }
+
+    /// Like [`spawn`](Self::spawn), but names the task. The name is reported
+    /// to the [`RuntimeObserver`](super::RuntimeObserver) and carried by
+    /// [`JoinError::Panicked`] if the task panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime
+    ///     .spawner()
+    ///     .spawn_named("fetcher", async { panic!("connection reset") })
+    ///     .unwrap();
+    ///
+    /// let error = runtime.block_on(handle).unwrap_err();
+    /// assert_eq!(error.to_string(), "task 'fetcher' panicked: connection reset");
+    /// ```
+    pub fn spawn_named<F, T>(
+        &self,
+        name: impl Into<String>,
+        future: F,
+    ) -> Result<JoinHandle<T>, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn_task(Some(Arc::from(name.into())), Priority::Normal, future)
+    }
+
+    /// Like [`spawn`](Self::spawn), but queues the task in the given
+    /// scheduling tier. Every time it is woken, the task goes back to that
+    /// tier.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{Priority, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime
+    ///     .spawner()
+    ///     .spawn_with_priority(Priority::High, async { "urgent" })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(runtime.block_on(handle).unwrap(), "urgent");
+    /// ```
+    pub fn spawn_with_priority<F, T>(
+        &self,
+        priority: Priority,
+        future: F,
+    ) -> Result<JoinHandle<T>, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn_task(None, priority, future)
+    }
+
+    fn spawn_task<F, T>(
+        &self,
+        name: Option<Arc<str>>,
+        priority: Priority,
+        future: F,
+    ) -> Result<JoinHandle<T>, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        if self.scheduler.is_shutdown() {
             return Err(SpawnError::RuntimeStopped);
         }
 
+        let (task, handle) = self.new_task(name, priority, future);
+        self.scheduler.submit(task);
+        Ok(handle)
+    }
+
+    /// Wraps `future` in a task that completes the returned handle.
+    fn new_task<F, T>(
+        &self,
+        name: Option<Arc<str>>,
+        priority: Priority,
+        future: F,
+    ) -> (Arc<Task>, JoinHandle<T>)
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
         let (handle, notifier) = JoinHandle::new();
-        let queue = self.global_queue.clone();
+        let failure_notifier = notifier.clone();
 
         let wrapped_future = Box::pin(async move {
             let result = future.await;
             notifier.complete(Ok(result));
         });
 
-        let task = Arc::new(Task::new(wrapped_future, queue));
-        self.global_queue.push(task);
+        let task = Task::new(wrapped_future, self.scheduler.clone())
+            .with_failure_callback(Box::new(move |error| failure_notifier.complete(Err(error))))
+            .with_priority(priority);
+        let task = match name {
+            Some(name) => task.with_name(name),
+            None => task,
+        };
+        let task = Arc::new(task);
+        (task.clone(), handle.with_task(task))
+    }
 
-        Ok(handle)
+    /// Like [`spawn`](Self::spawn), but guarantees that tasks spawned this
+    /// way start in the order they were spawned, on any number of workers.
+    /// "Start" means the task's first poll: the next ordered task isn't
+    /// polled until that poll returns, but it doesn't wait for earlier tasks
+    /// to finish. An aborted task gives up its turn.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let runtime = Runtime::new();
+    /// let started = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let handles: Vec<_> = (0..4)
+    ///     .map(|i| {
+    ///         let started = started.clone();
+    ///         runtime
+    ///             .spawner()
+    ///             .spawn_ordered(async move { started.lock().unwrap().push(i) })
+    ///             .unwrap()
+    ///     })
+    ///     .collect();
+    ///
+    /// let _workers = runtime.run(4);
+    /// for handle in handles {
+    ///     handle.join().unwrap();
+    /// }
+    /// assert_eq!(*started.lock().unwrap(), vec![0, 1, 2, 3]);
+    /// ```
+    pub fn spawn_ordered<F, T>(&self, future: F) -> Result<JoinHandle<T>, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        if self.scheduler.is_shutdown() {
+            return Err(SpawnError::RuntimeStopped);
+        }
+
+        let turn = self.scheduler.start_order().ticket();
+        self.spawn(async move {
+            let mut future = pin!(future);
+            turn.wait_turn().await;
+            let first_poll = poll_fn(|cx| Poll::Ready(future.as_mut().poll(cx))).await;
+            drop(turn);
+
+            match first_poll {
+                Poll::Ready(output) => output,
+                Poll::Pending => future.await,
+            }
+        })
+    }
+
+    /// Like [`spawn`](Self::spawn), but the future only starts running once
+    /// `delay` has elapsed, measured from this call. The handle is returned
+    /// right away, and aborting it during the delay cancels the task before
+    /// it ever runs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let runtime = Runtime::new();
+    /// let start = Instant::now();
+    /// let handle = runtime
+    ///     .spawner()
+    ///     .spawn_after(Duration::from_millis(20), async move { start.elapsed() })
+    ///     .unwrap();
+    ///
+    /// assert!(runtime.block_on(handle).unwrap() >= Duration::from_millis(20));
+    /// ```
+    pub fn spawn_after<F, T>(&self, delay: Duration, future: F) -> Result<JoinHandle<T>, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let delay = sleep(delay);
+        self.spawn(async move {
+            delay.await;
+            future.await
+        })
+    }
+
+    /// Spawns the future built by `f` as a child of the current scoped task.
+    ///
+    /// `f` receives the child's [`CancellationToken`]. When called from a
+    /// task that was itself spawned with `spawn_scoped`, the token is a
+    /// [child](CancellationToken::child_token) of that task's token;
+    /// otherwise it is a fresh root token. A task's token is cancelled when
+    /// the task is aborted or dropped before finishing, so aborting a parent
+    /// cancels its whole subtree.
+    ///
+    /// Cancellation is cooperative: children keep running until they observe
+    /// their token, e.g. through [`CancellationToken::cancelled`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let parent = runtime
+    ///     .spawner()
+    ///     .spawn_scoped(|token| async move {
+    ///         let child = Runtime::current()
+    ///             .spawn_scoped(|token| async move {
+    ///                 token.cancelled().await;
+    ///                 "stopped"
+    ///             })
+    ///             .unwrap();
+    ///
+    ///         token.cancel();
+    ///         child.await.unwrap()
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(runtime.block_on(parent).unwrap(), "stopped");
+    /// ```
+    pub fn spawn_scoped<C, F, T>(&self, f: C) -> Result<JoinHandle<T>, SpawnError>
+    where
+        C: FnOnce(CancellationToken) -> F,
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let token = context::current_cancellation_token()
+            .map_or_else(CancellationToken::new, |parent| parent.child_token());
+        let future = f(token.clone());
+        self.spawn(context::scope_cancellation(token, future))
+    }
+
+    /// Runs the future built by `f` and then waits for every task spawned
+    /// through the [`Scope`] it receives, so no child outlives the scope.
+    ///
+    /// The returned future resolves to the body's output only once all
+    /// children have finished, whether they completed, panicked or were
+    /// aborted. A child's failure doesn't fail the scope; await its
+    /// [`JoinHandle`] to observe it. If the scope future is dropped first,
+    /// e.g. because its own task was aborted, every child still running is
+    /// aborted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let runtime = Runtime::new();
+    /// let _workers = runtime.run(2);
+    /// let done = Arc::new(AtomicUsize::new(0));
+    ///
+    /// let counter = done.clone();
+    /// runtime.block_on(runtime.spawner().scope(|scope| async move {
+    ///     for _ in 0..3 {
+    ///         let counter = counter.clone();
+    ///         scope.spawn(async move { counter.fetch_add(1, Ordering::SeqCst) }).unwrap();
+    ///     }
+    /// }));
+    ///
+    /// assert_eq!(done.load(Ordering::SeqCst), 3);
+    /// ```
+    pub fn scope<C, F, R>(&self, f: C) -> impl Future<Output = R> + use<C, F, R>
+    where
+        C: FnOnce(Scope) -> F,
+        F: Future<Output = R>,
+    {
+        let scope = Scope::new(self.clone());
+        async move {
+            let _abort_children = scope.abort_on_drop();
+            let output = f(scope.clone()).await;
+            scope.wait_children().await;
+            output
+        }
+    }
+
+    /// Spawns a fire-and-forget task. No [`JoinHandle`] is created, so
+    /// there is no way to await, abort or observe the task's result; a panic
+    /// is only reported to the [`RuntimeObserver`](super::RuntimeObserver).
+    ///
+    /// Suited to service loops that run until the runtime shuts down.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{Runtime, sleep};
+    /// use std::time::Duration;
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime
+    ///     .spawner()
+    ///     .spawn_detached(async {
+    ///         loop {
+    ///             // ... accept and serve connections ...
+    ///             sleep(Duration::from_millis(10)).await;
+    ///         }
+    ///     })
+    ///     .unwrap();
+    ///
+    /// let workers = runtime.run(1);
+    /// runtime.shutdown();
+    /// workers.wait();
+    /// ```
+    pub fn spawn_detached<F>(&self, future: F) -> Result<(), SpawnError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        if self.scheduler.is_shutdown() {
+            return Err(SpawnError::RuntimeStopped);
+        }
+
+        let task = Arc::new(Task::new(Box::pin(future), self.scheduler.clone()));
+        self.scheduler.submit(task);
+        Ok(())
+    }
+
+    /// Like [`spawn`](Self::spawn), but fails with [`SpawnError::QueueFull`]
+    /// instead of queueing when the runtime was built with a
+    /// [`global_queue_capacity`] and that many spawned tasks are still
+    /// waiting for their first poll. Without a capacity this behaves exactly
+    /// like `spawn`.
+    ///
+    /// [`global_queue_capacity`]: super::RuntimeBuilder::global_queue_capacity
+    pub fn try_spawn<F, T>(&self, future: F) -> Result<JoinHandle<T>, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        if self.scheduler.is_shutdown() {
+            return Err(SpawnError::RuntimeStopped);
+        }
+
+        let (task, handle) = self.new_task(None, Priority::Normal, future);
+        match self.scheduler.try_submit(task) {
+            Ok(()) => Ok(handle),
+            Err(_) => Err(SpawnError::QueueFull),
+        }
+    }
+
+    /// Like [`spawn`](Self::spawn), but builds the future by calling `f` on
+    /// the worker that first polls the task rather than on the calling thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime
+    ///     .spawner()
+    ///     .spawn_fn(|| {
+    ///         let setup = std::thread::current().id();
+    ///         async move { setup }
+    ///     })
+    ///     .unwrap();
+    ///
+    /// let _workers = runtime.run(1);
+    /// assert_ne!(handle.join().unwrap(), std::thread::current().id());
+    /// ```
+    pub fn spawn_fn<C, F, T>(&self, f: C) -> Result<JoinHandle<T>, SpawnError>
+    where
+        C: FnOnce() -> F + Send + 'static,
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn(async move { f().await })
+    }
+
+    /// Runs the blocking closure `f` on the runtime's blocking thread pool,
+    /// keeping async workers free to make progress.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.spawner().spawn_blocking(|| {
+    ///     std::thread::sleep(std::time::Duration::from_millis(10));
+    ///     "done"
+    /// });
+    ///
+    /// assert_eq!(runtime.block_on(handle).unwrap(), "done");
+    /// ```
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        BlockingHandle::new(self.blocking_pool.clone()).submit(f)
+    }
+}
+
+/// A [`Spawner`] that doesn't keep its runtime alive, obtained from
+/// [`Spawner::weak`].
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+///
+/// let runtime = Runtime::new();
+/// let weak = runtime.spawner().weak();
+///
+/// let handle = weak.upgrade().unwrap().spawn(async { 1 }).unwrap();
+/// assert_eq!(runtime.block_on(handle).unwrap(), 1);
+///
+/// runtime.shutdown();
+/// assert!(weak.upgrade().is_none());
+/// ```
+#[derive(Clone)]
+pub struct WeakSpawner {
+    scheduler: Weak<Scheduler>,
+    blocking_pool: Weak<BlockingPool>,
+}
+
+impl WeakSpawner {
+    /// Returns a spawner for the runtime, or `None` once it has been shut
+    /// down or dropped.
+    pub fn upgrade(&self) -> Option<Spawner> {
+        let scheduler = self.scheduler.upgrade()?;
+        if scheduler.is_shutdown() {
+            return None;
+        }
+
+        Some(Spawner::new(scheduler, self.blocking_pool.upgrade()?))
     }
 }
 
+/// Spawns a task on the runtime executing the current task.
+///
+/// Returns [`SpawnError::NoRuntime`] when called outside a runtime task.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+///
+/// let runtime = Runtime::new();
+/// let handle = runtime
+///     .spawner()
+///     .spawn(async {
+///         let child = runtime::spawn(async { 40 + 2 }).unwrap();
+///         child.await.unwrap()
+///     })
+///     .unwrap();
+///
+/// assert_eq!(runtime.block_on(handle).unwrap(), 42);
+/// ```
+pub fn spawn<F, T>(future: F) -> Result<JoinHandle<T>, SpawnError>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    context::current_spawner()
+        .ok_or(SpawnError::NoRuntime)?
+        .spawn(future)
+}
+
+/// Returns `true` when called from a task running on a runtime worker or
+/// inside [`Runtime::block_on`](super::Runtime::block_on).
+///
+/// Lets code that can run either way pick between blocking and async
+/// behaviour.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+///
+/// let runtime = Runtime::new();
+///
+/// assert!(!runtime::is_in_runtime());
+/// assert!(runtime.block_on(async { runtime::is_in_runtime() }));
+/// ```
+pub fn is_in_runtime() -> bool {
+    context::current_spawner().is_some()
+}
+
+/// Returns the spawner of the runtime executing the current task, or `None`
+/// outside a runtime. The non-panicking form of
+/// [`Runtime::current`](super::Runtime::current).
+pub fn try_current() -> Option<Spawner> {
+    context::current_spawner()
+}
+
 #[derive(Debug)]
 pub enum SpawnError {
     RuntimeStopped,
+    NoRuntime,
+    QueueFull,
 }
 
 impl std::fmt::Display for SpawnError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SpawnError::RuntimeStopped => write!(f, "{}", RUNTIME_STOPPED_MESSAGE),
+            SpawnError::NoRuntime => write!(f, "{}", NO_RUNTIME_MESSAGE),
+            SpawnError::QueueFull => write!(f, "{}", QUEUE_FULL_MESSAGE),
         }
     }
 }
@@ -74,6 +663,111 @@ mod tests {
         assert!(format!("{}", error).contains("stopped"));
     }
 
+    #[test]
+    fn spawn_error_display_no_runtime() {
+        let error = SpawnError::NoRuntime;
+        assert!(format!("{}", error).contains("no runtime"));
+    }
+
+    #[test]
+    fn spawn_error_display_queue_full() {
+        let error = SpawnError::QueueFull;
+        assert!(format!("{}", error).contains("capacity"));
+    }
+
+    #[test]
+    fn spawn_outside_runtime_fails() {
+        let result = spawn(async {});
+        assert!(matches!(result, Err(SpawnError::NoRuntime)));
+    }
+
+    #[test]
+    fn runtime_context_is_visible_only_inside_tasks() {
+        let runtime = crate::Runtime::new();
+        let handle = runtime
+            .spawner()
+            .spawn(async { (is_in_runtime(), try_current().is_some()) })
+            .unwrap();
+        let _workers = runtime.run(1);
+
+        assert_eq!(handle.join().unwrap(), (true, true));
+        assert!(!is_in_runtime());
+        assert!(try_current().is_none());
+        runtime.shutdown();
+    }
+
+    #[test]
+    fn spawn_after_measures_delay_from_the_call() {
+        let runtime = crate::Runtime::new();
+        let clock = crate::timer::TestClock::new();
+        let guard = clock.enter();
+        let handle = runtime
+            .spawner()
+            .spawn_after(Duration::from_secs(60), async { "late" })
+            .unwrap();
+        drop(guard);
+
+        clock.advance(Duration::from_secs(60));
+
+        assert_eq!(runtime.block_on(handle).unwrap(), "late");
+    }
+
+    #[test]
+    fn spawn_all_returns_handles_in_order() {
+        let runtime = crate::Runtime::new();
+        let handles = runtime
+            .spawner()
+            .spawn_all((0..50).map(|i| async move { i }))
+            .unwrap();
+        let _workers = runtime.run(2);
+
+        let results: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(results, (0..50).collect::<Vec<_>>());
+        runtime.shutdown();
+    }
+
+    #[test]
+    fn spawn_all_after_shutdown_fails() {
+        let runtime = crate::Runtime::new();
+        let spawner = runtime.spawner();
+        runtime.shutdown();
+
+        let result = spawner.spawn_all((0..2).map(|_| async {}));
+        assert!(matches!(result, Err(SpawnError::RuntimeStopped)));
+    }
+
+    #[test]
+    fn spawn_detached_after_shutdown_fails() {
+        let runtime = crate::Runtime::new();
+        let spawner = runtime.spawner();
+        runtime.shutdown();
+
+        let result = spawner.spawn_detached(async {});
+        assert!(matches!(result, Err(SpawnError::RuntimeStopped)));
+    }
+
+    #[test]
+    fn weak_spawner_upgrades_until_shutdown() {
+        let runtime = crate::Runtime::new();
+        let weak = runtime.spawner().weak();
+
+        assert!(weak.upgrade().is_some());
+        runtime.shutdown();
+
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn weak_spawner_does_not_keep_scheduler_alive() {
+        let runtime = crate::Runtime::new();
+        let weak = runtime.spawner().weak();
+
+        drop(runtime);
+
+        assert!(weak.scheduler.upgrade().is_none());
+    }
+
     #[test]
     fn spawn_error_is_debug() {
         let error = SpawnError::RuntimeStopped;
@@ -3,32 +3,149 @@ use parking_lot::Mutex;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 
-use crossbeam_deque::Injector;
+use super::scheduler::Scheduler;
+use crate::join_handle::JoinError;
 
 pub(crate) type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
 
+/// Completes the task's `JoinHandle` when the future can't do it itself, e.g.
+/// because it panicked.
+pub(crate) type FailureCallback = Box<dyn Fn(JoinError) + Send + Sync + 'static>;
+
+const NO_WORKER: usize = usize::MAX;
+
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Scheduling tier of a task. Workers always run queued [`High`](Priority::High)
+/// tasks before [`Normal`](Priority::Normal) ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+}
+
 pub(crate) struct Task {
+    id: u64,
+    name: Option<Arc<str>>,
     future: Mutex<Option<BoxFuture>>,
-    global_queue: Arc<Injector<Arc<Task>>>,
+    scheduler: Arc<Scheduler>,
+    last_worker: AtomicUsize,
+    is_aborted: AtomicBool,
+    awaiting_first_poll: AtomicBool,
+    on_failure: Option<FailureCallback>,
+    priority: Priority,
 }
 
 impl Task {
-    pub(crate) fn new(future: BoxFuture, global_queue: Arc<Injector<Arc<Task>>>) -> Self {
+    pub(crate) fn new(future: BoxFuture, scheduler: Arc<Scheduler>) -> Self {
         Task {
+            id: NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed),
+            name: None,
             future: Mutex::new(Some(future)),
-            global_queue,
+            scheduler,
+            last_worker: AtomicUsize::new(NO_WORKER),
+            is_aborted: AtomicBool::new(false),
+            awaiting_first_poll: AtomicBool::new(false),
+            on_failure: None,
+            priority: Priority::Normal,
+        }
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(crate) fn with_name(mut self, name: Arc<str>) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// The name given to [`Spawner::spawn_named`], or the task's numeric id
+    /// for tasks spawned without one.
+    ///
+    /// [`Spawner::spawn_named`]: super::Spawner::spawn_named
+    pub(crate) fn name(&self) -> Arc<str> {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => Arc::from(self.id.to_string()),
+        }
+    }
+
+    pub(crate) fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub(crate) fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub(crate) fn with_failure_callback(mut self, on_failure: FailureCallback) -> Self {
+        self.on_failure = Some(on_failure);
+        self
+    }
+
+    /// Flags a task the scheduler has just counted as waiting for its first
+    /// poll.
+    pub(crate) fn mark_awaiting_first_poll(&self) {
+        self.awaiting_first_poll.store(true, Ordering::SeqCst);
+    }
+
+    /// Clears the flag set by [`mark_awaiting_first_poll`], returning
+    /// whether it was set. Only the first caller sees `true`.
+    ///
+    /// [`mark_awaiting_first_poll`]: Self::mark_awaiting_first_poll
+    pub(crate) fn take_awaiting_first_poll(&self) -> bool {
+        self.awaiting_first_poll.swap(false, Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.is_aborted.load(Ordering::SeqCst)
+    }
+
+    /// Marks the task as aborted, completes its `JoinHandle` with
+    /// [`JoinError::Cancelled`] right away, and queues the task so a worker
+    /// drops its future without polling it again. If the task finishes
+    /// concurrently, whichever result reaches the handle first is kept.
+    pub(crate) fn abort(self: &Arc<Self>) {
+        if !self.is_aborted.swap(true, Ordering::SeqCst) {
+            self.fail(JoinError::Cancelled);
+            self.scheduler.schedule(self.clone());
+        }
+    }
+
+    pub(crate) fn fail(&self, error: JoinError) {
+        if let Some(on_failure) = &self.on_failure {
+            on_failure(error);
         }
     }
 
     pub(crate) fn future_slot(&self) -> &Mutex<Option<BoxFuture>> {
         &self.future
     }
+
+    pub(crate) fn scheduler(&self) -> &Arc<Scheduler> {
+        &self.scheduler
+    }
+
+    pub(crate) fn last_worker(&self) -> Option<usize> {
+        match self.last_worker.load(Ordering::Relaxed) {
+            NO_WORKER => None,
+            worker_id => Some(worker_id),
+        }
+    }
+
+    pub(crate) fn set_last_worker(&self, worker_id: usize) {
+        self.last_worker.store(worker_id, Ordering::Relaxed);
+    }
 }
 
 impl ArcWake for Task {
     fn wake_by_ref(arc_self: &Arc<Self>) {
-        arc_self.global_queue.push(arc_self.clone());
+        arc_self.scheduler.schedule(arc_self.clone());
     }
 }
 
@@ -38,18 +155,18 @@ mod tests {
 
     #[test]
     fn task_new_creates_with_future() {
-        let queue = Arc::new(Injector::new());
+        let scheduler = Arc::new(Scheduler::new());
         let future = Box::pin(async {});
-        let task = Task::new(future, queue);
+        let task = Task::new(future, scheduler);
 
         assert!(task.future_slot().lock().is_some());
     }
 
     #[test]
     fn task_future_can_be_taken() {
-        let queue = Arc::new(Injector::new());
+        let scheduler = Arc::new(Scheduler::new());
         let future = Box::pin(async {});
-        let task = Task::new(future, queue);
+        let task = Task::new(future, scheduler);
 
         let taken = task.future_slot().lock().take();
         assert!(taken.is_some());
@@ -58,12 +175,97 @@ mod tests {
 
     #[test]
     fn task_wake_adds_to_queue() {
-        let queue = Arc::new(Injector::new());
+        let scheduler = Arc::new(Scheduler::new());
         let future = Box::pin(async {});
-        let task = Arc::new(Task::new(future, queue.clone()));
+        let task = Arc::new(Task::new(future, scheduler.clone()));
 
         ArcWake::wake_by_ref(&task);
 
-        assert!(!queue.is_empty());
+        assert!(!scheduler.global_queue().is_empty());
+    }
+
+    #[test]
+    fn task_fail_invokes_failure_callback() {
+        let scheduler = Arc::new(Scheduler::new());
+        let failures = Arc::new(AtomicUsize::new(0));
+        let failures_clone = failures.clone();
+
+        let task = Task::new(Box::pin(async {}), scheduler).with_failure_callback(Box::new(
+            move |error| {
+                assert!(matches!(error, JoinError::Panicked { .. }));
+                failures_clone.fetch_add(1, Ordering::SeqCst);
+            },
+        ));
+        task.fail(JoinError::panicked(Box::new("failed")));
+
+        assert_eq!(failures.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn task_abort_marks_and_queues_once() {
+        let scheduler = Arc::new(Scheduler::new());
+        let task = Arc::new(Task::new(Box::pin(async {}), scheduler.clone()));
+
+        task.abort();
+        task.abort();
+
+        assert!(task.is_aborted());
+        assert_eq!(scheduler.global_queue().len(), 1);
+    }
+
+    #[test]
+    fn task_abort_reports_cancellation_immediately() {
+        let scheduler = Arc::new(Scheduler::new());
+        let cancellations = Arc::new(AtomicUsize::new(0));
+        let counter = cancellations.clone();
+        let task = Arc::new(
+            Task::new(Box::pin(async {}), scheduler).with_failure_callback(Box::new(
+                move |error| {
+                    assert!(matches!(error, JoinError::Cancelled));
+                    counter.fetch_add(1, Ordering::SeqCst);
+                },
+            )),
+        );
+
+        task.abort();
+        task.abort();
+
+        assert_eq!(cancellations.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn task_starts_without_last_worker() {
+        let scheduler = Arc::new(Scheduler::new());
+        let task = Task::new(Box::pin(async {}), scheduler);
+
+        assert_eq!(task.last_worker(), None);
+
+        task.set_last_worker(3);
+        assert_eq!(task.last_worker(), Some(3));
+    }
+
+    #[test]
+    fn high_priority_wake_uses_high_priority_queue() {
+        let scheduler = Arc::new(Scheduler::new());
+        let task = Arc::new(
+            Task::new(Box::pin(async {}), scheduler.clone()).with_priority(Priority::High),
+        );
+
+        ArcWake::wake_by_ref(&task);
+
+        assert!(scheduler.global_queue().is_empty());
+        assert_eq!(scheduler.high_priority_queue().len(), 1);
+    }
+
+    #[test]
+    fn unnamed_tasks_get_distinct_numeric_names() {
+        let scheduler = Arc::new(Scheduler::new());
+        let first = Task::new(Box::pin(async {}), scheduler.clone());
+        let second = Task::new(Box::pin(async {}), scheduler.clone());
+        let named = Task::new(Box::pin(async {}), scheduler).with_name(Arc::from("worker"));
+
+        assert!(first.name().parse::<u64>().is_ok());
+        assert_ne!(first.name(), second.name());
+        assert_eq!(&*named.name(), "worker");
     }
 }
@@ -1,37 +1,248 @@
+use std::cell::{Cell, RefCell};
+use std::hint;
+use std::iter;
 use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+use std::rc::Rc;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::task::{Context, Poll};
 use std::thread;
+use std::time::Instant;
 
-use crossbeam_deque::Injector;
+use crossbeam::utils::Backoff;
+use crossbeam_deque::{Steal, Stealer, Worker};
 use futures::task::waker_ref;
 
+use super::builder::IdleStrategy;
+use super::context;
+use super::metrics::WorkerStats;
+use super::scheduler::Scheduler;
+use super::spawner::Spawner;
 use super::task::Task;
+use crate::join_handle::JoinError;
 
-pub fn run_worker_loop(
+pub(super) type LocalQueue = Worker<Arc<Task>>;
+pub(super) type TaskStealer = Stealer<Arc<Task>>;
+
+struct WorkerContext {
+    worker_id: usize,
+    scheduler: Arc<Scheduler>,
+    local_queue: LocalQueue,
+    running_task: Cell<*const Task>,
+    /// Tasks taken from the local queue since the global queue was last
+    /// checked first.
+    local_pops: Cell<usize>,
+    stats: RefCell<WorkerStats>,
+}
+
+impl WorkerContext {
+    fn new(worker_id: usize, scheduler: Arc<Scheduler>, local_queue: LocalQueue) -> Self {
+        WorkerContext {
+            worker_id,
+            scheduler,
+            local_queue,
+            running_task: Cell::new(ptr::null()),
+            local_pops: Cell::new(0),
+            stats: RefCell::new(WorkerStats {
+                worker_id,
+                ..WorkerStats::default()
+            }),
+        }
+    }
+
+    /// Returns whether the global queue is due to be checked ahead of the
+    /// local one, restarting the count if so.
+    fn take_global_check(&self) -> bool {
+        if self.local_pops.get() < self.scheduler.global_queue_interval() {
+            return false;
+        }
+
+        self.local_pops.set(0);
+        true
+    }
+
+    fn last_ran(&self, task: &Task) -> bool {
+        Arc::ptr_eq(&self.scheduler, task.scheduler()) && task.last_worker() == Some(self.worker_id)
+    }
+
+    fn is_running(&self, task: &Task) -> bool {
+        ptr::eq(self.running_task.get(), task)
+    }
+}
+
+thread_local! {
+    static CURRENT_WORKER: RefCell<Option<Rc<WorkerContext>>> = const { RefCell::new(None) };
+}
+
+/// Pushes `task` onto the local queue of the current thread's worker if that
+/// worker was the last one to run it; otherwise hands the task back.
+///
+/// A task waking itself mid-poll (e.g. `yield_now`) is handed back too, so it
+/// lands behind the tasks waiting on the global queue instead of starving them.
+pub(super) fn push_to_local_queue(task: Arc<Task>) -> Result<(), Arc<Task>> {
+    let current = CURRENT_WORKER
+        .try_with(|current| current.borrow().clone())
+        .ok()
+        .flatten();
+
+    match current {
+        Some(context) if context.last_ran(&task) && !context.is_running(&task) => {
+            context.local_queue.push(task);
+            Ok(())
+        }
+        _ => Err(task),
+    }
+}
+
+/// Returns whether the current thread is running a worker loop.
+pub(crate) fn is_worker_thread() -> bool {
+    CURRENT_WORKER
+        .try_with(|current| current.borrow().is_some())
+        .unwrap_or(false)
+}
+
+pub(super) fn run_worker_loop(
     worker_id: usize,
-    global_queue: Arc<Injector<Arc<Task>>>,
-    is_shutdown: Arc<AtomicBool>,
-) {
+    spawner: Spawner,
+    local_queue: LocalQueue,
+    stealers: Arc<[TaskStealer]>,
+) -> WorkerStats {
+    let scheduler = spawner.scheduler().clone();
+    let context = Rc::new(WorkerContext::new(worker_id, scheduler, local_queue));
+    CURRENT_WORKER.with(|current| *current.borrow_mut() = Some(context.clone()));
+    context.scheduler.metrics().worker_started();
+    context.scheduler.observer().on_worker_started(worker_id);
+
+    let poll_budget = context.scheduler.poll_budget();
+    let idle_strategy = context.scheduler.idle_strategy();
+    let backoff = Backoff::new();
+    let mut polls_since_remote_check = 0;
+    let mut idle_spins = 0;
     loop {
-        match global_queue.steal() {
-            crossbeam_deque::Steal::Success(task) => {
+        let prefer_remote = polls_since_remote_check >= poll_budget;
+        if prefer_remote {
+            polls_since_remote_check = 0;
+        }
+
+        match find_task(&context, &stealers, prefer_remote) {
+            Some(task) => {
+                idle_spins = 0;
+                backoff.reset();
+                polls_since_remote_check += 1;
+                context.stats.borrow_mut().tasks_executed += 1;
+                task.set_last_worker(worker_id);
+                context.running_task.set(Arc::as_ptr(&task));
+                let guard = context::enter(spawner.clone());
                 execute_task(&task);
+                drop(guard);
+                context.running_task.set(ptr::null());
             }
-            crossbeam_deque::Steal::Empty => {
-                if is_shutdown.load(Ordering::SeqCst) {
+            None => {
+                if context.scheduler.is_shutdown() {
                     break;
                 }
-                thread::yield_now();
+                match idle_strategy {
+                    IdleStrategy::Spin => hint::spin_loop(),
+                    IdleStrategy::Yield => thread::yield_now(),
+                    IdleStrategy::Park => park(&context, &stealers),
+                    IdleStrategy::SpinThenPark { spins } if idle_spins < spins => {
+                        idle_spins += 1;
+                        backoff.snooze();
+                    }
+                    IdleStrategy::SpinThenPark { .. } => {
+                        idle_spins = 0;
+                        backoff.reset();
+                        park(&context, &stealers);
+                    }
+                }
             }
-            crossbeam_deque::Steal::Retry => continue,
         }
     }
-    println!("worker {} ended work", worker_id);
+
+    context.scheduler.metrics().worker_stopped();
+    context.scheduler.observer().on_worker_stopped(worker_id);
+    CURRENT_WORKER.with(|current| *current.borrow_mut() = None);
+    context.stats.take()
+}
+
+fn park(context: &WorkerContext, stealers: &[TaskStealer]) {
+    context.stats.borrow_mut().times_parked += 1;
+    context
+        .scheduler
+        .park_worker(|| has_pending_work(context, stealers));
+}
+
+/// Looks for work in the high-priority queue first, then the local queue, the
+/// global queue and the local queues of sibling workers. Every
+/// `global_queue_interval` local pops, one task is taken from the global
+/// queue before the local queue. With `prefer_remote` the local queue is
+/// checked last instead, once the worker's poll budget is used up. Retries
+/// until a steal attempt is conclusive so tasks racing between queues are
+/// never lost.
+fn find_task(
+    context: &WorkerContext,
+    stealers: &[TaskStealer],
+    prefer_remote: bool,
+) -> Option<Arc<Task>> {
+    let high_priority = iter::repeat_with(|| context.scheduler.high_priority_queue().steal())
+        .find(|steal| !steal.is_retry())
+        .and_then(Steal::success);
+    if high_priority.is_some() {
+        return high_priority;
+    }
+
+    if context.take_global_check()
+        && let Some(task) = iter::repeat_with(|| context.scheduler.global_queue().steal())
+            .find(|steal| !steal.is_retry())
+            .and_then(Steal::success)
+    {
+        return Some(task);
+    }
+
+    if !prefer_remote && let Some(task) = context.local_queue.pop() {
+        context.local_pops.set(context.local_pops.get() + 1);
+        return Some(task);
+    }
+
+    iter::repeat_with(|| {
+        context
+            .scheduler
+            .global_queue()
+            .steal_batch_and_pop(&context.local_queue)
+            .or_else(|| steal_from_siblings(context, stealers))
+    })
+    .find(|steal| !steal.is_retry())
+    .and_then(Steal::success)
+    .or_else(|| context.local_queue.pop())
+}
+
+fn has_pending_work(context: &WorkerContext, stealers: &[TaskStealer]) -> bool {
+    !context.local_queue.is_empty()
+        || !context.scheduler.global_queue().is_empty()
+        || !context.scheduler.high_priority_queue().is_empty()
+        || stealers.iter().any(|stealer| !stealer.is_empty())
+}
+
+fn steal_from_siblings(context: &WorkerContext, stealers: &[TaskStealer]) -> Steal<Arc<Task>> {
+    let steal: Steal<Arc<Task>> = stealers
+        .iter()
+        .enumerate()
+        .filter(|(sibling_id, _)| *sibling_id != context.worker_id)
+        .map(|(_, stealer)| stealer.steal_batch_and_pop(&context.local_queue))
+        .collect();
+
+    if stealers.len() > 1 {
+        let mut stats = context.stats.borrow_mut();
+        match steal {
+            Steal::Success(_) => stats.steals_succeeded += 1,
+            Steal::Empty => stats.steals_failed += 1,
+            Steal::Retry => {}
+        }
+    }
+    steal
 }
 
-fn execute_task(task: &Arc<Task>) {
+pub(super) fn execute_task(task: &Arc<Task>) {
     let waker = waker_ref(task);
     let mut context = Context::from_waker(&waker);
     let mut future_slot = task.future_slot().lock();
@@ -40,7 +251,22 @@ fn execute_task(task: &Arc<Task>) {
         return;
     };
 
+    if task.is_aborted() {
+        *future_slot = None;
+        task.scheduler().task_finished(task);
+        task.fail(JoinError::Cancelled);
+        return;
+    }
+
+    task.scheduler().task_started(task);
+    let poll_started = Instant::now();
     let poll_result = catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(&mut context)));
+    let poll_duration = poll_started.elapsed();
+    if poll_duration > task.scheduler().slow_poll_threshold() {
+        task.scheduler()
+            .observer()
+            .on_slow_poll(&task.name(), poll_duration);
+    }
 
     match poll_result {
         Ok(Poll::Pending) => {
@@ -48,10 +274,169 @@ fn execute_task(task: &Arc<Task>) {
         }
         Ok(Poll::Ready(())) => {
             *future_slot = None;
+            task.scheduler().metrics().task_completed();
+            task.scheduler().task_finished(task);
         }
-        Err(_) => {
+        Err(payload) => {
             *future_slot = None;
-            eprintln!("task panicked!");
+            task.scheduler().metrics().task_panicked();
+            task.scheduler().task_finished(task);
+            let task_name = task.name();
+            task.scheduler().observer().on_task_panicked(&task_name);
+            task.fail(JoinError::Panicked {
+                task_name: Some(task_name),
+                payload,
+            });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::task::Priority;
+    use futures::task::ArcWake;
+
+    fn create_context(worker_id: usize, scheduler: &Arc<Scheduler>) -> WorkerContext {
+        WorkerContext::new(worker_id, scheduler.clone(), Worker::new_fifo())
+    }
+
+    fn create_task(scheduler: &Arc<Scheduler>) -> Arc<Task> {
+        Arc::new(Task::new(Box::pin(async {}), scheduler.clone()))
+    }
+
+    #[test]
+    fn find_task_prefers_local_queue() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = create_context(0, &scheduler);
+        let local_task = create_task(&scheduler);
+
+        scheduler.push(create_task(&scheduler));
+        context.local_queue.push(local_task.clone());
+
+        let found = find_task(&context, &[], false).unwrap();
+        assert!(Arc::ptr_eq(&found, &local_task));
+    }
+
+    #[test]
+    fn find_task_prefers_global_queue_when_budget_spent() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = create_context(0, &scheduler);
+        let global_task = create_task(&scheduler);
+
+        context.local_queue.push(create_task(&scheduler));
+        scheduler.push(global_task.clone());
+
+        let found = find_task(&context, &[], true).unwrap();
+        assert!(Arc::ptr_eq(&found, &global_task));
+        assert_eq!(context.local_queue.len(), 1);
+    }
+
+    #[test]
+    fn find_task_falls_back_to_local_when_remote_empty() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = create_context(0, &scheduler);
+
+        context.local_queue.push(create_task(&scheduler));
+
+        assert!(find_task(&context, &[], true).is_some());
+    }
+
+    #[test]
+    fn find_task_prefers_high_priority_queue() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = create_context(0, &scheduler);
+        let high_task = Arc::new(
+            Task::new(Box::pin(async {}), scheduler.clone()).with_priority(Priority::High),
+        );
+
+        context.local_queue.push(create_task(&scheduler));
+        scheduler.push(create_task(&scheduler));
+        scheduler.push(high_task.clone());
+
+        let found = find_task(&context, &[], false).unwrap();
+        assert!(Arc::ptr_eq(&found, &high_task));
+    }
+
+    #[test]
+    fn find_task_falls_back_to_global_queue() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = create_context(0, &scheduler);
+
+        scheduler.push(create_task(&scheduler));
+
+        assert!(find_task(&context, &[], false).is_some());
+        assert!(scheduler.global_queue().is_empty());
+    }
+
+    #[test]
+    fn find_task_steals_from_siblings() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = create_context(0, &scheduler);
+        let sibling = create_context(1, &scheduler);
+
+        sibling.local_queue.push(create_task(&scheduler));
+        let stealers = [context.local_queue.stealer(), sibling.local_queue.stealer()];
+
+        assert!(find_task(&context, &stealers, false).is_some());
+        assert!(sibling.local_queue.is_empty());
+        assert_eq!(context.stats.borrow().steals_succeeded, 1);
+
+        assert!(find_task(&context, &stealers, false).is_none());
+        assert_eq!(context.stats.borrow().steals_failed, 1);
+    }
+
+    #[test]
+    fn find_task_returns_none_when_idle() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = create_context(0, &scheduler);
+
+        assert!(find_task(&context, &[], false).is_none());
+    }
+
+    #[test]
+    fn wake_on_last_worker_uses_local_queue() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = Rc::new(create_context(2, &scheduler));
+        CURRENT_WORKER.with(|current| *current.borrow_mut() = Some(context.clone()));
+
+        let task = create_task(&scheduler);
+        task.set_last_worker(2);
+        ArcWake::wake_by_ref(&task);
+
+        CURRENT_WORKER.with(|current| *current.borrow_mut() = None);
+        assert_eq!(context.local_queue.len(), 1);
+        assert!(scheduler.global_queue().is_empty());
+    }
+
+    #[test]
+    fn wake_on_other_worker_uses_global_queue() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = Rc::new(create_context(0, &scheduler));
+        CURRENT_WORKER.with(|current| *current.borrow_mut() = Some(context.clone()));
+
+        let task = create_task(&scheduler);
+        task.set_last_worker(1);
+        ArcWake::wake_by_ref(&task);
+
+        CURRENT_WORKER.with(|current| *current.borrow_mut() = None);
+        assert!(context.local_queue.is_empty());
+        assert_eq!(scheduler.global_queue().len(), 1);
+    }
+
+    #[test]
+    fn self_wake_while_running_uses_global_queue() {
+        let scheduler = Arc::new(Scheduler::new());
+        let context = Rc::new(create_context(0, &scheduler));
+        CURRENT_WORKER.with(|current| *current.borrow_mut() = Some(context.clone()));
+
+        let task = create_task(&scheduler);
+        task.set_last_worker(0);
+        context.running_task.set(Arc::as_ptr(&task));
+        ArcWake::wake_by_ref(&task);
+
+        CURRENT_WORKER.with(|current| *current.borrow_mut() = None);
+        assert!(context.local_queue.is_empty());
+        assert_eq!(scheduler.global_queue().len(), 1);
+    }
+}
@@ -0,0 +1,61 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pub struct YieldNow {
+    has_yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.has_yielded {
+            return Poll::Ready(());
+        }
+
+        self.has_yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Gives other queued tasks a chance to run before continuing.
+///
+/// The task is re-queued behind everything already waiting on the global
+/// queue, through the same wakeup path as timers.
+///
+/// # Example
+///
+/// ```no_run
+/// use runtime::yield_now;
+///
+/// async fn crunch(items: Vec<u64>) -> u64 {
+///     let mut sum = 0;
+///     for (index, item) in items.into_iter().enumerate() {
+///         sum += item;
+///         if index % 1024 == 0 {
+///             yield_now().await;
+///         }
+///     }
+///     sum
+/// }
+/// ```
+pub fn yield_now() -> YieldNow {
+    YieldNow { has_yielded: false }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::noop_waker_ref;
+
+    #[test]
+    fn yield_now_is_pending_once() {
+        let mut context = Context::from_waker(noop_waker_ref());
+        let mut future = yield_now();
+
+        assert!(Pin::new(&mut future).poll(&mut context).is_pending());
+        assert!(Pin::new(&mut future).poll(&mut context).is_ready());
+    }
+}
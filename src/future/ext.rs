@@ -0,0 +1,119 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::cancellation::{CancellationToken, CancelledFuture};
+use crate::timer::{Timeout, timeout};
+
+/// Runtime-aware adapters for every [`Future`].
+///
+/// # Example
+///
+/// ```
+/// use runtime::{CancellationToken, Runtime, RuntimeFutureExt, sleep};
+/// use std::time::Duration;
+///
+/// let runtime = Runtime::new();
+/// let token = CancellationToken::new();
+/// token.cancel();
+///
+/// runtime.block_on(async {
+///     let slow = sleep(Duration::from_secs(60));
+///     assert!(slow.timeout(Duration::from_millis(5)).await.is_err());
+///
+///     let stopped = sleep(Duration::from_secs(60)).cancel_on(token).await;
+///     assert!(stopped.is_none());
+/// });
+/// ```
+pub trait RuntimeFutureExt: Future {
+    /// Same as [`timeout(duration, self)`](crate::timeout).
+    fn timeout(self, duration: Duration) -> Timeout<Self>
+    where
+        Self: Sized,
+    {
+        timeout(duration, self)
+    }
+
+    /// Runs the future until it completes or `token` is cancelled, like
+    /// [`CancellationToken::run_until_cancelled`] but owning the token.
+    fn cancel_on(self, token: CancellationToken) -> CancelOn<Self>
+    where
+        Self: Sized,
+    {
+        CancelOn {
+            future: Box::pin(self),
+            cancelled: token.cancelled_owned(),
+        }
+    }
+}
+
+impl<F: Future + ?Sized> RuntimeFutureExt for F {}
+
+/// Future returned by [`RuntimeFutureExt::cancel_on`]. Resolves to `None`
+/// if the token is cancelled first, in which case the inner future is
+/// dropped along with this one.
+pub struct CancelOn<F> {
+    future: Pin<Box<F>>,
+    cancelled: CancelledFuture,
+}
+
+impl<F: Future> Future for CancelOn<F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if Pin::new(&mut self.cancelled).poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        self.future.as_mut().poll(cx).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Runtime;
+    use crate::timer::{Elapsed, sleep};
+
+    #[test]
+    fn timeout_passes_through_fast_output() {
+        let runtime = Runtime::new();
+
+        let result = runtime.block_on(async { 7 }.timeout(Duration::from_secs(1)));
+
+        assert_eq!(result, Ok(7));
+    }
+
+    #[test]
+    fn timeout_elapses_for_slow_future() {
+        let runtime = Runtime::new();
+
+        let result = runtime.block_on(sleep(Duration::from_secs(60)).timeout(Duration::ZERO));
+
+        assert_eq!(result, Err(Elapsed));
+    }
+
+    #[test]
+    fn cancel_on_returns_output_when_not_cancelled() {
+        let runtime = Runtime::new();
+
+        let result = runtime.block_on(async { "done" }.cancel_on(CancellationToken::new()));
+
+        assert_eq!(result, Some("done"));
+    }
+
+    #[test]
+    fn cancel_on_stops_when_token_is_cancelled() {
+        let runtime = Runtime::new();
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+
+        let result = runtime.block_on(async move {
+            let pending = std::future::pending::<()>().cancel_on(token);
+            canceller.cancel();
+            pending.await
+        });
+
+        assert_eq!(result, None);
+    }
+}
@@ -0,0 +1,182 @@
+use std::future::{Future, poll_fn};
+use std::pin::pin;
+use std::task::Poll;
+
+/// Runs `a` and `b` concurrently on the current task and returns both
+/// outputs once both have completed.
+///
+/// A future that has completed is not polled again.
+///
+/// # Example
+///
+/// ```no_run
+/// use runtime::{join2, sleep};
+/// use std::time::Duration;
+///
+/// async fn example() {
+///     // Takes about one second, not two.
+///     join2(sleep(Duration::from_secs(1)), sleep(Duration::from_secs(1))).await;
+/// }
+/// ```
+pub async fn join2<A, B>(a: A, b: B) -> (A::Output, B::Output)
+where
+    A: Future,
+    B: Future,
+{
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+    let mut a_output = None;
+    let mut b_output = None;
+
+    poll_fn(|cx| {
+        if a_output.is_none()
+            && let Poll::Ready(output) = a.as_mut().poll(cx)
+        {
+            a_output = Some(output);
+        }
+        if b_output.is_none()
+            && let Poll::Ready(output) = b.as_mut().poll(cx)
+        {
+            b_output = Some(output);
+        }
+
+        if a_output.is_some() && b_output.is_some() {
+            Poll::Ready((a_output.take().unwrap(), b_output.take().unwrap()))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Like [`join2`], but for fallible futures: returns the first error as
+/// soon as either future fails, dropping the other one, or both outputs once
+/// both have succeeded.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::future::try_join2;
+///
+/// let runtime = Runtime::new();
+///
+/// let both = runtime.block_on(try_join2(async { Ok::<_, String>(1) }, async { Ok(2) }));
+/// assert_eq!(both, Ok((1, 2)));
+///
+/// let failed = runtime.block_on(try_join2(
+///     async { Err::<u32, _>("no connection") },
+///     std::future::pending::<Result<u32, &str>>(),
+/// ));
+/// assert_eq!(failed, Err("no connection"));
+/// ```
+pub async fn try_join2<A, B, T, U, E>(a: A, b: B) -> Result<(T, U), E>
+where
+    A: Future<Output = Result<T, E>>,
+    B: Future<Output = Result<U, E>>,
+{
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+    let mut a_output = None;
+    let mut b_output = None;
+
+    poll_fn(|cx| {
+        if a_output.is_none()
+            && let Poll::Ready(output) = a.as_mut().poll(cx)
+        {
+            a_output = Some(output?);
+        }
+        if b_output.is_none()
+            && let Poll::Ready(output) = b.as_mut().poll(cx)
+        {
+            b_output = Some(output?);
+        }
+
+        if a_output.is_some() && b_output.is_some() {
+            Poll::Ready(Ok((a_output.take().unwrap(), b_output.take().unwrap())))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{Runtime, yield_now};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn join2_returns_both_outputs() {
+        let runtime = Runtime::new();
+
+        let result = runtime.block_on(join2(async { 1 }, async { "two" }));
+
+        assert_eq!(result, (1, "two"));
+    }
+
+    #[test]
+    fn completed_future_is_not_polled_again() {
+        let runtime = Runtime::new();
+        let polls = Arc::new(AtomicUsize::new(0));
+        let polls_clone = polls.clone();
+
+        let counted = std::future::poll_fn(move |_| {
+            polls_clone.fetch_add(1, Ordering::SeqCst);
+            Poll::Ready(())
+        });
+        runtime.block_on(join2(counted, async {
+            for _ in 0..3 {
+                yield_now().await;
+            }
+        }));
+
+        assert_eq!(polls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn try_join2_returns_both_outputs_when_ok() {
+        let runtime = Runtime::new();
+
+        let result = runtime.block_on(try_join2(
+            async {
+                yield_now().await;
+                Ok::<_, &str>(1)
+            },
+            async { Ok("two") },
+        ));
+
+        assert_eq!(result, Ok((1, "two")));
+    }
+
+    #[test]
+    fn try_join2_returns_first_error_and_drops_other() {
+        let runtime = Runtime::new();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let guard = DropCounter(dropped.clone());
+
+        let result = runtime.block_on(try_join2(
+            async move {
+                let _guard = guard;
+                std::future::pending::<Result<u32, &str>>().await
+            },
+            async {
+                yield_now().await;
+                Err::<u32, _>("failed")
+            },
+        ));
+
+        assert_eq!(result, Err("failed"));
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    }
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
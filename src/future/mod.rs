@@ -0,0 +1,17 @@
+mod ext;
+mod join;
+mod pending;
+mod poll_fn;
+mod ready;
+mod select;
+mod stream;
+mod unordered;
+
+pub use ext::{CancelOn, RuntimeFutureExt};
+pub use join::{join2, try_join2};
+pub use pending::{Pending, pending};
+pub use poll_fn::{PollFn, poll_fn};
+pub use ready::{Ready, ready};
+pub use select::{Either, select_biased2, select2};
+pub use stream::{ForEach, Next, Stream, StreamExt};
+pub use unordered::Unordered;
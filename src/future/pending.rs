@@ -0,0 +1,53 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Creates a future that never resolves. Handy as a placeholder branch in
+/// [`select2`](super::select2) or for stubbing in tests.
+///
+/// # Example
+///
+/// ```
+/// use runtime::future::{pending, ready};
+/// use runtime::{Either, Runtime, select2};
+///
+/// let runtime = Runtime::new();
+///
+/// let winner = runtime.block_on(select2(pending::<()>(), ready("ready")));
+/// assert!(matches!(winner, Either::Right("ready")));
+/// ```
+pub fn pending<T>() -> Pending<T> {
+    Pending {
+        _output: PhantomData,
+    }
+}
+
+/// Future returned by [`pending`].
+pub struct Pending<T> {
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<T> Future for Pending<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<T> {
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Waker;
+
+    #[test]
+    fn pending_never_resolves() {
+        let mut context = Context::from_waker(Waker::noop());
+        let mut future = pending::<u32>();
+
+        for _ in 0..3 {
+            assert!(Pin::new(&mut future).poll(&mut context).is_pending());
+        }
+    }
+}
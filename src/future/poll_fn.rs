@@ -0,0 +1,102 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Creates a future that calls `f` each time it is polled, for ad-hoc
+/// futures that don't warrant a struct of their own.
+///
+/// `f` must arrange for the task to be woken before returning
+/// `Poll::Pending`, like any [`Future::poll`].
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::future::poll_fn;
+/// use std::task::Poll;
+///
+/// let runtime = Runtime::new();
+/// let mut polls = 0;
+///
+/// let output = runtime.block_on(poll_fn(|cx| {
+///     polls += 1;
+///     if polls < 3 {
+///         cx.waker().wake_by_ref();
+///         return Poll::Pending;
+///     }
+///     Poll::Ready(polls)
+/// }));
+/// assert_eq!(output, 3);
+/// ```
+pub fn poll_fn<T, F>(f: F) -> PollFn<F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<T>,
+{
+    PollFn { f }
+}
+
+/// Future returned by [`poll_fn`].
+pub struct PollFn<F> {
+    f: F,
+}
+
+// The closure is never pinned, only called through `&mut`.
+impl<F> Unpin for PollFn<F> {}
+
+impl<T, F> Future for PollFn<F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<T>,
+{
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        (self.f)(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Runtime;
+
+    #[test]
+    fn countdown_wakes_itself_until_zero() {
+        let runtime = Runtime::new();
+        let mut remaining = 5;
+        let mut polls = 0;
+
+        runtime.block_on(poll_fn(|cx| {
+            polls += 1;
+            if remaining == 0 {
+                return Poll::Ready(());
+            }
+            remaining -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }));
+
+        assert_eq!(remaining, 0);
+        assert_eq!(polls, 6);
+    }
+
+    #[test]
+    fn countdown_runs_as_a_spawned_task() {
+        let runtime = Runtime::new();
+        let mut remaining = 100_u32;
+        let handle = runtime
+            .spawner()
+            .spawn(poll_fn(move |cx| {
+                if remaining == 0 {
+                    return Poll::Ready("liftoff");
+                }
+                remaining -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }))
+            .unwrap();
+        let _workers = runtime.run(2);
+
+        assert_eq!(handle.join().unwrap(), "liftoff");
+        runtime.shutdown();
+    }
+}
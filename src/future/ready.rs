@@ -0,0 +1,69 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Creates a future that resolves to `value` on its first poll.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::future::ready;
+///
+/// let runtime = Runtime::new();
+///
+/// assert_eq!(runtime.block_on(ready(5)), 5);
+/// ```
+pub fn ready<T>(value: T) -> Ready<T> {
+    Ready { value: Some(value) }
+}
+
+/// Future returned by [`ready`].
+///
+/// # Panics
+///
+/// Panics if polled again after yielding its value.
+#[derive(Debug, Clone)]
+pub struct Ready<T> {
+    value: Option<T>,
+}
+
+impl<T> Unpin for Ready<T> {}
+
+impl<T> Future for Ready<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<T> {
+        Poll::Ready(self.value.take().expect("Ready polled after completion"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Runtime;
+    use std::task::Waker;
+
+    #[test]
+    fn ready_resolves_inside_a_task() {
+        let runtime = Runtime::new();
+        let handle = runtime
+            .spawner()
+            .spawn(async { ready(5).await == 5 })
+            .unwrap();
+        let _workers = runtime.run(1);
+
+        assert!(handle.join().unwrap());
+        runtime.shutdown();
+    }
+
+    #[test]
+    #[should_panic(expected = "Ready polled after completion")]
+    fn ready_panics_when_polled_twice() {
+        let mut context = Context::from_waker(Waker::noop());
+        let mut future = ready(5);
+
+        assert_eq!(Pin::new(&mut future).poll(&mut context), Poll::Ready(5));
+        let _ = Pin::new(&mut future).poll(&mut context);
+    }
+}
@@ -0,0 +1,175 @@
+use std::cell::Cell;
+use std::future::{Future, poll_fn};
+use std::pin::pin;
+use std::task::Poll;
+
+thread_local! {
+    /// Flipped by every [`select2`] started on this thread to pick which
+    /// future it polls first.
+    static POLL_LEFT_FIRST: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Output of [`select2`]: which of the two futures finished first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+/// Waits for the first of `a` and `b` to complete and returns its output.
+/// The other future is dropped without being polled again.
+///
+/// Fair: successive calls alternate which future is polled first, so when
+/// both are ready neither side wins every time. Use [`select_biased2`] when
+/// one branch must take precedence.
+///
+/// # Example
+///
+/// ```no_run
+/// use runtime::{CancellationToken, Either, select2, sleep};
+/// use std::time::Duration;
+///
+/// async fn work_or_cancel(token: CancellationToken) {
+///     match select2(sleep(Duration::from_secs(5)), token.cancelled()).await {
+///         Either::Left(()) => println!("work finished"),
+///         Either::Right(()) => println!("cancelled"),
+///     }
+/// }
+/// ```
+pub async fn select2<A, B>(a: A, b: B) -> Either<A::Output, B::Output>
+where
+    A: Future,
+    B: Future,
+{
+    let left_first = POLL_LEFT_FIRST.with(|flag| flag.replace(!flag.get()));
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+
+    poll_fn(|cx| {
+        if left_first && let Poll::Ready(output) = a.as_mut().poll(cx) {
+            return Poll::Ready(Either::Left(output));
+        }
+        if let Poll::Ready(output) = b.as_mut().poll(cx) {
+            return Poll::Ready(Either::Right(output));
+        }
+        if !left_first && let Poll::Ready(output) = a.as_mut().poll(cx) {
+            return Poll::Ready(Either::Left(output));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+/// Like [`select2`], but guarantees that `a` is polled before `b` every
+/// time, and `b` only when `a` is pending. `a` therefore always wins when
+/// both are ready, which suits a shutdown branch that must take priority
+/// over work.
+///
+/// # Example
+///
+/// ```no_run
+/// use runtime::future::select_biased2;
+/// use runtime::{CancellationToken, Either, sleep};
+/// use std::time::Duration;
+///
+/// async fn work_unless_cancelled(token: CancellationToken) {
+///     loop {
+///         match select_biased2(token.cancelled(), sleep(Duration::from_millis(10))).await {
+///             Either::Left(()) => break,
+///             Either::Right(()) => println!("tick"),
+///         }
+///     }
+/// }
+/// ```
+pub async fn select_biased2<A, B>(a: A, b: B) -> Either<A::Output, B::Output>
+where
+    A: Future,
+    B: Future,
+{
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = a.as_mut().poll(cx) {
+            return Poll::Ready(Either::Left(output));
+        }
+        if let Poll::Ready(output) = b.as_mut().poll(cx) {
+            return Poll::Ready(Either::Right(output));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Runtime;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn either_side_can_win_when_both_ready() {
+        let runtime = Runtime::new();
+
+        let results: Vec<_> = (0..4)
+            .map(|_| runtime.block_on(select2(async { 1 }, async { "two" })))
+            .collect();
+
+        assert!(results.contains(&Either::Left(1)));
+        assert!(results.contains(&Either::Right("two")));
+    }
+
+    #[test]
+    fn loser_is_dropped() {
+        let runtime = Runtime::new();
+        let dropped = Arc::new(AtomicBool::new(false));
+        let guard = DropFlag(dropped.clone());
+
+        let result = runtime.block_on(select2(
+            async move {
+                let _guard = guard;
+                std::future::pending::<()>().await;
+            },
+            async { 7 },
+        ));
+
+        assert_eq!(result, Either::Right(7));
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn biased_always_picks_first_when_both_ready() {
+        let runtime = Runtime::new();
+
+        for _ in 0..100 {
+            let result = runtime.block_on(select_biased2(async { 1 }, async { "two" }));
+            assert_eq!(result, Either::Left(1));
+        }
+    }
+
+    #[test]
+    fn biased_skips_second_while_first_is_ready() {
+        let runtime = Runtime::new();
+        let second_polls = AtomicUsize::new(0);
+
+        let result = runtime.block_on(select_biased2(
+            async { 1 },
+            std::future::poll_fn(|_| {
+                second_polls.fetch_add(1, Ordering::SeqCst);
+                std::task::Poll::<()>::Pending
+            }),
+        ));
+
+        assert_eq!(result, Either::Left(1));
+        assert_eq!(second_polls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,190 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// An asynchronous sequence of values, the async counterpart of
+/// [`Iterator`].
+///
+/// Implemented by the channel receivers in [`crate::sync`]; the adapters
+/// live on [`StreamExt`].
+pub trait Stream {
+    type Item;
+
+    /// Attempts to pull out the next value. Returns `Ready(None)` once the
+    /// stream is exhausted, and `Pending` after arranging for the task to be
+    /// woken when a value may be available.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+impl<S: Stream + Unpin + ?Sized> Stream for &mut S {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        Pin::new(&mut **self).poll_next(cx)
+    }
+}
+
+/// Convenience methods for every [`Stream`].
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::future::StreamExt;
+/// use runtime::sync::mpsc;
+///
+/// let runtime = Runtime::new();
+/// let (sender, mut receiver) = mpsc::channel(4);
+///
+/// runtime.block_on(async {
+///     sender.send(1).await.unwrap();
+///     sender.send(2).await.unwrap();
+///     drop(sender);
+///
+///     assert_eq!(receiver.next().await, Some(1));
+///
+///     let mut rest = Vec::new();
+///     receiver.for_each(|value| rest.push(value)).await;
+///     assert_eq!(rest, vec![2]);
+/// });
+/// ```
+pub trait StreamExt: Stream {
+    /// Waits for the next value, or `None` once the stream is exhausted.
+    fn next(&mut self) -> Next<'_, Self>
+    where
+        Self: Unpin,
+    {
+        Next { stream: self }
+    }
+
+    /// Calls `f` with every value until the stream is exhausted.
+    fn for_each<F>(self, f: F) -> ForEach<Self, F>
+    where
+        Self: Sized + Unpin,
+        F: FnMut(Self::Item),
+    {
+        ForEach { stream: self, f }
+    }
+}
+
+impl<S: Stream + ?Sized> StreamExt for S {}
+
+/// Future returned by [`StreamExt::next`].
+pub struct Next<'a, S: ?Sized> {
+    stream: &'a mut S,
+}
+
+impl<S: Stream + Unpin + ?Sized> Future for Next<'_, S> {
+    type Output = Option<S::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.stream).poll_next(cx)
+    }
+}
+
+/// Future returned by [`StreamExt::for_each`].
+pub struct ForEach<S, F> {
+    stream: S,
+    f: F,
+}
+
+impl<S: Unpin, F> Unpin for ForEach<S, F> {}
+
+impl<S, F> Future for ForEach<S, F>
+where
+    S: Stream + Unpin,
+    F: FnMut(S::Item),
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => (this.f)(item),
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Runtime;
+
+    /// Yields `0..end`, returning `Pending` once before every value.
+    struct CountUp {
+        next: u32,
+        end: u32,
+        ready: bool,
+    }
+
+    impl CountUp {
+        fn new(end: u32) -> Self {
+            CountUp {
+                next: 0,
+                end,
+                ready: false,
+            }
+        }
+    }
+
+    impl Stream for CountUp {
+        type Item = u32;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u32>> {
+            if self.next == self.end {
+                return Poll::Ready(None);
+            }
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            self.ready = false;
+            self.next += 1;
+            Poll::Ready(Some(self.next - 1))
+        }
+    }
+
+    #[test]
+    fn next_yields_values_then_none() {
+        let runtime = Runtime::new();
+        let mut stream = CountUp::new(2);
+
+        let values = runtime.block_on(async {
+            (
+                stream.next().await,
+                stream.next().await,
+                stream.next().await,
+            )
+        });
+
+        assert_eq!(values, (Some(0), Some(1), None));
+    }
+
+    #[test]
+    fn for_each_visits_every_value() {
+        let runtime = Runtime::new();
+        let mut values = Vec::new();
+
+        runtime.block_on(CountUp::new(4).for_each(|value| values.push(value)));
+
+        assert_eq!(values, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn for_each_works_through_a_mutable_reference() {
+        let runtime = Runtime::new();
+        let mut stream = CountUp::new(3);
+        let mut sum = 0;
+
+        runtime.block_on((&mut stream).for_each(|value| sum += value));
+
+        assert_eq!(sum, 3);
+        assert!(runtime.block_on(stream.next()).is_none());
+    }
+}
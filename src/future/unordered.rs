@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+use std::future::{Future, poll_fn};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+
+use parking_lot::Mutex;
+
+use crate::join_handle::{JoinError, JoinHandle};
+use crate::sync::AtomicWaker;
+
+/// A growable collection of [`JoinHandle`]s that yields results as the
+/// tasks finish, regardless of the order they were pushed in.
+///
+/// Each handle gets a waker of its own that queues the handle's slot when
+/// its task completes, so [`next`](Self::next) only polls handles that were
+/// woken and returns results in the order the tasks finished.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::future::Unordered;
+///
+/// let runtime = Runtime::new();
+/// let spawner = runtime.spawner();
+/// let mut pending = Unordered::new();
+/// pending.push(spawner.spawn(async { 1 }).unwrap());
+/// pending.push(spawner.spawn(async { 2 }).unwrap());
+///
+/// let sum = runtime.block_on(async {
+///     let mut sum = 0;
+///     while let Some(result) = pending.next().await {
+///         sum += result.unwrap();
+///     }
+///     sum
+/// });
+///
+/// assert_eq!(sum, 3);
+/// ```
+pub struct Unordered<T> {
+    slots: Vec<Option<Slot<T>>>,
+    free_slots: Vec<usize>,
+    len: usize,
+    ready: Arc<ReadyQueue>,
+}
+
+struct Slot<T> {
+    handle: JoinHandle<T>,
+    waker: Arc<SlotWaker>,
+}
+
+/// Slots whose handles were woken since they were last polled.
+struct ReadyQueue {
+    slots: Mutex<VecDeque<usize>>,
+    /// The task awaiting [`Unordered::next`].
+    waker: AtomicWaker,
+}
+
+/// Waker handed to a single handle; queues its slot when woken.
+struct SlotWaker {
+    slot: usize,
+    is_queued: AtomicBool,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Wake for SlotWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.is_queued.swap(true, Ordering::SeqCst) {
+            self.ready.slots.lock().push_back(self.slot);
+            self.ready.waker.wake();
+        }
+    }
+}
+
+impl<T> Unordered<T> {
+    pub fn new() -> Self {
+        Unordered {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            len: 0,
+            ready: Arc::new(ReadyQueue {
+                slots: Mutex::new(VecDeque::new()),
+                waker: AtomicWaker::new(),
+            }),
+        }
+    }
+
+    pub fn push(&mut self, handle: JoinHandle<T>) {
+        let slot = self.free_slots.pop().unwrap_or_else(|| {
+            self.slots.push(None);
+            self.slots.len() - 1
+        });
+        let waker = Arc::new(SlotWaker {
+            slot,
+            is_queued: AtomicBool::new(false),
+            ready: self.ready.clone(),
+        });
+        // Queued straight away so the first poll registers the slot's waker.
+        waker.wake_by_ref();
+        self.slots[slot] = Some(Slot { handle, waker });
+        self.len += 1;
+    }
+
+    /// Waits for the next pushed task to finish and returns its result, or
+    /// `None` if no handles are left.
+    pub async fn next(&mut self) -> Option<Result<T, JoinError>> {
+        poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, JoinError>>> {
+        if self.len == 0 {
+            return Poll::Ready(None);
+        }
+
+        self.ready.waker.register(cx.waker());
+        loop {
+            let Some(index) = self.ready.slots.lock().pop_front() else {
+                return Poll::Pending;
+            };
+            // A wakeup for a slot that has since been emptied is stale.
+            let Some(slot) = &mut self.slots[index] else {
+                continue;
+            };
+
+            slot.waker.is_queued.store(false, Ordering::SeqCst);
+            let waker = Waker::from(slot.waker.clone());
+            let mut slot_cx = Context::from_waker(&waker);
+            if let Poll::Ready(result) = Pin::new(&mut slot.handle).poll(&mut slot_cx) {
+                self.slots[index] = None;
+                self.free_slots.push(index);
+                self.len -= 1;
+                return Poll::Ready(Some(result));
+            }
+        }
+    }
+
+    /// Iterates over the handles still in the collection.
+    pub(crate) fn handles(&self) -> impl Iterator<Item = &JoinHandle<T>> {
+        self.slots.iter().flatten().map(|slot| &slot.handle)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T> Default for Unordered<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<JoinHandle<T>> for Unordered<T> {
+    fn from_iter<I: IntoIterator<Item = JoinHandle<T>>>(handles: I) -> Self {
+        let mut unordered = Unordered::new();
+        for handle in handles {
+            unordered.push(handle);
+        }
+        unordered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Runtime;
+
+    #[test]
+    fn empty_collection_yields_none() {
+        let runtime = Runtime::new();
+        let mut unordered: Unordered<()> = Unordered::new();
+
+        assert!(runtime.block_on(unordered.next()).is_none());
+    }
+
+    #[test]
+    fn collects_from_iterator() {
+        let runtime = Runtime::new();
+        let spawner = runtime.spawner();
+        let mut unordered: Unordered<_> = (0..3)
+            .map(|i| spawner.spawn(async move { i }).unwrap())
+            .collect();
+
+        assert_eq!(unordered.len(), 3);
+        let mut results = runtime.block_on(async {
+            let mut results = Vec::new();
+            while let Some(result) = unordered.next().await {
+                results.push(result.unwrap());
+            }
+            results
+        });
+        results.sort();
+
+        assert_eq!(results, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn yields_results_in_completion_order() {
+        let mut unordered = Unordered::new();
+        let notifiers: Vec<_> = (0..3)
+            .map(|_| {
+                let (handle, notifier) = JoinHandle::new();
+                unordered.push(handle);
+                notifier
+            })
+            .collect();
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(unordered.poll_next(&mut context).is_pending());
+
+        notifiers[2].complete(Ok(2));
+        notifiers[0].complete(Ok(0));
+
+        let mut order = Vec::new();
+        while let Poll::Ready(Some(result)) = unordered.poll_next(&mut context) {
+            order.push(result.unwrap());
+        }
+        assert_eq!(order, vec![2, 0]);
+        assert_eq!(unordered.len(), 1);
+    }
+
+    #[test]
+    fn only_woken_handles_are_queued() {
+        let mut unordered = Unordered::new();
+        let notifiers: Vec<_> = (0..100)
+            .map(|_| {
+                let (handle, notifier) = JoinHandle::new();
+                unordered.push(handle);
+                notifier
+            })
+            .collect();
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(unordered.poll_next(&mut context).is_pending());
+
+        notifiers[42].complete(Ok(42));
+
+        assert_eq!(*unordered.ready.slots.lock(), [42]);
+    }
+}
@@ -1,35 +1,345 @@
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex};
+use std::any::Any;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::task::{Context, Poll, Waker};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+
+use crate::executor::{Task, is_worker_thread};
+use crate::sync::AtomicWaker;
 
 pub struct JoinHandle<T> {
     state: Arc<JoinState<T>>,
+    abort_handle: AbortHandle,
+    /// Set once the result has been handed out, so a later poll panics
+    /// instead of waiting forever.
+    is_taken: bool,
+}
+
+/// Cancels a task without owning its output.
+///
+/// Obtained from [`JoinHandle::abort_handle`]. Clones can be shared freely,
+/// and aborting from several of them concurrently is safe and idempotent.
+#[derive(Clone)]
+pub struct AbortHandle {
+    task: Option<Arc<Task>>,
+}
+
+impl AbortHandle {
+    /// Same as [`JoinHandle::abort`].
+    pub fn abort(&self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
 }
 
 pub(crate) struct JoinState<T> {
     result: Mutex<Option<Result<T, JoinError>>>,
-    waker: Mutex<Option<Waker>>,
+    is_complete: AtomicBool,
+    completed: Condvar,
+    waker: AtomicWaker,
 }
 
 impl<T> JoinHandle<T> {
     pub(crate) fn new() -> (Self, JoinNotifier<T>) {
         let state = Arc::new(JoinState {
             result: Mutex::new(None),
-            waker: Mutex::new(None),
+            is_complete: AtomicBool::new(false),
+            completed: Condvar::new(),
+            waker: AtomicWaker::new(),
         });
 
         let handle = JoinHandle {
             state: state.clone(),
+            abort_handle: AbortHandle { task: None },
+            is_taken: false,
         };
         let notifier = JoinNotifier { state };
 
         (handle, notifier)
     }
 
+    pub(crate) fn with_task(mut self, task: Arc<Task>) -> Self {
+        self.abort_handle = AbortHandle { task: Some(task) };
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.is_complete.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether the task finished by panicking. `false` while the
+    /// task is still running or once the result has been taken.
+    pub fn is_panicked(&self) -> bool {
+        matches!(
+            *self.state.result.lock(),
+            Some(Err(JoinError::Panicked { .. }))
+        )
+    }
+
+    /// Returns whether the task was aborted. `false` while the task is still
+    /// running or once the result has been taken.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(*self.state.result.lock(), Some(Err(JoinError::Cancelled)))
+    }
+
+    /// Cancels the task. This handle resolves to [`JoinError::Cancelled`]
+    /// right away, and the task's future is dropped without being polled
+    /// the next time a worker picks it up. Does nothing if the task already
+    /// finished.
+    pub fn abort(&self) {
+        if !self.is_finished() {
+            self.abort_handle.abort();
+        }
+    }
+
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort_handle.clone()
+    }
+
+    /// Wraps the handle so that dropping it aborts the task, tying the
+    /// task's lifetime to the owner's scope.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let task = runtime
+    ///     .spawner()
+    ///     .spawn(std::future::pending::<()>())
+    ///     .unwrap()
+    ///     .abort_on_drop();
+    /// let abort_handle = task.abort_handle();
+    ///
+    /// drop(task); // the task is cancelled here
+    /// # let _ = abort_handle;
+    /// ```
+    pub fn abort_on_drop(self) -> OwnedTask<T> {
+        OwnedTask { handle: Some(self) }
+    }
+
+    /// Waits for the task through a borrow, leaving the handle usable for
+    /// [`is_finished`](Self::is_finished) or [`abort`](Self::abort)
+    /// afterwards. Same as awaiting `&mut handle`.
+    ///
+    /// # Panics
+    ///
+    /// The result is handed out only once: awaiting the handle again after
+    /// it resolved panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let mut handle = runtime.spawner().spawn(async { 42 }).unwrap();
+    ///
+    /// assert_eq!(runtime.block_on(handle.wait_ref()).unwrap(), 42);
+    /// assert!(handle.is_finished());
+    /// ```
+    pub fn wait_ref(&mut self) -> impl Future<Output = Result<T, JoinError>> + '_ {
+        self
+    }
+
+    /// Transforms a successful result with `f` when the returned handle is
+    /// awaited. Errors pass through unchanged, and no extra task is spawned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.spawner().spawn(async { 21 }).unwrap();
+    ///
+    /// let doubled = handle.map(|value| value * 2);
+    /// assert_eq!(runtime.block_on(doubled).unwrap(), 42);
+    /// ```
+    pub fn map<U, F>(self, f: F) -> MappedJoinHandle<T, F>
+    where
+        F: FnOnce(T) -> U,
+    {
+        MappedJoinHandle {
+            handle: self,
+            f: Some(f),
+        }
+    }
+
+    /// Blocks the current thread until the task finishes and returns its
+    /// result. Meant for synchronous code such as `main`; async code should
+    /// `.await` the handle instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a runtime worker thread, since blocking a
+    /// worker can deadlock the runtime.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new();
+    /// let handle = runtime.spawner().spawn(async { 42 }).unwrap();
+    /// let _workers = runtime.run(1);
+    ///
+    /// assert_eq!(handle.join().unwrap(), 42);
+    /// ```
+    pub fn join(self) -> Result<T, JoinError> {
+        assert!(
+            !is_worker_thread(),
+            "JoinHandle::join() called from a runtime worker thread"
+        );
+        assert!(
+            !self.is_taken,
+            "JoinHandle joined after its result was taken"
+        );
+
+        let mut result = self.state.result.lock();
+        loop {
+            if let Some(result) = result.take() {
+                return result;
+            }
+            self.state.completed.wait(&mut result);
+        }
+    }
+}
+
+/// A [`JoinHandle`] that aborts its task when dropped, created by
+/// [`JoinHandle::abort_on_drop`]. Awaiting it yields the task's result as
+/// usual.
+pub struct OwnedTask<T> {
+    /// `None` once detached.
+    handle: Option<JoinHandle<T>>,
+}
+
+impl<T> OwnedTask<T> {
+    /// Disarms the abort-on-drop and returns the plain handle, so the task
+    /// keeps running when it's dropped.
+    pub fn detach(mut self) -> JoinHandle<T> {
+        self.handle.take().expect("owned task detached twice")
+    }
+
+    pub fn abort(&self) {
+        self.handle().abort();
+    }
+
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.handle().abort_handle()
+    }
+
     pub fn is_finished(&self) -> bool {
-        self.state.result.lock().is_some()
+        self.handle().is_finished()
+    }
+
+    fn handle(&self) -> &JoinHandle<T> {
+        self.handle.as_ref().expect("owned task already detached")
+    }
+}
+
+impl<T> Future for OwnedTask<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let handle = self.handle.as_mut().expect("owned task already detached");
+        Pin::new(handle).poll(cx)
+    }
+}
+
+impl<T> Drop for OwnedTask<T> {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.handle {
+            handle.abort();
+        }
+    }
+}
+
+/// A [`JoinHandle`] whose result is transformed on completion, created by
+/// [`JoinHandle::map`].
+pub struct MappedJoinHandle<T, F> {
+    handle: JoinHandle<T>,
+    /// Taken once the result has been mapped.
+    f: Option<F>,
+}
+
+impl<T, F> MappedJoinHandle<T, F> {
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.handle.abort_handle()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+// `f` is never pinned, so moving it out after a poll is fine.
+impl<T, F> Unpin for MappedJoinHandle<T, F> {}
+
+impl<T, U, F> Future for MappedJoinHandle<T, F>
+where
+    F: FnOnce(T) -> U,
+{
+    type Output = Result<U, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = std::task::ready!(Pin::new(&mut self.handle).poll(cx));
+        let f = self
+            .f
+            .take()
+            .expect("MappedJoinHandle polled after completion");
+        Poll::Ready(result.map(f))
+    }
+}
+
+impl<T, E> JoinHandle<Result<T, E>> {
+    /// Flattens the handle of a fallible task, so awaiting it yields the
+    /// task's own `Result`. A [`JoinError`] is converted into `E` through
+    /// its `From<JoinError>` impl.
+    pub fn flatten(self) -> FlattenedJoinHandle<T, E> {
+        FlattenedJoinHandle { handle: self }
+    }
+}
+
+/// Handle of a fallible task whose [`JoinError`] is folded into the task's
+/// error type, created by [`JoinHandle::flatten`] or
+/// [`Spawner::spawn_fallible`](crate::Spawner::spawn_fallible).
+pub struct FlattenedJoinHandle<T, E> {
+    handle: JoinHandle<Result<T, E>>,
+}
+
+impl<T, E> FlattenedJoinHandle<T, E> {
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.handle.abort_handle()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+impl<T, E> Future for FlattenedJoinHandle<T, E>
+where
+    E: From<JoinError>,
+{
+    type Output = Result<T, E>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.handle)
+            .poll(cx)
+            .map(|result| result.unwrap_or_else(|error| Err(E::from(error))))
     }
 }
 
@@ -37,26 +347,45 @@ pub(crate) struct JoinNotifier<T> {
     state: Arc<JoinState<T>>,
 }
 
+impl<T> Clone for JoinNotifier<T> {
+    fn clone(&self) -> Self {
+        JoinNotifier {
+            state: self.state.clone(),
+        }
+    }
+}
+
 impl<T> JoinNotifier<T> {
-    pub fn complete(self, result: Result<T, JoinError>) {
-        *self.state.result.lock() = Some(result);
-        if let Some(waker) = self.state.waker.lock().take() {
-            waker.wake();
+    /// Stores the task's result and wakes the awaiting handle. Only the first
+    /// completion takes effect; later calls are ignored.
+    pub fn complete(&self, result: Result<T, JoinError>) {
+        if self.state.is_complete.swap(true, Ordering::SeqCst) {
+            return;
         }
+
+        *self.state.result.lock() = Some(result);
+        self.state.completed.notify_all();
+        self.state.waker.wake();
     }
 }
 
 impl<T> Future for JoinHandle<T> {
     type Output = Result<T, JoinError>;
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let mut result_guard = self.state.result.lock();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        assert!(
+            !self.is_taken,
+            "JoinHandle polled after its result was taken"
+        );
+        let state = self.state.clone();
+        let mut result_guard = state.result.lock();
 
         if let Some(result) = result_guard.take() {
+            self.is_taken = true;
             return Poll::Ready(result);
         }
 
-        *self.state.waker.lock() = Some(cx.waker().clone());
+        self.state.waker.register(cx.waker());
         Poll::Pending
     }
 }
@@ -64,14 +393,60 @@ impl<T> Future for JoinHandle<T> {
 #[derive(Debug)]
 pub enum JoinError {
     Cancelled,
-    Panicked,
+    /// The task panicked.
+    Panicked {
+        /// Name of the task, if it ran on a runtime worker. Tasks spawned
+        /// without a name get their numeric id.
+        task_name: Option<Arc<str>>,
+        /// Payload caught from the panic.
+        payload: Box<dyn Any + Send>,
+    },
+}
+
+impl JoinError {
+    /// Shorthand for a panic of a task without a name.
+    pub(crate) fn panicked(payload: Box<dyn Any + Send>) -> Self {
+        JoinError::Panicked {
+            task_name: None,
+            payload,
+        }
+    }
+
+    /// Returns the name of the task that panicked, if known.
+    pub fn task_name(&self) -> Option<&str> {
+        match self {
+            JoinError::Panicked { task_name, .. } => task_name.as_deref(),
+            JoinError::Cancelled => None,
+        }
+    }
+
+    /// Returns the panic message if the task panicked with a `&str` or
+    /// `String` payload, as `panic!` does.
+    pub fn panic_message(&self) -> Option<&str> {
+        let JoinError::Panicked { payload, .. } = self else {
+            return None;
+        };
+
+        payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+    }
 }
 
 impl std::fmt::Display for JoinError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            JoinError::Cancelled => write!(f, "task was cancelled"),
-            JoinError::Panicked => write!(f, "task panicked"),
+        if let JoinError::Cancelled = self {
+            return write!(f, "task was cancelled");
+        }
+
+        match self.task_name() {
+            Some(name) => write!(f, "task '{}' panicked", name)?,
+            None => write!(f, "task panicked")?,
+        }
+        match self.panic_message() {
+            Some(message) => write!(f, ": {}", message),
+            None => Ok(()),
         }
     }
 }
@@ -81,6 +456,7 @@ impl std::error::Error for JoinError {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::task::Waker;
 
     #[test]
     fn join_handle_starts_not_finished() {
@@ -95,6 +471,133 @@ mod tests {
         assert!(handle.is_finished());
     }
 
+    #[test]
+    fn join_notifier_first_completion_wins() {
+        let (handle, notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        let other = notifier.clone();
+
+        notifier.complete(Ok(1));
+        other.complete(Err(JoinError::panicked(Box::new(()))));
+
+        assert!(matches!(*handle.state.result.lock(), Some(Ok(1))));
+    }
+
+    #[test]
+    fn join_handle_stays_finished_after_result_taken() {
+        let (handle, notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        notifier.complete(Ok(42));
+
+        let _ = handle.state.result.lock().take();
+
+        assert!(handle.is_finished());
+    }
+
+    #[test]
+    #[should_panic(expected = "polled after its result was taken")]
+    fn polling_after_result_taken_panics() {
+        let (mut handle, notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        notifier.complete(Ok(42));
+
+        assert_eq!(
+            Pin::new(&mut handle).poll(&mut context).map(Result::unwrap),
+            Poll::Ready(42)
+        );
+        let _ = Pin::new(&mut handle).poll(&mut context);
+    }
+
+    #[test]
+    fn map_transforms_success_and_passes_errors_through() {
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let (ok, ok_notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        let (cancelled, cancelled_notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        let mut doubled = ok.map(|value| value * 2);
+        let mut described = cancelled.map(|value| value.to_string());
+
+        assert!(Pin::new(&mut doubled).poll(&mut context).is_pending());
+        ok_notifier.complete(Ok(21));
+        cancelled_notifier.complete(Err(JoinError::Cancelled));
+
+        assert!(matches!(
+            Pin::new(&mut doubled).poll(&mut context),
+            Poll::Ready(Ok(42))
+        ));
+        assert!(matches!(
+            Pin::new(&mut described).poll(&mut context),
+            Poll::Ready(Err(JoinError::Cancelled))
+        ));
+    }
+
+    #[test]
+    fn flatten_converts_join_errors_into_task_errors() {
+        #[derive(Debug, PartialEq)]
+        enum FetchError {
+            Timeout,
+            Join(String),
+        }
+
+        impl From<JoinError> for FetchError {
+            fn from(error: JoinError) -> Self {
+                FetchError::Join(error.to_string())
+            }
+        }
+
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let (failed, failed_notifier): (JoinHandle<Result<u8, FetchError>>, _) = JoinHandle::new();
+        let (aborted, aborted_notifier): (JoinHandle<Result<u8, FetchError>>, _) =
+            JoinHandle::new();
+        failed_notifier.complete(Ok(Err(FetchError::Timeout)));
+        aborted_notifier.complete(Err(JoinError::Cancelled));
+
+        assert_eq!(
+            Pin::new(&mut failed.flatten()).poll(&mut context),
+            Poll::Ready(Err(FetchError::Timeout))
+        );
+        assert!(matches!(
+            Pin::new(&mut aborted.flatten()).poll(&mut context),
+            Poll::Ready(Err(FetchError::Join(_)))
+        ));
+    }
+
+    #[test]
+    fn pending_handle_is_neither_panicked_nor_cancelled() {
+        let (handle, _notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+
+        assert!(!handle.is_panicked());
+        assert!(!handle.is_cancelled());
+    }
+
+    #[test]
+    fn outcome_inspection_matches_stored_result() {
+        let (ok, ok_notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        let (panicked, panicked_notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        let (cancelled, cancelled_notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+
+        ok_notifier.complete(Ok(1));
+        panicked_notifier.complete(Err(JoinError::panicked(Box::new("boom"))));
+        cancelled_notifier.complete(Err(JoinError::Cancelled));
+
+        assert!(!ok.is_panicked() && !ok.is_cancelled());
+        assert!(panicked.is_panicked() && !panicked.is_cancelled());
+        assert!(cancelled.is_cancelled() && !cancelled.is_panicked());
+    }
+
+    #[test]
+    fn abort_handle_is_clone_send_sync() {
+        fn assert_traits<H: Clone + Send + Sync>() {}
+        assert_traits::<AbortHandle>();
+    }
+
+    #[test]
+    fn abort_handle_without_task_is_noop() {
+        let (handle, _notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        handle.abort_handle().abort();
+        assert!(!handle.is_finished());
+    }
+
     #[test]
     fn join_error_display_cancelled() {
         let error = JoinError::Cancelled;
@@ -103,7 +606,56 @@ mod tests {
 
     #[test]
     fn join_error_display_panicked() {
-        let error = JoinError::Panicked;
+        let error = JoinError::panicked(Box::new(42));
         assert_eq!(format!("{}", error), "task panicked");
     }
+
+    #[test]
+    fn join_error_display_includes_panic_message() {
+        let error = JoinError::panicked(Box::new("boom"));
+        assert_eq!(format!("{}", error), "task panicked: boom");
+    }
+
+    #[test]
+    fn join_error_display_includes_task_name() {
+        let error = JoinError::Panicked {
+            task_name: Some(Arc::from("fetcher")),
+            payload: Box::new("boom"),
+        };
+
+        assert_eq!(error.task_name(), Some("fetcher"));
+        assert_eq!(format!("{}", error), "task 'fetcher' panicked: boom");
+    }
+
+    #[test]
+    fn panic_message_downcasts_str_and_string() {
+        let literal = JoinError::panicked(Box::new("literal"));
+        let formatted = JoinError::panicked(Box::new(format!("code {}", 7)));
+
+        assert_eq!(literal.panic_message(), Some("literal"));
+        assert_eq!(formatted.panic_message(), Some("code 7"));
+        assert_eq!(JoinError::panicked(Box::new(1)).panic_message(), None);
+        assert_eq!(JoinError::Cancelled.panic_message(), None);
+    }
+
+    #[test]
+    fn join_returns_result_completed_on_another_thread() {
+        let (handle, notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+
+        let completer = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            notifier.complete(Ok(42));
+        });
+
+        assert_eq!(handle.join().unwrap(), 42);
+        completer.join().unwrap();
+    }
+
+    #[test]
+    fn join_returns_immediately_when_already_complete() {
+        let (handle, notifier): (JoinHandle<i32>, _) = JoinHandle::new();
+        notifier.complete(Err(JoinError::Cancelled));
+
+        assert!(matches!(handle.join(), Err(JoinError::Cancelled)));
+    }
 }
@@ -0,0 +1,148 @@
+use std::future::Future;
+
+use crate::executor::{SpawnError, Spawner, spawn};
+use crate::future::Unordered;
+use crate::join_handle::{AbortHandle, JoinError, JoinHandle};
+
+/// A group of tasks whose results are collected as they finish. Built on
+/// [`Unordered`], which also works for handles spawned elsewhere.
+///
+/// # Example
+///
+/// ```
+/// use runtime::{JoinSet, Runtime};
+///
+/// let runtime = Runtime::new();
+/// let total = runtime.block_on(async {
+///     let mut set = JoinSet::new();
+///     for i in 1..=3 {
+///         set.spawn(async move { i }).unwrap();
+///     }
+///
+///     let mut total = 0;
+///     while let Some(result) = set.join_next().await {
+///         total += result.unwrap();
+///     }
+///     total
+/// });
+///
+/// assert_eq!(total, 6);
+/// ```
+pub struct JoinSet<T> {
+    tasks: Unordered<T>,
+}
+
+impl<T: Send + 'static> JoinSet<T> {
+    pub fn new() -> Self {
+        JoinSet {
+            tasks: Unordered::new(),
+        }
+    }
+
+    /// Spawns `future` on the current runtime and adds it to the set.
+    pub fn spawn<F>(&mut self, future: F) -> Result<AbortHandle, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        Ok(self.insert(spawn(future)?))
+    }
+
+    /// Spawns `future` through `spawner` and adds it to the set.
+    pub fn spawn_on<F>(&mut self, future: F, spawner: &Spawner) -> Result<AbortHandle, SpawnError>
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        Ok(self.insert(spawner.spawn(future)?))
+    }
+
+    fn insert(&mut self, handle: JoinHandle<T>) -> AbortHandle {
+        let abort_handle = handle.abort_handle();
+        self.tasks.push(handle);
+        abort_handle
+    }
+
+    /// Waits for the next task in the set to finish and returns its result,
+    /// or `None` once the set is empty.
+    pub async fn join_next(&mut self) -> Option<Result<T, JoinError>> {
+        self.tasks.next().await
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Aborts every task in the set. Their cancellation results are still
+    /// returned by [`join_next`](Self::join_next).
+    pub fn abort_all(&self) {
+        for handle in self.tasks.handles() {
+            handle.abort();
+        }
+    }
+}
+
+impl<T: Send + 'static> Default for JoinSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Runtime;
+
+    #[test]
+    fn empty_set_joins_none() {
+        let runtime = Runtime::new();
+        let mut set: JoinSet<()> = JoinSet::new();
+
+        assert!(set.is_empty());
+        assert!(runtime.block_on(set.join_next()).is_none());
+    }
+
+    #[test]
+    fn join_next_drains_every_task() {
+        let runtime = Runtime::new();
+        let spawner = runtime.spawner();
+        let mut set = JoinSet::new();
+        for i in 0..4 {
+            set.spawn_on(async move { i }, &spawner).unwrap();
+        }
+        assert_eq!(set.len(), 4);
+
+        let mut results = runtime.block_on(async {
+            let mut results = Vec::new();
+            while let Some(result) = set.join_next().await {
+                results.push(result.unwrap());
+            }
+            results
+        });
+        results.sort();
+
+        assert_eq!(results, vec![0, 1, 2, 3]);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn abort_all_cancels_pending_tasks() {
+        let runtime = Runtime::new();
+        let spawner = runtime.spawner();
+        let mut set = JoinSet::new();
+        for _ in 0..3 {
+            set.spawn_on(std::future::pending::<()>(), &spawner)
+                .unwrap();
+        }
+
+        set.abort_all();
+
+        runtime.block_on(async {
+            while let Some(result) = set.join_next().await {
+                assert!(matches!(result, Err(JoinError::Cancelled)));
+            }
+        });
+    }
+}
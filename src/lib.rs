@@ -1,9 +1,22 @@
 pub mod cancellation;
 pub mod executor;
+pub mod future;
 pub mod join_handle;
+pub mod join_set;
+pub mod sync;
+pub mod task_local;
 pub mod timer;
 
-pub use cancellation::CancellationToken;
-pub use executor::{Runtime, RuntimeHandle, SpawnError, Spawner};
-pub use join_handle::{JoinError, JoinHandle};
-pub use timer::sleep;
+pub use cancellation::{CancellationToken, DropGuard};
+pub use executor::{
+    BlockingHandle, IdleStrategy, LocalRuntime, Priority, Runtime, RuntimeBuilder, RuntimeHandle,
+    RuntimeMetrics, RuntimeObserver, Scope, SpawnError, Spawner, StderrObserver, WorkerStats,
+    YieldNow, is_in_runtime, spawn, try_current, yield_now,
+};
+pub use future::{Either, RuntimeFutureExt, join2, select2};
+pub use join_handle::{
+    AbortHandle, FlattenedJoinHandle, JoinError, JoinHandle, MappedJoinHandle, OwnedTask,
+};
+pub use join_set::JoinSet;
+pub use task_local::TaskLocal;
+pub use timer::{interval, sleep, sleep_until, timeout};
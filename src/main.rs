@@ -57,32 +57,26 @@ fn spawn_delayed_task(spawner: &runtime::Spawner) {
 }
 
 fn spawn_task_with_subtask(spawner: &runtime::Spawner) {
-    let spawner_clone = spawner.clone();
-
     spawner
         .spawn(async move {
             println!("[parent] started");
             sleep(Duration::from_secs(1)).await;
             println!("[parent] spawning subtask...");
 
-            spawner_clone
-                .spawn(async {
-                    println!("[subtask] executing");
-                })
-                .expect("spawn failed");
+            runtime::spawn(async {
+                println!("[subtask] executing");
+            })
+            .expect("spawn failed");
         })
         .expect("spawn failed");
 }
 
 fn spawn_counter_tasks(spawner: &runtime::Spawner, count: usize) {
-    for i in 0..count {
-        let task_id = i;
-        spawner
-            .spawn(async move {
-                println!("[counter:{}] executed", task_id);
-            })
-            .expect("spawn failed");
-    }
+    spawner
+        .spawn_all((0..count).map(|task_id| async move {
+            println!("[counter:{}] executed", task_id);
+        }))
+        .expect("spawn failed");
 }
 
 fn spawn_cancellable_task(spawner: &runtime::Spawner) {
@@ -0,0 +1,149 @@
+use parking_lot::Mutex;
+use std::task::Waker;
+
+/// Holds at most one [`Waker`] to be woken when an event happens.
+///
+/// To avoid missing an event that fires while a task is being polled, the
+/// task must [`register`](AtomicWaker::register) its waker *before* checking
+/// whether the event has happened, and the producer must record the event
+/// *before* calling [`wake`](AtomicWaker::wake). `register` and `wake` are
+/// serialized, so one of the two always sees the other.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::future::poll_fn;
+/// use runtime::sync::AtomicWaker;
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::task::Poll;
+///
+/// let runtime = Runtime::new();
+/// let waker = Arc::new(AtomicWaker::new());
+/// let is_done = Arc::new(AtomicBool::new(false));
+///
+/// let (producer_waker, producer_done) = (waker.clone(), is_done.clone());
+/// std::thread::spawn(move || {
+///     producer_done.store(true, Ordering::SeqCst);
+///     producer_waker.wake();
+/// });
+///
+/// runtime.block_on(poll_fn(|cx| {
+///     waker.register(cx.waker());
+///     if is_done.load(Ordering::SeqCst) {
+///         Poll::Ready(())
+///     } else {
+///         Poll::Pending
+///     }
+/// }));
+/// ```
+#[derive(Default)]
+pub struct AtomicWaker {
+    waker: Mutex<Option<Waker>>,
+}
+
+impl AtomicWaker {
+    pub const fn new() -> Self {
+        AtomicWaker {
+            waker: Mutex::new(None),
+        }
+    }
+
+    /// Stores `waker` to be woken by the next [`wake`](Self::wake),
+    /// replacing any waker registered before.
+    pub fn register(&self, waker: &Waker) {
+        let mut slot = self.waker.lock();
+        match &*slot {
+            Some(current) if current.will_wake(waker) => {}
+            _ => *slot = Some(waker.clone()),
+        }
+    }
+
+    /// Wakes and removes the registered waker, if any. Does nothing when no
+    /// waker is registered.
+    pub fn wake(&self) {
+        let waker = self.waker.lock().take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::task::Wake;
+    use std::thread;
+
+    #[derive(Default)]
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn create_counting_waker() -> (Waker, Arc<CountingWaker>) {
+        let counter = Arc::new(CountingWaker::default());
+        (Waker::from(counter.clone()), counter)
+    }
+
+    #[test]
+    fn wake_without_registered_waker_does_nothing() {
+        AtomicWaker::new().wake();
+    }
+
+    #[test]
+    fn wake_takes_the_registered_waker() {
+        let cell = AtomicWaker::new();
+        let (waker, counter) = create_counting_waker();
+
+        cell.register(&waker);
+        cell.wake();
+        cell.wake();
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn register_replaces_previous_waker() {
+        let cell = AtomicWaker::new();
+        let (first, first_counter) = create_counting_waker();
+        let (second, second_counter) = create_counting_waker();
+
+        cell.register(&first);
+        cell.register(&second);
+        cell.wake();
+
+        assert_eq!(first_counter.wake_count.load(Ordering::SeqCst), 0);
+        assert_eq!(second_counter.wake_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn concurrent_wake_is_never_missed_after_register() {
+        for _ in 0..1_000 {
+            let cell = Arc::new(AtomicWaker::new());
+            let is_done = Arc::new(AtomicBool::new(false));
+            let (waker, counter) = create_counting_waker();
+
+            let producer = {
+                let (cell, is_done) = (cell.clone(), is_done.clone());
+                thread::spawn(move || {
+                    is_done.store(true, Ordering::SeqCst);
+                    cell.wake();
+                })
+            };
+            cell.register(&waker);
+            let saw_event = is_done.load(Ordering::SeqCst);
+            producer.join().unwrap();
+
+            assert!(saw_event || counter.wake_count.load(Ordering::SeqCst) == 1);
+        }
+    }
+}
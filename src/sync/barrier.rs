@@ -0,0 +1,179 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::poll_fn;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Poll, Waker};
+
+type WaiterId = u64;
+
+/// Lets a fixed number of tasks wait until all of them have reached the same
+/// point.
+///
+/// The barrier is reusable: once `n` tasks have been released, the next `n`
+/// calls to [`wait`](Barrier::wait) form a new generation.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::Barrier;
+/// use std::sync::Arc;
+///
+/// let runtime = Runtime::new();
+/// let barrier = Arc::new(Barrier::new(2));
+///
+/// let other = barrier.clone();
+/// let handle = runtime.spawner().spawn(async move {
+///     other.wait().await.is_leader()
+/// }).unwrap();
+///
+/// let is_leader = runtime.block_on(barrier.wait()).is_leader();
+/// assert_ne!(is_leader, handle.join().unwrap());
+/// ```
+pub struct Barrier {
+    state: Mutex<BarrierState>,
+    parties: usize,
+    next_waiter_id: AtomicU64,
+}
+
+struct BarrierState {
+    arrived: usize,
+    generation: u64,
+    wakers: HashMap<WaiterId, Waker>,
+}
+
+impl Barrier {
+    /// Creates a barrier that releases tasks in groups of `n`. A barrier of
+    /// zero behaves like a barrier of one.
+    pub fn new(n: usize) -> Self {
+        Barrier {
+            state: Mutex::new(BarrierState {
+                arrived: 0,
+                generation: 0,
+                wakers: HashMap::new(),
+            }),
+            parties: n.max(1),
+            next_waiter_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits until `n` tasks have called `wait`, then releases them all.
+    /// The task whose arrival completed the group is the leader.
+    ///
+    /// Dropping the future after it was first polled doesn't withdraw the
+    /// arrival: the group still completes one task early.
+    pub async fn wait(&self) -> BarrierWaitResult {
+        let generation = {
+            let mut state = self.state.lock();
+            state.arrived += 1;
+            if state.arrived == self.parties {
+                state.arrived = 0;
+                state.generation += 1;
+                let wakers = std::mem::take(&mut state.wakers);
+                drop(state);
+
+                for waker in wakers.into_values() {
+                    waker.wake();
+                }
+                return BarrierWaitResult { is_leader: true };
+            }
+            state.generation
+        };
+
+        let waiter = Waiter {
+            barrier: self,
+            id: self.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+        };
+        poll_fn(|cx| {
+            let mut state = self.state.lock();
+            if state.generation != generation {
+                return Poll::Ready(());
+            }
+
+            state.wakers.insert(waiter.id, cx.waker().clone());
+            Poll::Pending
+        })
+        .await;
+
+        BarrierWaitResult { is_leader: false }
+    }
+}
+
+/// Removes a waiting task's waker if its `wait` future is dropped early.
+struct Waiter<'a> {
+    barrier: &'a Barrier,
+    id: WaiterId,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        self.barrier.state.lock().wakers.remove(&self.id);
+    }
+}
+
+/// Returned by [`Barrier::wait`] once the group has been released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarrierWaitResult {
+    is_leader: bool,
+}
+
+impl BarrierWaitResult {
+    /// Returns `true` for exactly one task of each generation.
+    pub fn is_leader(&self) -> bool {
+        self.is_leader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::Context;
+
+    #[test]
+    fn releases_all_once_group_is_complete() {
+        let barrier = Barrier::new(3);
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let mut first = pin!(barrier.wait());
+        let mut second = pin!(barrier.wait());
+        let mut third = pin!(barrier.wait());
+
+        assert!(first.as_mut().poll(&mut context).is_pending());
+        assert!(second.as_mut().poll(&mut context).is_pending());
+
+        let leader = third.as_mut().poll(&mut context);
+        assert_eq!(leader, Poll::Ready(BarrierWaitResult { is_leader: true }));
+        for waiting in [first, second] {
+            assert_eq!(
+                waiting.poll(&mut context),
+                Poll::Ready(BarrierWaitResult { is_leader: false })
+            );
+        }
+    }
+
+    #[test]
+    fn barrier_is_reusable_across_generations() {
+        let barrier = Barrier::new(2);
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+
+        for _ in 0..3 {
+            let mut first = pin!(barrier.wait());
+            assert!(first.as_mut().poll(&mut context).is_pending());
+            assert!(pin!(barrier.wait()).poll(&mut context).is_ready());
+            assert!(first.as_mut().poll(&mut context).is_ready());
+        }
+        assert!(barrier.state.lock().wakers.is_empty());
+    }
+
+    #[test]
+    fn zero_parties_never_waits() {
+        let barrier = Barrier::new(0);
+        let waker = Waker::noop();
+
+        let result = pin!(barrier.wait()).poll(&mut Context::from_waker(waker));
+        assert_eq!(result, Poll::Ready(BarrierWaitResult { is_leader: true }));
+    }
+}
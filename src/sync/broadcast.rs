@@ -0,0 +1,395 @@
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+
+use crate::future::Stream;
+
+type WaiterId = u64;
+
+struct Shared<T> {
+    state: Mutex<BroadcastState<T>>,
+    next_waiter_id: AtomicU64,
+}
+
+struct BroadcastState<T> {
+    /// The last `capacity` messages; `buffer[0]` has position `head`.
+    buffer: VecDeque<T>,
+    capacity: usize,
+    head: u64,
+    sender_count: usize,
+    receiver_count: usize,
+    recv_wakers: HashMap<WaiterId, Waker>,
+}
+
+impl<T> BroadcastState<T> {
+    /// Position the next sent message will get.
+    fn tail(&self) -> u64 {
+        self.head + self.buffer.len() as u64
+    }
+
+    fn wake_receivers(&mut self) {
+        for waker in std::mem::take(&mut self.recv_wakers).into_values() {
+            waker.wake();
+        }
+    }
+}
+
+/// Creates a channel delivering every message to every receiver. Each
+/// receiver can fall at most `capacity` messages behind before it starts
+/// missing messages.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::broadcast;
+///
+/// let runtime = Runtime::new();
+/// let (sender, mut first) = broadcast::channel(8);
+/// let mut second = sender.subscribe();
+///
+/// sender.send("hello").unwrap();
+///
+/// runtime.block_on(async {
+///     assert_eq!(first.recv().await.unwrap(), "hello");
+///     assert_eq!(second.recv().await.unwrap(), "hello");
+/// });
+/// ```
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "broadcast channel capacity must be non-zero");
+
+    let shared = Arc::new(Shared {
+        state: Mutex::new(BroadcastState {
+            buffer: VecDeque::with_capacity(capacity),
+            capacity,
+            head: 0,
+            sender_count: 1,
+            receiver_count: 0,
+            recv_wakers: HashMap::new(),
+        }),
+        next_waiter_id: AtomicU64::new(0),
+    });
+
+    let sender = Sender { shared };
+    let receiver = sender.subscribe();
+    (sender, receiver)
+}
+
+/// Sending half of a [`broadcast`](self) channel. Clone it for more
+/// producers.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Clone> Sender<T> {
+    /// Delivers `value` to every current receiver and returns how many there
+    /// are. Never waits: once the buffer is full the oldest message is
+    /// dropped, and receivers that hadn't seen it get [`RecvError::Lagged`].
+    /// Fails with the value if there are no receivers.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let mut state = self.shared.state.lock();
+        if state.receiver_count == 0 {
+            return Err(SendError(value));
+        }
+
+        if state.buffer.len() == state.capacity {
+            state.buffer.pop_front();
+            state.head += 1;
+        }
+        state.buffer.push_back(value);
+        state.wake_receivers();
+        Ok(state.receiver_count)
+    }
+
+    /// Creates a receiver that sees every message sent after this call.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut state = self.shared.state.lock();
+        state.receiver_count += 1;
+        Receiver {
+            shared: self.shared.clone(),
+            next: state.tail(),
+            waiter_id: self.shared.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.lock().receiver_count
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().sender_count += 1;
+        Sender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.sender_count -= 1;
+        if state.sender_count == 0 {
+            state.wake_receivers();
+        }
+    }
+}
+
+/// Receiving half of a [`broadcast`](self) channel. More receivers are
+/// created with [`Sender::subscribe`].
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    /// Position of the next message this receiver will yield.
+    next: u64,
+    waiter_id: WaiterId,
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receives the next message.
+    ///
+    /// Fails with [`RecvError::Lagged`] if messages were dropped before this
+    /// receiver saw them; the next call resumes at the oldest message still
+    /// buffered. Fails with [`RecvError::Closed`] once every sender has been
+    /// dropped and all buffered messages have been received.
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, RecvError>> {
+        let mut state = self.shared.state.lock();
+
+        if self.next < state.head {
+            let missed = state.head - self.next;
+            self.next = state.head;
+            return Poll::Ready(Err(RecvError::Lagged(missed)));
+        }
+
+        if self.next < state.tail() {
+            let value = state.buffer[(self.next - state.head) as usize].clone();
+            self.next += 1;
+            state.recv_wakers.remove(&self.waiter_id);
+            return Poll::Ready(Ok(value));
+        }
+
+        if state.sender_count == 0 {
+            return Poll::Ready(Err(RecvError::Closed));
+        }
+
+        state.recv_wakers.insert(self.waiter_id, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Yields the same results as [`Receiver::recv`], including
+/// [`RecvError::Lagged`], but ends instead of yielding [`RecvError::Closed`].
+impl<T: Clone> Stream for Receiver<T> {
+    type Item = Result<T, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut().poll_recv(cx) {
+            Poll::Ready(Err(RecvError::Closed)) => Poll::Ready(None),
+            Poll::Ready(result) => Poll::Ready(Some(result)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.receiver_count -= 1;
+        state.recv_wakers.remove(&self.waiter_id);
+    }
+}
+
+/// Error returned by [`Sender::send`] when there are no receivers. Carries
+/// back the value that couldn't be delivered.
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+/// Error returned by [`Receiver::recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// Every sender was dropped and no messages are left.
+    Closed,
+    /// The receiver fell behind and this many messages were dropped before
+    /// it could see them.
+    Lagged(u64),
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Closed => write!(f, "channel closed"),
+            RecvError::Lagged(missed) => write!(f, "receiver lagged by {} messages", missed),
+        }
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::atomic::AtomicUsize;
+    use std::task::Wake;
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn create_counting_waker() -> (Waker, Arc<CountingWaker>) {
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        (Waker::from(counter.clone()), counter)
+    }
+
+    fn poll_recv<T: Clone>(
+        receiver: &mut Receiver<T>,
+        waker: &Waker,
+    ) -> Poll<Result<T, RecvError>> {
+        pin!(receiver.recv()).poll(&mut Context::from_waker(waker))
+    }
+
+    #[test]
+    fn every_receiver_sees_every_message() {
+        let (waker, _) = create_counting_waker();
+        let (sender, mut first) = channel(4);
+        let mut second = sender.subscribe();
+
+        assert_eq!(sender.send(1).unwrap(), 2);
+        sender.send(2).unwrap();
+
+        for receiver in [&mut first, &mut second] {
+            assert_eq!(poll_recv(receiver, &waker), Poll::Ready(Ok(1)));
+            assert_eq!(poll_recv(receiver, &waker), Poll::Ready(Ok(2)));
+            assert!(poll_recv(receiver, &waker).is_pending());
+        }
+    }
+
+    #[test]
+    fn subscriber_starts_at_current_position() {
+        let (waker, _) = create_counting_waker();
+        let (sender, _first) = channel(4);
+
+        sender.send(1).unwrap();
+        let mut late = sender.subscribe();
+        sender.send(2).unwrap();
+
+        assert_eq!(poll_recv(&mut late, &waker), Poll::Ready(Ok(2)));
+    }
+
+    #[test]
+    fn slow_receiver_reports_lag_then_resumes() {
+        let (waker, _) = create_counting_waker();
+        let (sender, mut receiver) = channel(2);
+
+        for value in 0..5 {
+            sender.send(value).unwrap();
+        }
+
+        assert_eq!(
+            poll_recv(&mut receiver, &waker),
+            Poll::Ready(Err(RecvError::Lagged(3)))
+        );
+        assert_eq!(poll_recv(&mut receiver, &waker), Poll::Ready(Ok(3)));
+        assert_eq!(poll_recv(&mut receiver, &waker), Poll::Ready(Ok(4)));
+    }
+
+    #[test]
+    fn send_wakes_pending_receivers() {
+        let (waker, counter) = create_counting_waker();
+        let (sender, mut first) = channel(1);
+        let mut second = sender.subscribe();
+
+        assert!(poll_recv(&mut first, &waker).is_pending());
+        assert!(poll_recv(&mut second, &waker).is_pending());
+        sender.send(()).unwrap();
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn recv_drains_buffer_before_reporting_closed() {
+        let (waker, counter) = create_counting_waker();
+        let (sender, mut receiver) = channel(2);
+
+        sender.send(1).unwrap();
+        drop(sender);
+
+        assert_eq!(poll_recv(&mut receiver, &waker), Poll::Ready(Ok(1)));
+        assert_eq!(
+            poll_recv(&mut receiver, &waker),
+            Poll::Ready(Err(RecvError::Closed))
+        );
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn stream_ends_instead_of_yielding_closed() {
+        let (waker, _) = create_counting_waker();
+        let mut context = Context::from_waker(&waker);
+        let (sender, mut receiver) = channel(1);
+
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        drop(sender);
+
+        let mut stream = Pin::new(&mut receiver);
+        assert_eq!(
+            stream.as_mut().poll_next(&mut context),
+            Poll::Ready(Some(Err(RecvError::Lagged(1))))
+        );
+        assert_eq!(
+            stream.as_mut().poll_next(&mut context),
+            Poll::Ready(Some(Ok(2)))
+        );
+        assert_eq!(stream.as_mut().poll_next(&mut context), Poll::Ready(None));
+    }
+
+    #[test]
+    fn send_without_receivers_fails() {
+        let (sender, receiver) = channel(1);
+        drop(receiver);
+
+        assert!(matches!(sender.send(9), Err(SendError(9))));
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn channel_rejects_zero_capacity() {
+        let _ = channel::<()>(0);
+    }
+}
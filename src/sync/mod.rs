@@ -0,0 +1,21 @@
+mod atomic_waker;
+mod barrier;
+pub mod broadcast;
+pub mod mpsc;
+mod mutex;
+mod notify;
+pub mod oneshot;
+mod rate_limiter;
+mod rwlock;
+mod semaphore;
+mod wait_group;
+pub mod watch;
+
+pub use atomic_waker::AtomicWaker;
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use mutex::{Mutex, MutexGuard};
+pub use notify::{Notified, Notify};
+pub use rate_limiter::RateLimiter;
+pub use rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+pub use semaphore::{Acquire, Semaphore, SemaphorePermit};
+pub use wait_group::{Wait, WaitGroup, WaitGroupGuard};
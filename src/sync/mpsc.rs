@@ -0,0 +1,340 @@
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::{Future, poll_fn};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+
+use crate::future::Stream;
+
+type WaiterId = u64;
+
+struct Chan<T> {
+    state: Mutex<ChanState<T>>,
+    next_waiter_id: AtomicU64,
+}
+
+struct ChanState<T> {
+    queue: VecDeque<T>,
+    capacity: usize,
+    sender_count: usize,
+    is_receiver_alive: bool,
+    recv_waker: Option<Waker>,
+    send_wakers: HashMap<WaiterId, Waker>,
+}
+
+impl<T> ChanState<T> {
+    fn wake_senders(&mut self) {
+        for waker in std::mem::take(&mut self.send_wakers).into_values() {
+            waker.wake();
+        }
+    }
+}
+
+/// Creates a bounded channel holding at most `capacity` queued messages.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::mpsc;
+///
+/// let runtime = Runtime::new();
+/// let (sender, mut receiver) = mpsc::channel(8);
+///
+/// runtime.spawner().spawn(async move {
+///     for i in 0..3 {
+///         sender.send(i).await.unwrap();
+///     }
+/// }).unwrap();
+///
+/// let received = runtime.block_on(async move {
+///     let mut received = Vec::new();
+///     while let Some(value) = receiver.recv().await {
+///         received.push(value);
+///     }
+///     received
+/// });
+///
+/// assert_eq!(received, vec![0, 1, 2]);
+/// ```
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "mpsc channel capacity must be non-zero");
+
+    let chan = Arc::new(Chan {
+        state: Mutex::new(ChanState {
+            queue: VecDeque::with_capacity(capacity),
+            capacity,
+            sender_count: 1,
+            is_receiver_alive: true,
+            recv_waker: None,
+            send_wakers: HashMap::new(),
+        }),
+        next_waiter_id: AtomicU64::new(0),
+    });
+
+    (Sender { chan: chan.clone() }, Receiver { chan })
+}
+
+/// Sending half of an [`mpsc`](self) channel. Clone it for more producers.
+pub struct Sender<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `value`, waiting for capacity if the channel is full. Fails
+    /// with the value if the receiver has been dropped.
+    pub fn send(&self, value: T) -> SendFuture<'_, T> {
+        SendFuture {
+            chan: &self.chan,
+            value: Some(value),
+            waiter_id: self.chan.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.chan.state.lock().sender_count += 1;
+        Sender {
+            chan: self.chan.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.chan.state.lock();
+        state.sender_count -= 1;
+        if state.sender_count == 0
+            && let Some(waker) = state.recv_waker.take()
+        {
+            waker.wake();
+        }
+    }
+}
+
+/// Future returned by [`Sender::send`].
+pub struct SendFuture<'a, T> {
+    chan: &'a Chan<T>,
+    value: Option<T>,
+    waiter_id: WaiterId,
+}
+
+impl<T> Unpin for SendFuture<'_, T> {}
+
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut state = this.chan.state.lock();
+
+        let value = this
+            .value
+            .take()
+            .expect("SendFuture polled after completion");
+        if !state.is_receiver_alive {
+            return Poll::Ready(Err(SendError(value)));
+        }
+
+        if state.queue.len() < state.capacity {
+            state.queue.push_back(value);
+            state.send_wakers.remove(&this.waiter_id);
+            if let Some(waker) = state.recv_waker.take() {
+                waker.wake();
+            }
+            return Poll::Ready(Ok(()));
+        }
+
+        this.value = Some(value);
+        state.send_wakers.insert(this.waiter_id, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for SendFuture<'_, T> {
+    fn drop(&mut self) {
+        self.chan.state.lock().send_wakers.remove(&self.waiter_id);
+    }
+}
+
+/// Receiving half of an [`mpsc`](self) channel.
+pub struct Receiver<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Receives the next message, or `None` once every sender has been
+    /// dropped and the queue is drained.
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.chan.state.lock();
+
+        if let Some(value) = state.queue.pop_front() {
+            state.wake_senders();
+            return Poll::Ready(Some(value));
+        }
+
+        if state.sender_count == 0 {
+            return Poll::Ready(None);
+        }
+
+        state.recv_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Yields messages until every sender has been dropped and the queue is
+/// drained.
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.chan.state.lock();
+        state.is_receiver_alive = false;
+        state.wake_senders();
+    }
+}
+
+/// Error returned by [`Sender::send`] when the receiver has been dropped.
+/// Carries back the value that couldn't be delivered.
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::pin;
+    use std::sync::atomic::AtomicUsize;
+    use std::task::Wake;
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn create_counting_waker() -> (Waker, Arc<CountingWaker>) {
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        (Waker::from(counter.clone()), counter)
+    }
+
+    fn poll_once<F: Future>(future: Pin<&mut F>, waker: &Waker) -> Poll<F::Output> {
+        future.poll(&mut Context::from_waker(waker))
+    }
+
+    #[test]
+    fn send_waits_for_capacity() {
+        let (waker, counter) = create_counting_waker();
+        let (sender, mut receiver) = channel(1);
+
+        assert!(poll_once(pin!(sender.send(1)), &waker).is_ready());
+        let mut blocked = pin!(sender.send(2));
+        assert!(poll_once(blocked.as_mut(), &waker).is_pending());
+
+        assert_eq!(
+            poll_once(pin!(receiver.recv()), &waker),
+            Poll::Ready(Some(1))
+        );
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            poll_once(blocked.as_mut(), &waker),
+            Poll::Ready(Ok(()))
+        ));
+    }
+
+    #[test]
+    fn recv_returns_none_after_senders_drop() {
+        let (waker, _) = create_counting_waker();
+        let (sender, mut receiver) = channel(4);
+        let second = sender.clone();
+
+        assert!(poll_once(pin!(sender.send(1)), &waker).is_ready());
+        drop(sender);
+        drop(second);
+
+        assert_eq!(
+            poll_once(pin!(receiver.recv()), &waker),
+            Poll::Ready(Some(1))
+        );
+        assert_eq!(poll_once(pin!(receiver.recv()), &waker), Poll::Ready(None));
+    }
+
+    #[test]
+    fn dropping_last_sender_wakes_receiver() {
+        let (waker, counter) = create_counting_waker();
+        let (sender, mut receiver) = channel::<i32>(1);
+
+        assert!(poll_once(pin!(receiver.recv()), &waker).is_pending());
+        drop(sender);
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn send_fails_after_receiver_drops() {
+        let (waker, _) = create_counting_waker();
+        let (sender, receiver) = channel(1);
+        drop(receiver);
+
+        match poll_once(pin!(sender.send(7)), &waker) {
+            Poll::Ready(Err(SendError(value))) => assert_eq!(value, 7),
+            _ => panic!("send should fail once the receiver is gone"),
+        }
+    }
+
+    #[test]
+    fn dropped_send_future_releases_waker() {
+        let (waker, _) = create_counting_waker();
+        let (sender, _receiver) = channel(1);
+
+        assert!(poll_once(pin!(sender.send(1)), &waker).is_ready());
+        {
+            let blocked = pin!(sender.send(2));
+            assert!(poll_once(blocked, &waker).is_pending());
+        }
+
+        assert!(sender.chan.state.lock().send_wakers.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn channel_rejects_zero_capacity() {
+        let _ = channel::<()>(0);
+    }
+}
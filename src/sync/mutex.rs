@@ -0,0 +1,152 @@
+use std::ops::{Deref, DerefMut};
+
+use super::semaphore::{Semaphore, SemaphorePermit};
+
+const VALUE_MISSING: &str = "mutex value missing while locked";
+
+/// Async mutex whose guard can be held across `.await` points.
+///
+/// Contended lockers park their task instead of the worker thread and are
+/// granted the lock in FIFO order.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::Mutex;
+///
+/// let runtime = Runtime::new();
+/// let mutex = Mutex::new(0);
+///
+/// runtime.block_on(async {
+///     let mut value = mutex.lock().await;
+///     *value += 1;
+/// });
+///
+/// assert_eq!(mutex.into_inner(), 1);
+/// ```
+pub struct Mutex<T> {
+    semaphore: Semaphore,
+    // Holds the value while unlocked; the guard takes it out for the duration
+    // of the lock, so the inner lock is only ever held momentarily.
+    value: parking_lot::Mutex<Option<T>>,
+}
+
+impl<T> Mutex<T> {
+    pub fn new(value: T) -> Self {
+        Mutex {
+            semaphore: Semaphore::new(1),
+            value: parking_lot::Mutex::new(Some(value)),
+        }
+    }
+
+    /// Waits until the lock is free and acquires it.
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        let permit = self.semaphore.acquire().await;
+        self.guard(permit)
+    }
+
+    /// Acquires the lock if it's free right now.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.semaphore
+            .try_acquire()
+            .map(|permit| self.guard(permit))
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner().expect(VALUE_MISSING)
+    }
+
+    fn guard<'a>(&'a self, permit: SemaphorePermit<'a>) -> MutexGuard<'a, T> {
+        MutexGuard {
+            mutex: self,
+            value: Some(self.value.lock().take().expect(VALUE_MISSING)),
+            _permit: permit,
+        }
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Guard returned by [`Mutex::lock`]. Releases the lock on drop.
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+    value: Option<T>,
+    // Dropped after `Drop::drop` has put the value back, so the next locker
+    // always finds it in place.
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().expect(VALUE_MISSING)
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().expect(VALUE_MISSING)
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        *self.mutex.value.lock() = self.value.take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{Runtime, yield_now};
+
+    #[test]
+    fn try_lock_fails_while_locked() {
+        let mutex = Mutex::new(1);
+
+        let guard = mutex.try_lock().unwrap();
+        assert!(mutex.try_lock().is_none());
+
+        drop(guard);
+        assert_eq!(*mutex.try_lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn guard_writes_are_visible_to_next_locker() {
+        let mutex = Mutex::new(Vec::new());
+
+        mutex.try_lock().unwrap().push(1);
+        mutex.try_lock().unwrap().push(2);
+
+        assert_eq!(mutex.into_inner(), vec![1, 2]);
+    }
+
+    #[test]
+    fn guard_held_across_await_does_not_deadlock() {
+        let runtime = Runtime::new();
+        let mutex = std::sync::Arc::new(Mutex::new(0));
+        let contender = mutex.clone();
+
+        let handle = runtime
+            .spawner()
+            .spawn(async move {
+                *contender.lock().await += 1;
+            })
+            .unwrap();
+
+        runtime.block_on(async {
+            let mut guard = mutex.lock().await;
+            yield_now().await;
+            *guard += 1;
+        });
+        runtime.block_on(handle).unwrap();
+
+        assert_eq!(*mutex.try_lock().unwrap(), 2);
+    }
+}
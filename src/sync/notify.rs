@@ -0,0 +1,241 @@
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+
+type WaiterId = u64;
+
+/// Signals tasks without sending data.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::Notify;
+/// use std::sync::Arc;
+///
+/// let runtime = Runtime::new();
+/// let notify = Arc::new(Notify::new());
+/// let notifier = notify.clone();
+///
+/// runtime.spawner().spawn(async move {
+///     notifier.notify_one();
+/// }).unwrap();
+///
+/// runtime.block_on(notify.notified());
+/// ```
+pub struct Notify {
+    state: Mutex<NotifyState>,
+    next_waiter_id: AtomicU64,
+}
+
+struct NotifyState {
+    has_permit: bool,
+    waiters: VecDeque<(WaiterId, Waker)>,
+    /// Waiters that have been notified but not yet polled, mapped to whether
+    /// the notification came from `notify_one` and must be passed on if the
+    /// waiter is dropped before observing it.
+    notified: HashMap<WaiterId, bool>,
+}
+
+impl Notify {
+    pub fn new() -> Self {
+        Notify {
+            state: Mutex::new(NotifyState {
+                has_permit: false,
+                waiters: VecDeque::new(),
+                notified: HashMap::new(),
+            }),
+            next_waiter_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits for a notification.
+    pub fn notified(&self) -> Notified<'_> {
+        Notified {
+            notify: self,
+            waiter_id: self.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+            is_queued: false,
+        }
+    }
+
+    /// Wakes the longest-waiting task, or stores a permit that completes the
+    /// next [`notified`](Self::notified) call immediately if nobody is
+    /// waiting. At most one permit is stored.
+    pub fn notify_one(&self) {
+        let mut state = self.state.lock();
+        match state.waiters.pop_front() {
+            Some((id, waker)) => {
+                state.notified.insert(id, true);
+                waker.wake();
+            }
+            None => state.has_permit = true,
+        }
+    }
+
+    /// Wakes every task currently waiting. Does not store a permit.
+    pub fn notify_waiters(&self) {
+        let mut state = self.state.lock();
+        let waiters = std::mem::take(&mut state.waiters);
+        for (id, waker) in waiters {
+            state.notified.insert(id, false);
+            waker.wake();
+        }
+    }
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Future returned by [`Notify::notified`].
+pub struct Notified<'a> {
+    notify: &'a Notify,
+    waiter_id: WaiterId,
+    is_queued: bool,
+}
+
+impl Future for Notified<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.notify.state.lock();
+
+        if self.is_queued {
+            if state.notified.remove(&self.waiter_id).is_some() {
+                self.is_queued = false;
+                return Poll::Ready(());
+            }
+
+            if let Some((_, waker)) = state
+                .waiters
+                .iter_mut()
+                .find(|(id, _)| *id == self.waiter_id)
+                && !waker.will_wake(cx.waker())
+            {
+                *waker = cx.waker().clone();
+            }
+            return Poll::Pending;
+        }
+
+        if state.has_permit {
+            state.has_permit = false;
+            return Poll::Ready(());
+        }
+
+        state
+            .waiters
+            .push_back((self.waiter_id, cx.waker().clone()));
+        self.is_queued = true;
+        Poll::Pending
+    }
+}
+
+impl Drop for Notified<'_> {
+    fn drop(&mut self) {
+        if !self.is_queued {
+            return;
+        }
+
+        let mut state = self.notify.state.lock();
+        match state.notified.remove(&self.waiter_id) {
+            Some(true) => {
+                drop(state);
+                self.notify.notify_one();
+            }
+            Some(false) => {}
+            None => state.waiters.retain(|(id, _)| *id != self.waiter_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use std::task::Wake;
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn create_counting_waker() -> (Waker, Arc<CountingWaker>) {
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        (Waker::from(counter.clone()), counter)
+    }
+
+    #[test]
+    fn notify_before_wait_stores_permit() {
+        let (waker, _) = create_counting_waker();
+        let mut context = Context::from_waker(&waker);
+        let notify = Notify::new();
+
+        notify.notify_one();
+        notify.notify_one();
+
+        assert!(pin!(notify.notified()).poll(&mut context).is_ready());
+        assert!(pin!(notify.notified()).poll(&mut context).is_pending());
+    }
+
+    #[test]
+    fn notify_after_wait_wakes_waiter() {
+        let (waker, counter) = create_counting_waker();
+        let mut context = Context::from_waker(&waker);
+        let notify = Notify::new();
+        let mut notified = pin!(notify.notified());
+
+        assert!(notified.as_mut().poll(&mut context).is_pending());
+        notify.notify_one();
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+        assert!(notified.as_mut().poll(&mut context).is_ready());
+    }
+
+    #[test]
+    fn notify_waiters_wakes_all_without_permit() {
+        let (waker, counter) = create_counting_waker();
+        let mut context = Context::from_waker(&waker);
+        let notify = Notify::new();
+        let mut first = pin!(notify.notified());
+        let mut second = pin!(notify.notified());
+        assert!(first.as_mut().poll(&mut context).is_pending());
+        assert!(second.as_mut().poll(&mut context).is_pending());
+
+        notify.notify_waiters();
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 2);
+        assert!(first.as_mut().poll(&mut context).is_ready());
+        assert!(second.as_mut().poll(&mut context).is_ready());
+        assert!(pin!(notify.notified()).poll(&mut context).is_pending());
+    }
+
+    #[test]
+    fn dropped_waiter_passes_notification_on() {
+        let (waker, _) = create_counting_waker();
+        let mut context = Context::from_waker(&waker);
+        let notify = Notify::new();
+        let mut second = pin!(notify.notified());
+        {
+            let mut first = pin!(notify.notified());
+            assert!(first.as_mut().poll(&mut context).is_pending());
+            assert!(second.as_mut().poll(&mut context).is_pending());
+            notify.notify_one();
+        }
+
+        assert!(second.as_mut().poll(&mut context).is_ready());
+    }
+}
@@ -0,0 +1,263 @@
+use parking_lot::Mutex;
+use std::fmt;
+use std::future::{Future, poll_fn};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+struct Inner<T> {
+    state: Mutex<State<T>>,
+}
+
+struct State<T> {
+    value: Option<T>,
+    is_sender_closed: bool,
+    is_receiver_alive: bool,
+    receiver_waker: Option<Waker>,
+    sender_waker: Option<Waker>,
+}
+
+/// Creates a channel that carries a single value from one task to another.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::oneshot;
+///
+/// let runtime = Runtime::new();
+/// let (sender, receiver) = oneshot::channel();
+///
+/// runtime.spawner().spawn(async move {
+///     sender.send("pong").unwrap();
+/// }).unwrap();
+///
+/// assert_eq!(runtime.block_on(receiver), Ok("pong"));
+/// ```
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            value: None,
+            is_sender_closed: false,
+            is_receiver_alive: true,
+            receiver_waker: None,
+            sender_waker: None,
+        }),
+    });
+
+    (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver { inner },
+    )
+}
+
+/// Sending half of a [`oneshot`](self) channel.
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Delivers `value` to the receiver, handing it back if the receiver
+    /// has already been dropped.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let mut state = self.inner.state.lock();
+        if !state.is_receiver_alive {
+            return Err(value);
+        }
+
+        state.value = Some(value);
+        Ok(())
+    }
+
+    /// Returns whether the receiver has been dropped, in which case
+    /// [`send`](Self::send) would fail.
+    pub fn is_closed(&self) -> bool {
+        !self.inner.state.lock().is_receiver_alive
+    }
+
+    /// Resolves once the receiver is dropped. Lets a producer abandon
+    /// expensive work nobody is waiting for any more.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::sync::oneshot;
+    /// use runtime::{Either, Runtime, select2, sleep};
+    /// use std::pin::pin;
+    /// use std::time::Duration;
+    ///
+    /// let runtime = Runtime::new();
+    /// let (sender, receiver) = oneshot::channel::<u64>();
+    /// drop(receiver);
+    ///
+    /// runtime.block_on(async {
+    ///     let work = pin!(async {
+    ///         sleep(Duration::from_secs(60)).await;
+    ///         42
+    ///     });
+    ///     let outcome = select2(work, pin!(sender.closed())).await;
+    ///     match outcome {
+    ///         Either::Left(value) => drop(sender.send(value)),
+    ///         Either::Right(()) => {} // nobody is listening, give up
+    ///     }
+    /// });
+    /// ```
+    pub async fn closed(&self) {
+        poll_fn(|cx| {
+            let mut state = self.inner.state.lock();
+            if !state.is_receiver_alive {
+                return Poll::Ready(());
+            }
+
+            state.sender_waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock();
+        state.is_sender_closed = true;
+        if let Some(waker) = state.receiver_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Receiving half of a [`oneshot`](self) channel. Resolves to the sent
+/// value, or [`RecvError`] if the sender was dropped without sending.
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.inner.state.lock();
+
+        if let Some(value) = state.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+
+        if state.is_sender_closed {
+            return Poll::Ready(Err(RecvError));
+        }
+
+        state.receiver_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock();
+        state.is_receiver_alive = false;
+        if let Some(waker) = state.sender_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Error returned by [`Receiver`] when the sender is dropped without
+/// sending a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sender dropped without sending a value")
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn create_counting_waker() -> (Waker, Arc<CountingWaker>) {
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        (Waker::from(counter.clone()), counter)
+    }
+
+    #[test]
+    fn send_delivers_value_and_wakes_receiver() {
+        let (waker, counter) = create_counting_waker();
+        let (sender, mut receiver) = channel();
+
+        assert!(
+            Pin::new(&mut receiver)
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending()
+        );
+        sender.send(5).unwrap();
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            Pin::new(&mut receiver).poll(&mut Context::from_waker(&waker)),
+            Poll::Ready(Ok(5))
+        );
+    }
+
+    #[test]
+    fn dropped_sender_resolves_with_error() {
+        let (waker, _) = create_counting_waker();
+        let (sender, mut receiver) = channel::<i32>();
+
+        drop(sender);
+
+        assert_eq!(
+            Pin::new(&mut receiver).poll(&mut Context::from_waker(&waker)),
+            Poll::Ready(Err(RecvError))
+        );
+    }
+
+    #[test]
+    fn send_to_dropped_receiver_returns_value() {
+        let (sender, receiver) = channel();
+
+        drop(receiver);
+
+        assert_eq!(sender.send("lost"), Err("lost"));
+    }
+
+    #[test]
+    fn dropping_receiver_resolves_closed() {
+        let (waker, counter) = create_counting_waker();
+        let (sender, receiver) = channel::<i32>();
+        let mut closed = pin!(sender.closed());
+
+        assert!(
+            closed
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending()
+        );
+        assert!(!sender.is_closed());
+        drop(receiver);
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+        assert!(closed.poll(&mut Context::from_waker(&waker)).is_ready());
+        assert!(sender.is_closed());
+    }
+}
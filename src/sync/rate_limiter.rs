@@ -0,0 +1,174 @@
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::timer::{Reactor, SleepFuture, current_reactor};
+
+/// Async token-bucket rate limiter.
+///
+/// The bucket holds up to `burst` tokens and refills continuously at
+/// `rate_per_sec`, computed from the time elapsed since the last
+/// [`acquire`](RateLimiter::acquire) rather than on a fixed tick. Callers are
+/// served in the order they first poll `acquire`. Time is read from the
+/// reactor current when the limiter is created, so a
+/// [`TestClock`](crate::timer::TestClock) entered then drives it.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::RateLimiter;
+///
+/// let runtime = Runtime::new();
+/// let limiter = RateLimiter::new(100, 2);
+///
+/// runtime.block_on(async {
+///     for _ in 0..4 {
+///         limiter.acquire().await;
+///         // ... send a request ...
+///     }
+/// });
+/// ```
+pub struct RateLimiter {
+    reactor: Arc<Reactor>,
+    state: Mutex<BucketState>,
+    rate_per_sec: f64,
+    burst: f64,
+}
+
+struct BucketState {
+    /// Goes negative while callers are queued for tokens not yet minted.
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a limiter that starts with a full bucket. A rate or burst of
+    /// zero behaves like one.
+    pub fn new(rate_per_sec: u32, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        let reactor = current_reactor();
+        RateLimiter {
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                refilled_at: reactor.now(),
+            }),
+            reactor,
+            rate_per_sec: f64::from(rate_per_sec.max(1)),
+            burst,
+        }
+    }
+
+    /// Takes a token, sleeping until the next one is minted if the bucket is
+    /// empty.
+    ///
+    /// The token is reserved on the first poll, so dropping the future while
+    /// it sleeps still consumes it.
+    pub async fn acquire(&self) {
+        if let Some(ready_at) = self.reserve(self.reactor.now()) {
+            SleepFuture::new(self.reactor.clone(), ready_at).await;
+        }
+    }
+
+    /// Takes a token now if one is available, without waiting or reserving.
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock();
+        self.refill(&mut state, self.reactor.now());
+        if state.tokens < 1.0 {
+            return false;
+        }
+
+        state.tokens -= 1.0;
+        true
+    }
+
+    /// Reserves a token and returns when it becomes usable, or `None` if it
+    /// is usable right away.
+    fn reserve(&self, now: Instant) -> Option<Instant> {
+        let mut state = self.state.lock();
+        self.refill(&mut state, now);
+        state.tokens -= 1.0;
+        if state.tokens >= 0.0 {
+            return None;
+        }
+
+        Some(now + Duration::from_secs_f64(-state.tokens / self.rate_per_sec))
+    }
+
+    fn refill(&self, state: &mut BucketState, now: Instant) {
+        let elapsed = now.saturating_duration_since(state.refilled_at);
+        state.tokens = (state.tokens + elapsed.as_secs_f64() * self.rate_per_sec).min(self.burst);
+        state.refilled_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::TestClock;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Waker};
+
+    #[test]
+    fn burst_is_available_immediately() {
+        let limiter = RateLimiter::new(1, 3);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(now), None);
+        }
+        assert!(limiter.reserve(now).is_some());
+    }
+
+    #[test]
+    fn queued_reservations_are_spaced_by_the_rate() {
+        let limiter = RateLimiter::new(4, 1);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(now), None);
+
+        assert_eq!(limiter.reserve(now), Some(now + Duration::from_millis(250)));
+        assert_eq!(limiter.reserve(now), Some(now + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn refill_is_continuous_and_capped_at_burst() {
+        let limiter = RateLimiter::new(10, 2);
+        let now = Instant::now();
+        limiter.reserve(now);
+        limiter.reserve(now);
+
+        assert_eq!(limiter.reserve(now + Duration::from_millis(100)), None);
+        assert!(limiter.reserve(now + Duration::from_millis(100)).is_some());
+
+        let later = now + Duration::from_secs(60);
+        limiter.reserve(later);
+        assert!((limiter.state.lock().tokens - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn try_acquire_does_not_reserve() {
+        let limiter = RateLimiter::new(1, 1);
+
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        assert!(limiter.state.lock().tokens >= 0.0);
+    }
+
+    #[test]
+    fn acquire_follows_the_test_clock() {
+        let clock = TestClock::new();
+        let _guard = clock.enter();
+        let limiter = RateLimiter::new(1, 1);
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(limiter.try_acquire());
+
+        let mut acquire = pin!(limiter.acquire());
+        assert!(acquire.as_mut().poll(&mut context).is_pending());
+
+        clock.advance(Duration::from_millis(999));
+        assert!(acquire.as_mut().poll(&mut context).is_pending());
+        clock.advance(Duration::from_millis(1));
+        assert!(acquire.as_mut().poll(&mut context).is_ready());
+    }
+}
@@ -0,0 +1,230 @@
+use std::cell::UnsafeCell;
+use std::ops::{Deref, DerefMut};
+
+use super::semaphore::{Semaphore, SemaphorePermit};
+
+/// Upper bound on concurrent readers. A writer takes all of them at once.
+const MAX_READERS: usize = u32::MAX as usize >> 3;
+
+/// Async reader-writer lock whose guards can be held across `.await` points.
+///
+/// Any number of readers or a single writer hold the lock at a time. Tasks
+/// waiting for it park instead of blocking their worker and are admitted in
+/// FIFO order, so a waiting writer holds back readers that arrive after it
+/// and is never starved.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::RwLock;
+///
+/// let runtime = Runtime::new();
+/// let lock = RwLock::new(5);
+///
+/// runtime.block_on(async {
+///     {
+///         let first = lock.read().await;
+///         let second = lock.read().await;
+///         assert_eq!(*first + *second, 10);
+///     }
+///
+///     *lock.write().await += 1;
+/// });
+///
+/// assert_eq!(lock.into_inner(), 6);
+/// ```
+pub struct RwLock<T> {
+    semaphore: Semaphore,
+    // Only touched through a guard, and guards only exist while holding
+    // permits: one per reader, or all of them for the writer.
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the semaphore hands out either shared access to any number of
+// readers or exclusive access to one writer, as `std::sync::RwLock` does, so
+// the same bounds apply.
+unsafe impl<T: Send> Send for RwLock<T> {}
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    pub fn new(value: T) -> Self {
+        RwLock {
+            semaphore: Semaphore::new(MAX_READERS),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Waits until no writer holds or is queued for the lock, then acquires
+    /// shared access.
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        let permit = self.semaphore.acquire_permits(1).await;
+        self.read_guard(permit)
+    }
+
+    /// Waits until the lock is free, then acquires exclusive access.
+    pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
+        let permit = self.semaphore.acquire_permits(MAX_READERS).await;
+        self.write_guard(permit)
+    }
+
+    /// Acquires shared access if that's possible right now.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.semaphore
+            .try_acquire_permits(1)
+            .map(|permit| self.read_guard(permit))
+    }
+
+    /// Acquires exclusive access if the lock is free right now.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.semaphore
+            .try_acquire_permits(MAX_READERS)
+            .map(|permit| self.write_guard(permit))
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    fn read_guard<'a>(&'a self, permit: SemaphorePermit<'a>) -> RwLockReadGuard<'a, T> {
+        RwLockReadGuard {
+            lock: self,
+            _permit: permit,
+        }
+    }
+
+    fn write_guard<'a>(&'a self, permit: SemaphorePermit<'a>) -> RwLockWriteGuard<'a, T> {
+        RwLockWriteGuard {
+            lock: self,
+            _permit: permit,
+        }
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Guard returned by [`RwLock::read`]. Releases shared access on drop.
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: a read permit rules out a writer for the guard's lifetime.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+/// Guard returned by [`RwLock::write`]. Releases exclusive access on drop.
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the writer holds every permit, so no other guard exists.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: as for `deref`; `&mut self` keeps this borrow unique.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    #[test]
+    fn readers_share_the_lock() {
+        let lock = RwLock::new(1);
+
+        let first = lock.try_read().unwrap();
+        let second = lock.try_read().unwrap();
+
+        assert_eq!(*first + *second, 2);
+        assert!(lock.try_write().is_none());
+    }
+
+    #[test]
+    fn writer_acquires_after_readers_drop() {
+        let lock = RwLock::new(0);
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let reader = lock.try_read().unwrap();
+        let mut write = pin!(lock.write());
+
+        assert!(write.as_mut().poll(&mut context).is_pending());
+
+        drop(reader);
+        match write.as_mut().poll(&mut context) {
+            Poll::Ready(mut guard) => *guard = 7,
+            Poll::Pending => panic!("writer not admitted after reader dropped"),
+        }
+        assert_eq!(*lock.try_read().unwrap(), 7);
+    }
+
+    #[test]
+    fn queued_writer_blocks_new_readers() {
+        let lock = RwLock::new(0);
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let reader = lock.try_read().unwrap();
+        let mut write = pin!(lock.write());
+        let _ = write.as_mut().poll(&mut context);
+
+        assert!(lock.try_read().is_none());
+        let mut late_read = pin!(lock.read());
+        assert!(late_read.as_mut().poll(&mut context).is_pending());
+
+        drop(reader);
+        let guard = match write.as_mut().poll(&mut context) {
+            Poll::Ready(guard) => guard,
+            Poll::Pending => panic!("writer starved by readers"),
+        };
+        assert!(late_read.as_mut().poll(&mut context).is_pending());
+
+        drop(guard);
+        assert!(late_read.as_mut().poll(&mut context).is_ready());
+    }
+
+    #[test]
+    fn write_guard_excludes_readers_on_other_threads() {
+        let lock = Arc::new(RwLock::new(0));
+        let mut guard = lock.try_write().unwrap();
+        *guard += 1;
+
+        let reader = {
+            let lock = lock.clone();
+            std::thread::spawn(move || lock.try_read().map(|value| *value))
+        };
+
+        assert_eq!(reader.join().unwrap(), None);
+        drop(guard);
+        assert_eq!(*lock.try_read().unwrap(), 1);
+    }
+
+    #[test]
+    fn guards_are_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<RwLockReadGuard<'_, u32>>();
+        assert_send::<RwLockWriteGuard<'_, u32>>();
+    }
+}
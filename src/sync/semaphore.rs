@@ -0,0 +1,382 @@
+use parking_lot::Mutex;
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+
+type WaiterId = u64;
+
+/// Async counting semaphore. Waiters are granted permits in FIFO order.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::Semaphore;
+///
+/// let runtime = Runtime::new();
+/// let semaphore = Semaphore::new(1);
+///
+/// runtime.block_on(async {
+///     let _permit = semaphore.acquire().await;
+///     assert!(semaphore.try_acquire().is_none());
+/// });
+/// assert_eq!(semaphore.available_permits(), 1);
+/// ```
+pub struct Semaphore {
+    state: Mutex<SemaphoreState>,
+    next_waiter_id: AtomicU64,
+    /// Permits the semaphore was created with; no request may exceed it.
+    total_permits: usize,
+}
+
+struct SemaphoreState {
+    permits: usize,
+    waiters: VecDeque<Waiter>,
+    granted: HashSet<WaiterId>,
+}
+
+struct Waiter {
+    id: WaiterId,
+    needed: usize,
+    waker: Waker,
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Semaphore {
+            state: Mutex::new(SemaphoreState {
+                permits,
+                waiters: VecDeque::new(),
+                granted: HashSet::new(),
+            }),
+            next_waiter_id: AtomicU64::new(0),
+            total_permits: permits,
+        }
+    }
+
+    /// Waits until a permit is available and takes it.
+    pub fn acquire(&self) -> Acquire<'_> {
+        self.acquire_permits(1)
+    }
+
+    /// Waits until `n` permits are available at once and takes them
+    /// together. The returned permit gives all `n` back when dropped.
+    ///
+    /// Requests are served strictly in FIFO order, so a large request at the
+    /// front of the queue holds back smaller ones behind it instead of being
+    /// starved by them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` exceeds the number of permits the semaphore was created
+    /// with, since such a request could never be granted and would block
+    /// every request queued behind it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::Runtime;
+    /// use runtime::sync::Semaphore;
+    ///
+    /// let runtime = Runtime::new();
+    /// let memory_budget = Semaphore::new(10);
+    ///
+    /// runtime.block_on(async {
+    ///     let _reserved = memory_budget.acquire_many(4).await;
+    ///     assert_eq!(memory_budget.available_permits(), 6);
+    /// });
+    /// assert_eq!(memory_budget.available_permits(), 10);
+    /// ```
+    pub fn acquire_many(&self, n: u32) -> Acquire<'_> {
+        self.acquire_permits(n as usize)
+    }
+
+    /// Takes a permit if one is available right now. Never jumps ahead of
+    /// tasks already waiting in [`acquire`](Self::acquire).
+    pub fn try_acquire(&self) -> Option<SemaphorePermit<'_>> {
+        self.try_acquire_permits(1)
+    }
+
+    pub(super) fn acquire_permits(&self, permits: usize) -> Acquire<'_> {
+        assert!(
+            permits <= self.total_permits,
+            "requested {} permits from a semaphore with {}",
+            permits,
+            self.total_permits
+        );
+        Acquire {
+            semaphore: self,
+            waiter_id: self.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+            needed: permits,
+            is_queued: false,
+        }
+    }
+
+    pub(super) fn try_acquire_permits(&self, permits: usize) -> Option<SemaphorePermit<'_>> {
+        let mut state = self.state.lock();
+        if !state.waiters.is_empty() || state.permits < permits {
+            return None;
+        }
+
+        state.permits -= permits;
+        Some(SemaphorePermit {
+            semaphore: self,
+            permits,
+        })
+    }
+
+    pub fn available_permits(&self) -> usize {
+        self.state.lock().permits
+    }
+
+    /// Returns `count` permits and hands them to waiters at the front of the
+    /// queue for as long as their requests can be satisfied.
+    fn release(&self, count: usize) {
+        let mut state = self.state.lock();
+        state.permits += count;
+        state.grant_waiters();
+    }
+}
+
+impl SemaphoreState {
+    fn grant_waiters(&mut self) {
+        while self
+            .waiters
+            .front()
+            .is_some_and(|waiter| waiter.needed <= self.permits)
+        {
+            let waiter = self.waiters.pop_front().expect("front waiter checked");
+            self.permits -= waiter.needed;
+            self.granted.insert(waiter.id);
+            waiter.waker.wake();
+        }
+    }
+}
+
+/// Future returned by [`Semaphore::acquire`].
+pub struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    waiter_id: WaiterId,
+    needed: usize,
+    is_queued: bool,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = SemaphorePermit<'a>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let semaphore = self.semaphore;
+        let mut state = semaphore.state.lock();
+
+        if self.is_queued {
+            if state.granted.remove(&self.waiter_id) {
+                self.is_queued = false;
+                return Poll::Ready(self.permit());
+            }
+
+            if let Some(waiter) = state
+                .waiters
+                .iter_mut()
+                .find(|waiter| waiter.id == self.waiter_id)
+                && !waiter.waker.will_wake(cx.waker())
+            {
+                waiter.waker = cx.waker().clone();
+            }
+            return Poll::Pending;
+        }
+
+        if state.waiters.is_empty() && state.permits >= self.needed {
+            state.permits -= self.needed;
+            return Poll::Ready(self.permit());
+        }
+
+        state.waiters.push_back(Waiter {
+            id: self.waiter_id,
+            needed: self.needed,
+            waker: cx.waker().clone(),
+        });
+        self.is_queued = true;
+        Poll::Pending
+    }
+}
+
+impl<'a> Acquire<'a> {
+    fn permit(&self) -> SemaphorePermit<'a> {
+        SemaphorePermit {
+            semaphore: self.semaphore,
+            permits: self.needed,
+        }
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        if !self.is_queued {
+            return;
+        }
+
+        let mut state = self.semaphore.state.lock();
+        if state.granted.remove(&self.waiter_id) {
+            // Permits were handed to us but never picked up; pass them on.
+            drop(state);
+            self.semaphore.release(self.needed);
+        } else {
+            state.waiters.retain(|waiter| waiter.id != self.waiter_id);
+            // A large request leaving the front may unblock smaller ones.
+            state.grant_waiters();
+        }
+    }
+}
+
+/// A permit held from a [`Semaphore`]. Returned to the semaphore on drop.
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.release(self.permits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::pin;
+
+    #[test]
+    fn try_acquire_respects_permit_count() {
+        let semaphore = Semaphore::new(2);
+
+        let first = semaphore.try_acquire();
+        let second = semaphore.try_acquire();
+
+        assert!(first.is_some() && second.is_some());
+        assert!(semaphore.try_acquire().is_none());
+
+        drop(first);
+        assert_eq!(semaphore.available_permits(), 1);
+    }
+
+    #[test]
+    fn waiters_are_granted_in_fifo_order() {
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let semaphore = Semaphore::new(1);
+        let held = semaphore.try_acquire().unwrap();
+
+        let mut first = pin!(semaphore.acquire());
+        let mut second = pin!(semaphore.acquire());
+        assert!(first.as_mut().poll(&mut context).is_pending());
+        assert!(second.as_mut().poll(&mut context).is_pending());
+
+        drop(held);
+
+        assert!(second.as_mut().poll(&mut context).is_pending());
+        assert!(first.as_mut().poll(&mut context).is_ready());
+    }
+
+    #[test]
+    fn try_acquire_does_not_barge_past_waiters() {
+        let waker = Waker::noop();
+        let semaphore = Semaphore::new(1);
+        let held = semaphore.try_acquire().unwrap();
+        let mut waiting = pin!(semaphore.acquire());
+        assert!(
+            waiting
+                .as_mut()
+                .poll(&mut Context::from_waker(waker))
+                .is_pending()
+        );
+
+        drop(held);
+
+        assert!(semaphore.try_acquire().is_none());
+    }
+
+    #[test]
+    fn large_request_holds_back_later_small_ones() {
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let semaphore = Semaphore::new(5);
+        let held = semaphore.try_acquire_permits(3).unwrap();
+
+        let mut large = pin!(semaphore.acquire_many(3));
+        let mut small = pin!(semaphore.acquire());
+        assert!(large.as_mut().poll(&mut context).is_pending());
+        assert!(small.as_mut().poll(&mut context).is_pending());
+        assert_eq!(semaphore.available_permits(), 2);
+
+        drop(held);
+
+        let Poll::Ready(_large_permit) = large.as_mut().poll(&mut context) else {
+            panic!("large request should be granted first");
+        };
+        let Poll::Ready(_small_permit) = small.as_mut().poll(&mut context) else {
+            panic!("small request should fit next to the large one");
+        };
+        assert_eq!(semaphore.available_permits(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "requested 6 permits from a semaphore with 5")]
+    fn acquire_many_beyond_total_permits_panics() {
+        let semaphore = Semaphore::new(5);
+
+        let _never = semaphore.acquire_many(6);
+    }
+
+    #[test]
+    fn acquire_many_of_all_permits_is_granted() {
+        let waker = Waker::noop();
+        let semaphore = Semaphore::new(5);
+
+        let mut everything = pin!(semaphore.acquire_many(5));
+
+        assert!(
+            everything
+                .as_mut()
+                .poll(&mut Context::from_waker(waker))
+                .is_ready()
+        );
+    }
+
+    #[test]
+    fn cancelled_large_request_unblocks_waiters_behind_it() {
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let semaphore = Semaphore::new(2);
+        let held = semaphore.try_acquire().unwrap();
+
+        let mut small = pin!(semaphore.acquire());
+        {
+            let mut large = pin!(semaphore.acquire_many(2));
+            assert!(large.as_mut().poll(&mut context).is_pending());
+            assert!(small.as_mut().poll(&mut context).is_pending());
+        }
+
+        assert!(small.as_mut().poll(&mut context).is_ready());
+        drop(held);
+    }
+
+    #[test]
+    fn dropping_granted_waiter_passes_permit_on() {
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let semaphore = Semaphore::new(1);
+        let held = semaphore.try_acquire().unwrap();
+
+        let mut second = pin!(semaphore.acquire());
+        {
+            let mut first = pin!(semaphore.acquire());
+            assert!(first.as_mut().poll(&mut context).is_pending());
+            assert!(second.as_mut().poll(&mut context).is_pending());
+            drop(held);
+        }
+
+        assert!(second.as_mut().poll(&mut context).is_ready());
+    }
+}
@@ -0,0 +1,188 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+
+type WaiterId = u64;
+
+/// Waits for a group of tasks to finish.
+///
+/// Each task holds a [`WaitGroupGuard`] from [`add`](WaitGroup::add);
+/// [`wait`](WaitGroup::wait) resolves once every guard has been dropped.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::WaitGroup;
+///
+/// let runtime = Runtime::new();
+/// let group = WaitGroup::new();
+///
+/// for _ in 0..4 {
+///     let guard = group.add();
+///     runtime.spawner().spawn(async move {
+///         // ... work ...
+///         drop(guard);
+///     }).unwrap();
+/// }
+///
+/// runtime.block_on(group.wait());
+/// ```
+pub struct WaitGroup {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    state: Mutex<WaitGroupState>,
+    next_waiter_id: AtomicU64,
+}
+
+struct WaitGroupState {
+    count: usize,
+    wakers: HashMap<WaiterId, Waker>,
+}
+
+impl WaitGroup {
+    pub fn new() -> Self {
+        WaitGroup {
+            inner: Arc::new(Inner {
+                state: Mutex::new(WaitGroupState {
+                    count: 0,
+                    wakers: HashMap::new(),
+                }),
+                next_waiter_id: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Adds one to the count. The count drops again when the returned guard
+    /// is dropped.
+    pub fn add(&self) -> WaitGroupGuard {
+        self.inner.state.lock().count += 1;
+        WaitGroupGuard {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Number of guards still alive.
+    pub fn count(&self) -> usize {
+        self.inner.state.lock().count
+    }
+
+    /// Waits until the count reaches zero. Resolves immediately if no guard
+    /// is alive.
+    pub fn wait(&self) -> Wait<'_> {
+        Wait {
+            group: self,
+            waiter_id: self.inner.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for WaitGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps a [`WaitGroup`] from completing until dropped.
+pub struct WaitGroupGuard {
+    inner: Arc<Inner>,
+}
+
+impl Drop for WaitGroupGuard {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock();
+        state.count -= 1;
+        if state.count > 0 {
+            return;
+        }
+
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+}
+
+/// Future returned by [`WaitGroup::wait`].
+pub struct Wait<'a> {
+    group: &'a WaitGroup,
+    waiter_id: WaiterId,
+}
+
+impl Future for Wait<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.group.inner.state.lock();
+        if state.count == 0 {
+            state.wakers.remove(&self.waiter_id);
+            return Poll::Ready(());
+        }
+
+        match state.wakers.get_mut(&self.waiter_id) {
+            Some(stored) if stored.will_wake(cx.waker()) => {}
+            Some(stored) => *stored = cx.waker().clone(),
+            None => {
+                state.wakers.insert(self.waiter_id, cx.waker().clone());
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Wait<'_> {
+    fn drop(&mut self) {
+        self.group.inner.state.lock().wakers.remove(&self.waiter_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll_wait(wait: &mut Wait<'_>) -> Poll<()> {
+        let waker = Waker::noop();
+        Pin::new(wait).poll(&mut Context::from_waker(waker))
+    }
+
+    #[test]
+    fn empty_group_is_ready() {
+        let group = WaitGroup::new();
+        assert!(poll_wait(&mut group.wait()).is_ready());
+    }
+
+    #[test]
+    fn wait_completes_after_last_guard_drops() {
+        let group = WaitGroup::new();
+        let first = group.add();
+        let second = group.add();
+        let mut wait = group.wait();
+
+        assert!(poll_wait(&mut wait).is_pending());
+        drop(first);
+        assert_eq!(group.count(), 1);
+        assert!(poll_wait(&mut wait).is_pending());
+
+        drop(second);
+        assert!(poll_wait(&mut wait).is_ready());
+    }
+
+    #[test]
+    fn dropping_wait_releases_waker() {
+        let group = WaitGroup::new();
+        let _guard = group.add();
+        let mut wait = group.wait();
+
+        let _ = poll_wait(&mut wait);
+        drop(wait);
+
+        assert!(group.inner.state.lock().wakers.is_empty());
+    }
+}
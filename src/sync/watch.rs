@@ -0,0 +1,296 @@
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::poll_fn;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Poll, Waker};
+
+type WaiterId = u64;
+
+struct Shared<T> {
+    value: RwLock<T>,
+    state: Mutex<WatchState>,
+    next_waiter_id: AtomicU64,
+}
+
+struct WatchState {
+    /// Bumped on every send, so receivers can tell whether they've seen the
+    /// latest value.
+    version: u64,
+    is_closed: bool,
+    wakers: HashMap<WaiterId, Waker>,
+}
+
+/// Creates a channel that holds a single value, starting at `initial`.
+/// Receivers only ever see the latest value; intermediate updates they
+/// didn't look at in time are skipped.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::sync::watch;
+///
+/// let runtime = Runtime::new();
+/// let (sender, mut receiver) = watch::channel("initial");
+///
+/// sender.send("reloaded");
+///
+/// runtime.block_on(async {
+///     receiver.changed().await.unwrap();
+///     assert_eq!(*receiver.borrow(), "reloaded");
+/// });
+/// ```
+pub fn channel<T>(initial: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        value: RwLock::new(initial),
+        state: Mutex::new(WatchState {
+            version: 0,
+            is_closed: false,
+            wakers: HashMap::new(),
+        }),
+        next_waiter_id: AtomicU64::new(0),
+    });
+
+    let sender = Sender { shared };
+    let receiver = sender.subscribe();
+    (sender, receiver)
+}
+
+/// Sending half of a [`watch`](self) channel.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Replaces the value and wakes every receiver waiting in
+    /// [`Receiver::changed`].
+    pub fn send(&self, value: T) {
+        *self.shared.value.write() = value;
+
+        let mut state = self.shared.state.lock();
+        state.version += 1;
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+
+    /// Returns a reference to the current value.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.value.read(),
+        }
+    }
+
+    /// Creates a receiver that considers the current value already seen.
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver {
+            shared: self.shared.clone(),
+            seen_version: self.shared.state.lock().version,
+            waiter_id: self.shared.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.is_closed = true;
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+}
+
+/// Receiving half of a [`watch`](self) channel. Clones start out having seen
+/// the same version as the original.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    seen_version: u64,
+    waiter_id: WaiterId,
+}
+
+impl<T> Receiver<T> {
+    /// Returns a reference to the current value without marking it seen.
+    ///
+    /// The reference holds a read lock on the value, so don't keep it across
+    /// an `.await`: the sender blocks until it's dropped.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.value.read(),
+        }
+    }
+
+    /// Waits until a value is sent that this receiver hasn't seen yet and
+    /// marks it seen. Resolves immediately if one was sent since the last
+    /// call.
+    ///
+    /// Fails once the sender has been dropped and no unseen value is left.
+    pub async fn changed(&mut self) -> Result<(), RecvError> {
+        poll_fn(|cx| {
+            let mut state = self.shared.state.lock();
+
+            if state.version != self.seen_version {
+                self.seen_version = state.version;
+                state.wakers.remove(&self.waiter_id);
+                return Poll::Ready(Ok(()));
+            }
+
+            if state.is_closed {
+                return Poll::Ready(Err(RecvError));
+            }
+
+            state.wakers.insert(self.waiter_id, cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Receiver {
+            shared: self.shared.clone(),
+            seen_version: self.seen_version,
+            waiter_id: self.shared.next_waiter_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.state.lock().wakers.remove(&self.waiter_id);
+    }
+}
+
+/// Reference to the value in a [`watch`](self) channel, returned by
+/// `borrow`.
+pub struct Ref<'a, T> {
+    guard: RwLockReadGuard<'a, T>,
+}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+/// Error returned by [`Receiver::changed`] once the sender has been dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sender dropped")
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::atomic::AtomicUsize;
+    use std::task::{Context, Wake};
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn create_counting_waker() -> (Waker, Arc<CountingWaker>) {
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        (Waker::from(counter.clone()), counter)
+    }
+
+    fn poll_changed<T>(receiver: &mut Receiver<T>, waker: &Waker) -> Poll<Result<(), RecvError>> {
+        pin!(receiver.changed()).poll(&mut Context::from_waker(waker))
+    }
+
+    #[test]
+    fn initial_value_is_already_seen() {
+        let (waker, _) = create_counting_waker();
+        let (_sender, mut receiver) = channel(1);
+
+        assert_eq!(*receiver.borrow(), 1);
+        assert!(poll_changed(&mut receiver, &waker).is_pending());
+    }
+
+    #[test]
+    fn changed_skips_to_latest_value() {
+        let (waker, _) = create_counting_waker();
+        let (sender, mut receiver) = channel(0);
+
+        sender.send(1);
+        sender.send(2);
+
+        assert_eq!(poll_changed(&mut receiver, &waker), Poll::Ready(Ok(())));
+        assert_eq!(*receiver.borrow(), 2);
+        assert!(poll_changed(&mut receiver, &waker).is_pending());
+    }
+
+    #[test]
+    fn send_wakes_every_waiting_receiver() {
+        let (waker, counter) = create_counting_waker();
+        let (sender, mut first) = channel(0);
+        let mut second = first.clone();
+
+        assert!(poll_changed(&mut first, &waker).is_pending());
+        assert!(poll_changed(&mut second, &waker).is_pending());
+        sender.send(1);
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 2);
+        assert_eq!(poll_changed(&mut first, &waker), Poll::Ready(Ok(())));
+        assert_eq!(poll_changed(&mut second, &waker), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn dropping_sender_fails_changed() {
+        let (waker, counter) = create_counting_waker();
+        let (sender, mut receiver) = channel(0);
+
+        assert!(poll_changed(&mut receiver, &waker).is_pending());
+        drop(sender);
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            poll_changed(&mut receiver, &waker),
+            Poll::Ready(Err(RecvError))
+        );
+        assert_eq!(*receiver.borrow(), 0);
+    }
+
+    #[test]
+    fn unseen_value_is_reported_before_close() {
+        let (waker, _) = create_counting_waker();
+        let (sender, mut receiver) = channel(0);
+
+        sender.send(5);
+        drop(sender);
+
+        assert_eq!(poll_changed(&mut receiver, &waker), Poll::Ready(Ok(())));
+        assert_eq!(
+            poll_changed(&mut receiver, &waker),
+            Poll::Ready(Err(RecvError))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread::LocalKey;
+
+/// Declares a [`TaskLocal`] key.
+///
+/// # Example
+///
+/// ```
+/// runtime::task_local! {
+///     static REQUEST_ID: u64;
+/// }
+///
+/// let runtime = runtime::Runtime::new();
+/// let id = runtime.block_on(REQUEST_ID.scope(7, async { REQUEST_ID.get() }));
+/// assert_eq!(id, 7);
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty;) => {
+        $(#[$attr])*
+        $vis static $name: $crate::task_local::TaskLocal<$t> = {
+            ::std::thread_local! {
+                static VALUE: ::std::cell::RefCell<::std::option::Option<$t>> =
+                    const { ::std::cell::RefCell::new(::std::option::Option::None) };
+            }
+            $crate::task_local::TaskLocal { value: &VALUE }
+        };
+    };
+}
+
+/// Value scoped to a future rather than a thread.
+///
+/// Declared with [`task_local!`](crate::task_local!) and set with
+/// [`scope`](TaskLocal::scope). The value is swapped into a thread-local
+/// around every poll of the scoped future, so it follows the task across
+/// workers and is only visible to code running inside that future.
+pub struct TaskLocal<T: 'static> {
+    #[doc(hidden)]
+    pub value: &'static LocalKey<RefCell<Option<T>>>,
+}
+
+impl<T: 'static> TaskLocal<T> {
+    /// Runs `future` with this key set to `value`. A scope nested inside
+    /// another scope of the same key shadows it until the inner future
+    /// completes.
+    pub fn scope<F: Future>(&'static self, value: T, future: F) -> TaskLocalFuture<T, F> {
+        TaskLocalFuture {
+            key: self,
+            slot: Some(value),
+            future: Box::pin(future),
+        }
+    }
+
+    /// Calls `f` with a reference to the current value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a [`scope`](TaskLocal::scope) of this key.
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        self.try_with(f)
+            .expect("task-local value accessed outside of its scope")
+    }
+
+    /// Like [`with`](TaskLocal::with), but returns `None` outside a scope.
+    pub fn try_with<R>(&'static self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.value.with(|value| value.borrow().as_ref().map(f))
+    }
+}
+
+impl<T: Clone + 'static> TaskLocal<T> {
+    /// Returns a copy of the current value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a [`scope`](TaskLocal::scope) of this key.
+    pub fn get(&'static self) -> T {
+        self.with(T::clone)
+    }
+}
+
+/// Future returned by [`TaskLocal::scope`].
+pub struct TaskLocalFuture<T: 'static, F> {
+    key: &'static TaskLocal<T>,
+    /// Holds the value while the future isn't being polled.
+    slot: Option<T>,
+    future: Pin<Box<F>>,
+}
+
+impl<T: 'static, F> Unpin for TaskLocalFuture<T, F> {}
+
+impl<T: 'static, F: Future> Future for TaskLocalFuture<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let _entered = Entered::swap(this.key, &mut this.slot);
+        this.future.as_mut().poll(cx)
+    }
+}
+
+/// Swaps a scope's value into the thread-local and back out on drop, so the
+/// previous value is restored even if the poll panics.
+struct Entered<'a, T: 'static> {
+    key: &'static TaskLocal<T>,
+    slot: &'a mut Option<T>,
+}
+
+impl<'a, T: 'static> Entered<'a, T> {
+    fn swap(key: &'static TaskLocal<T>, slot: &'a mut Option<T>) -> Self {
+        key.value
+            .with(|value| std::mem::swap(&mut *value.borrow_mut(), slot));
+        Entered { key, slot }
+    }
+}
+
+impl<T: 'static> Drop for Entered<'_, T> {
+    fn drop(&mut self) {
+        self.key
+            .value
+            .with(|value| std::mem::swap(&mut *value.borrow_mut(), self.slot));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::executor::Runtime;
+    use crate::yield_now;
+
+    crate::task_local! {
+        static NAME: &'static str;
+    }
+
+    #[test]
+    fn value_is_unset_outside_scope() {
+        assert_eq!(NAME.try_with(|name| *name), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of its scope")]
+    fn get_outside_scope_panics() {
+        NAME.get();
+    }
+
+    #[test]
+    fn value_survives_yields_and_is_cleared_after() {
+        let runtime = Runtime::new();
+
+        let name = runtime.block_on(NAME.scope("outer", async {
+            yield_now().await;
+            NAME.get()
+        }));
+
+        assert_eq!(name, "outer");
+        assert_eq!(NAME.try_with(|name| *name), None);
+    }
+
+    #[test]
+    fn nested_scope_shadows_and_restores() {
+        let runtime = Runtime::new();
+
+        let names = runtime.block_on(NAME.scope("outer", async {
+            let inner = NAME
+                .scope("inner", async {
+                    yield_now().await;
+                    NAME.get()
+                })
+                .await;
+            (inner, NAME.get())
+        }));
+
+        assert_eq!(names, ("inner", "outer"));
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use super::reactor::{self, Reactor, ReactorGuard};
+
+/// Source of the current time used by the timer reactor.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// Clock backed by [`Instant::now`]. This is what the global reactor uses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+struct ManualClock {
+    now: Mutex<Instant>,
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock()
+    }
+}
+
+/// Manually advanced clock for deterministic timer tests.
+///
+/// Timers created while a clock is [entered](TestClock::enter) on the
+/// current thread only fire when the clock is [advanced](TestClock::advance)
+/// past their deadline; no real time has to pass.
+///
+/// Entering is per thread: tasks polled on a runtime's workers don't see
+/// the clock, and [`Runtime::block_on`] on a runtime with a
+/// [dedicated reactor](crate::RuntimeBuilder::dedicated_reactor) swaps in
+/// that reactor for the duration of the call. To drive every timer of a
+/// runtime, workers and `block_on` included, hand the clock to
+/// [`RuntimeBuilder::test_clock`](crate::RuntimeBuilder::test_clock)
+/// instead.
+///
+/// [`Runtime::block_on`]: crate::Runtime::block_on
+///
+/// # Example
+///
+/// ```
+/// use runtime::timer::TestClock;
+/// use runtime::{Runtime, sleep};
+/// use std::time::Duration;
+///
+/// let runtime = Runtime::new();
+/// let clock = TestClock::new();
+/// let _guard = clock.enter();
+///
+/// let sleeper = sleep(Duration::from_secs(3600));
+/// clock.advance(Duration::from_secs(3600));
+/// runtime.block_on(sleeper);
+/// ```
+#[derive(Clone)]
+pub struct TestClock {
+    clock: Arc<ManualClock>,
+    reactor: Arc<Reactor>,
+}
+
+impl TestClock {
+    pub fn new() -> Self {
+        let clock = Arc::new(ManualClock {
+            now: Mutex::new(Instant::now()),
+        });
+        let reactor = Reactor::with_clock(clock.clone());
+        TestClock { clock, reactor }
+    }
+
+    /// Moves the clock forward by `duration` and wakes every timer whose
+    /// deadline has now passed.
+    pub fn advance(&self, duration: Duration) {
+        *self.clock.now.lock() += duration;
+        self.reactor.fire_ready_timers();
+    }
+
+    /// Routes timers created on the current thread to this clock until the
+    /// returned guard is dropped. Other threads, including a runtime's
+    /// workers, are unaffected.
+    pub fn enter(&self) -> ClockGuard {
+        ClockGuard {
+            _reactor: reactor::enter(self.reactor.clone()),
+        }
+    }
+}
+
+impl TestClock {
+    pub(crate) fn reactor(&self) -> &Arc<Reactor> {
+        &self.reactor
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+}
+
+/// Guard returned by [`TestClock::enter`].
+pub struct ClockGuard {
+    _reactor: ReactorGuard,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::sleep;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Wake, Waker};
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn advance_moves_time_forward() {
+        let clock = TestClock::new();
+        let start = clock.now();
+
+        clock.advance(Duration::from_secs(5));
+
+        assert_eq!(clock.now() - start, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn advance_wakes_expired_sleep() {
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        let waker = Waker::from(counter.clone());
+        let mut context = Context::from_waker(&waker);
+        let clock = TestClock::new();
+        let guard = clock.enter();
+        let mut future = sleep(Duration::from_secs(60));
+        drop(guard);
+
+        assert!(Pin::new(&mut future).poll(&mut context).is_pending());
+
+        clock.advance(Duration::from_secs(59));
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 0);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+        assert!(Pin::new(&mut future).poll(&mut context).is_ready());
+    }
+
+    #[test]
+    fn dropping_guard_restores_system_clock() {
+        let clock = TestClock::new();
+        drop(clock.enter());
+
+        let future = sleep(Duration::from_secs(60));
+
+        assert!(!Arc::ptr_eq(future.reactor(), &clock.reactor));
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::{Future, poll_fn};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+use super::reactor::{Reactor, current_reactor};
+use super::sleep::SleepFuture;
+
+/// Identifies a value inserted into a [`DelayQueue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key(u64);
+
+/// Queue of values that become available once their delay has elapsed.
+///
+/// Values are yielded in deadline order by [`next`](DelayQueue::next). The
+/// queue arms a single reactor timer for its earliest deadline, so it needs
+/// no thread of its own.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::timer::DelayQueue;
+/// use std::time::Duration;
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let mut queue = DelayQueue::new();
+///     queue.insert("later", Duration::from_millis(20));
+///     queue.insert("sooner", Duration::from_millis(10));
+///
+///     assert_eq!(queue.next().await, Some("sooner"));
+///     assert_eq!(queue.next().await, Some("later"));
+///     assert_eq!(queue.next().await, None);
+/// });
+/// ```
+pub struct DelayQueue<T> {
+    reactor: Arc<Reactor>,
+    entries: BTreeMap<(Instant, Key), T>,
+    deadlines: HashMap<Key, Instant>,
+    next_key: u64,
+    /// Timer for the earliest deadline, replaced when that deadline changes.
+    sleep: Option<SleepFuture>,
+}
+
+impl<T> DelayQueue<T> {
+    pub fn new() -> Self {
+        DelayQueue {
+            reactor: current_reactor(),
+            entries: BTreeMap::new(),
+            deadlines: HashMap::new(),
+            next_key: 0,
+            sleep: None,
+        }
+    }
+
+    /// Schedules `value` to be yielded once `delay` has elapsed.
+    pub fn insert(&mut self, value: T, delay: Duration) -> Key {
+        let key = Key(self.next_key);
+        self.next_key += 1;
+
+        let deadline = self.reactor.now() + delay;
+        self.entries.insert((deadline, key), value);
+        self.deadlines.insert(key, deadline);
+        key
+    }
+
+    /// Removes the value for `key` before it is yielded. Returns `None` if it
+    /// was already yielded or removed.
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        let deadline = self.deadlines.remove(&key)?;
+        self.entries.remove(&(deadline, key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Waits for the next value whose deadline has elapsed. Resolves to
+    /// `None` once the queue is empty.
+    ///
+    /// Values inserted while this future is pending are taken into account
+    /// the next time it is polled.
+    pub async fn next(&mut self) -> Option<T> {
+        poll_fn(|cx| {
+            let Some(&(deadline, _)) = self.entries.keys().next() else {
+                self.sleep = None;
+                return Poll::Ready(None);
+            };
+
+            let sleep = match &mut self.sleep {
+                Some(sleep) if sleep.deadline() == deadline => sleep,
+                slot => slot.insert(SleepFuture::new(self.reactor.clone(), deadline)),
+            };
+            if Pin::new(sleep).poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+
+            self.sleep = None;
+            let next = self.entries.pop_first().map(|((_, key), value)| {
+                self.deadlines.remove(&key);
+                value
+            });
+            Poll::Ready(next)
+        })
+        .await
+    }
+}
+
+impl<T> Default for DelayQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::TestClock;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Wake, Waker};
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn poll_next<T>(queue: &mut DelayQueue<T>, waker: &Waker) -> Poll<Option<T>> {
+        pin!(queue.next()).poll(&mut Context::from_waker(waker))
+    }
+
+    fn create_queue<T>() -> (TestClock, DelayQueue<T>) {
+        let clock = TestClock::new();
+        let _guard = clock.enter();
+        (clock.clone(), DelayQueue::new())
+    }
+
+    #[test]
+    fn values_are_yielded_in_deadline_order() {
+        let (clock, mut queue) = create_queue();
+        let waker = Waker::from(Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        }));
+
+        queue.insert("third", Duration::from_millis(30));
+        queue.insert("first", Duration::from_millis(10));
+        queue.insert("second", Duration::from_millis(20));
+
+        assert!(poll_next(&mut queue, &waker).is_pending());
+
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(poll_next(&mut queue, &waker), Poll::Ready(Some("first")));
+        assert!(poll_next(&mut queue, &waker).is_pending());
+
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(poll_next(&mut queue, &waker), Poll::Ready(Some("second")));
+        assert_eq!(poll_next(&mut queue, &waker), Poll::Ready(Some("third")));
+        assert_eq!(poll_next(&mut queue, &waker), Poll::Ready(None));
+    }
+
+    #[test]
+    fn removed_value_is_never_yielded() {
+        let (clock, mut queue) = create_queue();
+        let waker = Waker::from(Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        }));
+
+        let removed = queue.insert(1, Duration::from_millis(10));
+        queue.insert(2, Duration::from_millis(20));
+
+        assert_eq!(queue.remove(removed), Some(1));
+        assert_eq!(queue.remove(removed), None);
+        assert_eq!(queue.len(), 1);
+
+        clock.advance(Duration::from_millis(10));
+        assert!(poll_next(&mut queue, &waker).is_pending());
+
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(poll_next(&mut queue, &waker), Poll::Ready(Some(2)));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn pending_next_is_woken_when_deadline_elapses() {
+        let (clock, mut queue) = create_queue();
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        let waker = Waker::from(counter.clone());
+
+        queue.insert((), Duration::from_secs(5));
+        assert!(poll_next(&mut queue, &waker).is_pending());
+
+        clock.advance(Duration::from_secs(5));
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::reactor::{Reactor, current_reactor};
+use super::sleep::SleepFuture;
+
+/// What an [`Interval`] does when ticks were missed because the task polling
+/// it fell behind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissedTickBehavior {
+    /// Fires the missed ticks back to back until caught up with the
+    /// original schedule.
+    #[default]
+    Burst,
+    /// Schedules the next tick one period after the late tick fired,
+    /// shifting the schedule.
+    Delay,
+    /// Drops missed ticks and resumes at the next multiple of the period on
+    /// the original schedule.
+    Skip,
+}
+
+impl MissedTickBehavior {
+    fn next_deadline(self, scheduled: Instant, now: Instant, period: Duration) -> Instant {
+        match self {
+            MissedTickBehavior::Burst => scheduled + period,
+            MissedTickBehavior::Delay => now + period,
+            MissedTickBehavior::Skip => {
+                let missed_ticks =
+                    now.saturating_duration_since(scheduled).as_nanos() / period.as_nanos() + 1;
+                scheduled + Duration::from_nanos((period.as_nanos() * missed_ticks) as u64)
+            }
+        }
+    }
+}
+
+/// Ticker returned by [`interval`].
+pub struct Interval {
+    reactor: Arc<Reactor>,
+    next_deadline: Instant,
+    period: Duration,
+    missed_tick_behavior: MissedTickBehavior,
+}
+
+impl Interval {
+    /// Waits for the next tick and returns the instant it was scheduled for.
+    ///
+    /// Deadlines are computed from the previous scheduled deadline rather
+    /// than from when the tick was observed, so the schedule doesn't drift.
+    pub async fn tick(&mut self) -> Instant {
+        let scheduled = self.next_deadline;
+        SleepFuture::new(self.reactor.clone(), scheduled).await;
+
+        self.next_deadline =
+            self.missed_tick_behavior
+                .next_deadline(scheduled, self.reactor.now(), self.period);
+        scheduled
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.missed_tick_behavior
+    }
+
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.missed_tick_behavior = behavior;
+    }
+}
+
+/// Creates an [`Interval`] that ticks every `period`, starting one period
+/// from now.
+///
+/// # Panics
+///
+/// Panics if `period` is zero.
+///
+/// # Example
+///
+/// ```no_run
+/// use runtime::interval;
+/// use std::time::Duration;
+///
+/// async fn heartbeat() {
+///     let mut ticker = interval(Duration::from_secs(5));
+///     loop {
+///         ticker.tick().await;
+///         println!("still alive");
+///     }
+/// }
+/// ```
+pub fn interval(period: Duration) -> Interval {
+    assert!(!period.is_zero(), "interval period must be non-zero");
+
+    let reactor = current_reactor();
+    Interval {
+        next_deadline: reactor.now() + period,
+        reactor,
+        period,
+        missed_tick_behavior: MissedTickBehavior::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn burst_keeps_original_schedule() {
+        let scheduled = Instant::now();
+        let now = scheduled + Duration::from_millis(35);
+
+        let next = MissedTickBehavior::Burst.next_deadline(scheduled, now, PERIOD);
+
+        assert_eq!(next, scheduled + PERIOD);
+    }
+
+    #[test]
+    fn delay_restarts_from_now() {
+        let scheduled = Instant::now();
+        let now = scheduled + Duration::from_millis(35);
+
+        let next = MissedTickBehavior::Delay.next_deadline(scheduled, now, PERIOD);
+
+        assert_eq!(next, now + PERIOD);
+    }
+
+    #[test]
+    fn skip_jumps_to_next_multiple() {
+        let scheduled = Instant::now();
+        let now = scheduled + Duration::from_millis(35);
+
+        let next = MissedTickBehavior::Skip.next_deadline(scheduled, now, PERIOD);
+
+        assert_eq!(next, scheduled + Duration::from_millis(40));
+    }
+
+    #[test]
+    fn skip_on_time_advances_one_period() {
+        let scheduled = Instant::now();
+
+        let next = MissedTickBehavior::Skip.next_deadline(scheduled, scheduled, PERIOD);
+
+        assert_eq!(next, scheduled + PERIOD);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn interval_rejects_zero_period() {
+        let _ = interval(Duration::ZERO);
+    }
+
+    #[test]
+    fn interval_defaults_to_burst() {
+        let ticker = interval(PERIOD);
+        assert_eq!(ticker.missed_tick_behavior(), MissedTickBehavior::Burst);
+        assert_eq!(ticker.period(), PERIOD);
+    }
+}
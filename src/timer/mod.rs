@@ -1,5 +1,15 @@
+mod clock;
+mod delay_queue;
+mod interval;
 mod reactor;
 mod registry;
 mod sleep;
+mod timeout;
 
-pub use sleep::{SleepFuture, sleep};
+pub use clock::{Clock, ClockGuard, SystemClock, TestClock};
+pub use delay_queue::{DelayQueue, Key};
+pub use interval::{Interval, MissedTickBehavior, interval};
+pub(crate) use reactor::{Reactor, ReactorRef, TimerEntry, current_reactor};
+pub use reactor::{ReactorStats, reactor_stats, set_max_wakes_per_tick, set_timer_granularity};
+pub use sleep::{SleepFuture, SleepTimed, sleep, sleep_timed, sleep_until};
+pub use timeout::{Elapsed, Timeout, timeout, timeout_at};
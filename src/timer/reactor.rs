@@ -1,37 +1,123 @@
-use std::sync::{Arc, OnceLock};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
 use std::task::Waker;
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use parking_lot::{Condvar, Mutex, MutexGuard};
 
-use super::registry::TimerRegistry;
+use super::clock::{Clock, SystemClock};
+use super::registry::{TimerId, TimerRegistry};
 
 const REACTOR_THREAD_NAME: &str = "timer-reactor";
 
 pub struct Reactor {
     registry: Mutex<TimerRegistry>,
     condvar: Condvar,
+    clock: Arc<dyn Clock>,
+    is_shutdown: AtomicBool,
+    /// Cap on wakers woken per loop iteration; `0` means no cap.
+    max_wakes_per_tick: AtomicUsize,
+    /// Expired timers held back by `max_wakes_per_tick`, in expiry order.
+    backlog: Mutex<VecDeque<Waker>>,
 }
 
 impl Reactor {
-    fn new() -> Arc<Self> {
+    /// Creates a reactor without a driver thread; timers only fire when
+    /// [`Reactor::fire_ready_timers`] is called.
+    pub(super) fn with_clock(clock: Arc<dyn Clock>) -> Arc<Self> {
         Arc::new(Reactor {
-            registry: Mutex::new(TimerRegistry::default()),
+            registry: Mutex::new(TimerRegistry::new(clock.now())),
             condvar: Condvar::new(),
+            clock,
+            is_shutdown: AtomicBool::new(false),
+            max_wakes_per_tick: AtomicUsize::new(0),
+            backlog: Mutex::new(VecDeque::new()),
         })
     }
 
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.is_shutdown.load(Ordering::SeqCst)
+    }
+
+    /// Stops the reactor and its driver thread, if any. Expired timers
+    /// still waiting in the backlog are woken; timers that haven't expired
+    /// are moved to the global reactor, as are timers registered or
+    /// cancelled afterwards, so a sleep never ends early because its
+    /// reactor went away.
+    pub(super) fn shutdown(&self) {
+        let mut registry = self.registry.lock();
+        self.is_shutdown.store(true, Ordering::SeqCst);
+        let pending = registry.drain();
+        drop(registry);
+        let expired: Vec<Waker> = self.backlog.lock().drain(..).collect();
+
+        self.condvar.notify_all();
+        for waker in expired {
+            waker.wake();
+        }
+        if !pending.is_empty() {
+            let successor = get_reactor();
+            for (deadline, id, waker) in pending {
+                successor.register_timer(deadline, id, waker);
+            }
+        }
+    }
+
+    /// Whether no timers are registered or waiting to be woken.
+    fn is_idle(&self) -> bool {
+        self.registry.lock().is_empty() && !self.has_backlog()
+    }
+
+    /// Wakes the timers whose deadline has passed, or only the first
+    /// `max_wakes_per_tick` of them when capped.
+    pub(super) fn fire_ready_timers(&self) {
+        let ready = self.registry.lock().pop_ready_wakers(self.now());
+        for waker in self.next_batch(ready) {
+            waker.wake();
+        }
+    }
+
+    /// Queues `ready` behind timers left over from earlier ticks and returns
+    /// the wakers to wake this tick.
+    fn next_batch(&self, ready: Vec<Waker>) -> Vec<Waker> {
+        let max_wakes = self.max_wakes_per_tick.load(Ordering::Relaxed);
+        let mut backlog = self.backlog.lock();
+        if max_wakes == 0 && backlog.is_empty() {
+            return ready;
+        }
+
+        backlog.extend(ready);
+        let batch_len = match max_wakes {
+            0 => backlog.len(),
+            max_wakes => max_wakes.min(backlog.len()),
+        };
+        backlog.drain(..batch_len).collect()
+    }
+
+    fn has_backlog(&self) -> bool {
+        !self.backlog.lock().is_empty()
+    }
+
     fn run(self: Arc<Self>) {
         let mut registry = self.registry.lock();
 
-        loop {
-            let now = Instant::now();
+        while !self.is_shutdown() {
+            let now = self.now();
 
             match registry.next_deadline() {
                 Some(deadline) if now >= deadline => {
                     registry = self.process_ready_timers(registry, now);
                 }
+                _ if self.has_backlog() => {
+                    registry = self.process_ready_timers(registry, now);
+                }
                 Some(deadline) => {
                     registry = self.park_until(registry, deadline);
                 }
@@ -47,12 +133,16 @@ impl Reactor {
         mut registry: MutexGuard<TimerRegistry>,
         now: Instant,
     ) -> MutexGuard<'_, TimerRegistry> {
-        let wakers = registry.pop_ready_wakers(now);
+        let ready = registry.pop_ready_wakers(now);
         drop(registry);
 
-        for waker in wakers {
+        for waker in self.next_batch(ready) {
             waker.wake();
         }
+        // Gives woken tasks a chance to run before the next batch.
+        if self.has_backlog() {
+            thread::yield_now();
+        }
 
         self.registry.lock()
     }
@@ -62,7 +152,7 @@ impl Reactor {
         mut registry: MutexGuard<'a, TimerRegistry>,
         deadline: Instant,
     ) -> MutexGuard<'a, TimerRegistry> {
-        let now = Instant::now();
+        let now = self.now();
 
         if deadline > now {
             self.condvar.wait_for(&mut registry, deadline - now);
@@ -79,28 +169,559 @@ impl Reactor {
         registry
     }
 
-    pub fn register_timer(&self, deadline: Instant, waker: Waker) {
+    pub fn register_timer(&self, deadline: Instant, id: TimerId, waker: Waker) {
         let mut registry = self.registry.lock();
-        registry.register(deadline, waker);
+        if self.is_shutdown() {
+            drop(registry);
+            get_reactor().register_timer(deadline, id, waker);
+            return;
+        }
+        registry.register(deadline, id, waker);
         self.condvar.notify_one();
     }
+
+    pub fn set_granularity(&self, granularity: Duration) {
+        self.registry.lock().set_granularity(granularity);
+    }
+
+    pub fn set_max_wakes_per_tick(&self, max_wakes: usize) {
+        self.max_wakes_per_tick.store(max_wakes, Ordering::Relaxed);
+    }
+
+    fn configure(&self, config: ReactorConfig) {
+        self.set_granularity(config.granularity);
+        self.set_max_wakes_per_tick(config.max_wakes_per_tick);
+    }
+
+    pub fn cancel_timer(&self, id: TimerId) {
+        let mut registry = self.registry.lock();
+        if self.is_shutdown() {
+            drop(registry);
+            get_reactor().cancel_timer(id);
+            return;
+        }
+        registry.remove(id);
+    }
+
+    pub fn stats(&self) -> ReactorStats {
+        let registry = self.registry.lock();
+        ReactorStats {
+            pending_timers: registry.len(),
+            distinct_deadlines: registry.distinct_deadlines(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn has_timer(&self, deadline: Instant, id: TimerId) -> bool {
+        self.registry.lock().contains(deadline, id)
+    }
 }
 
-static GLOBAL_REACTOR: OnceLock<Arc<Reactor>> = OnceLock::new();
+/// Snapshot of the timers held by a reactor, returned by [`reactor_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReactorStats {
+    /// Timers registered and not yet fired or cancelled.
+    pub pending_timers: usize,
+    /// Different deadlines among the pending timers. Timers rounded to the
+    /// same deadline by [`set_timer_granularity`] count once.
+    pub distinct_deadlines: usize,
+}
+
+/// Settings applied to every reactor the runtimes start, kept here so a
+/// restarted or newly started reactor gets them too.
+#[derive(Clone, Copy)]
+struct ReactorConfig {
+    granularity: Duration,
+    max_wakes_per_tick: usize,
+}
+
+/// The process-wide reactor, the number of runtimes keeping it alive, and the
+/// dedicated reactors started since, so settings reach all of them.
+struct GlobalReactor {
+    reactor: Option<Arc<Reactor>>,
+    runtimes: usize,
+    dedicated: Vec<Weak<Reactor>>,
+    config: ReactorConfig,
+}
+
+impl GlobalReactor {
+    const fn new() -> Self {
+        GlobalReactor {
+            reactor: None,
+            runtimes: 0,
+            dedicated: Vec::new(),
+            config: ReactorConfig {
+                granularity: Duration::ZERO,
+                max_wakes_per_tick: 0,
+            },
+        }
+    }
+
+    fn get_or_start(&mut self) -> Arc<Reactor> {
+        let config = self.config;
+        self.reactor
+            .get_or_insert_with(|| initialize_reactor(config))
+            .clone()
+    }
+
+    fn start_dedicated(&mut self) -> Arc<Reactor> {
+        let reactor = initialize_reactor(self.config);
+        self.dedicated
+            .retain(|existing| existing.strong_count() > 0);
+        self.dedicated.push(Arc::downgrade(&reactor));
+        reactor
+    }
+
+    /// Updates the stored settings and applies them to every running reactor.
+    fn configure(&mut self, update: impl FnOnce(&mut ReactorConfig)) {
+        update(&mut self.config);
+        let dedicated = self.dedicated.iter().filter_map(Weak::upgrade);
+        for reactor in self.reactor.iter().cloned().chain(dedicated) {
+            reactor.configure(self.config);
+        }
+    }
+}
+
+static GLOBAL_REACTOR: Mutex<GlobalReactor> = Mutex::new(GlobalReactor::new());
+static NEXT_TIMER_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static CURRENT_REACTOR: RefCell<Option<Arc<Reactor>>> = const { RefCell::new(None) };
+}
+
+/// Restores the previously entered reactor when dropped.
+pub(crate) struct ReactorGuard {
+    previous: Option<Arc<Reactor>>,
+}
+
+impl Drop for ReactorGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_REACTOR.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Overrides the reactor used by timers created on this thread.
+pub(super) fn enter(reactor: Arc<Reactor>) -> ReactorGuard {
+    let previous = CURRENT_REACTOR.with(|current| current.borrow_mut().replace(reactor));
+    ReactorGuard { previous }
+}
+
+/// Returns the reactor entered on this thread, falling back to the global one.
+pub(crate) fn current_reactor() -> Arc<Reactor> {
+    CURRENT_REACTOR
+        .with(|current| current.borrow().clone())
+        .unwrap_or_else(get_reactor)
+}
+
+pub(super) fn next_timer_id() -> TimerId {
+    NEXT_TIMER_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Rounds timer deadlines up to a multiple of `granularity`, so timers due
+/// within the same window share a single reactor wakeup. Timers are exact by
+/// default (`Duration::ZERO`).
+///
+/// Trades timer precision for fewer wakeups when many sleeps are in flight.
+/// Applies process-wide, to the global reactor and to runtimes'
+/// [dedicated reactors](crate::RuntimeBuilder::dedicated_reactor), including
+/// ones started later, for timers registered after the call.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// runtime::timer::set_timer_granularity(Duration::from_millis(1));
+/// ```
+pub fn set_timer_granularity(granularity: Duration) {
+    GLOBAL_REACTOR
+        .lock()
+        .configure(|config| config.granularity = granularity);
+}
+
+/// Caps how many expired timers each reactor wakes per loop iteration.
+/// Timers beyond the cap are woken on later iterations in expiry order, with
+/// the clock re-checked in between, so a burst of timers sharing one
+/// deadline doesn't flood the run queues all at once. `0`, the default,
+/// wakes every expired timer immediately. Like [`set_timer_granularity`],
+/// applies to the global reactor and every dedicated one, including ones
+/// started later.
+///
+/// # Example
+///
+/// ```
+/// runtime::timer::set_max_wakes_per_tick(1024);
+/// ```
+pub fn set_max_wakes_per_tick(max_wakes: usize) {
+    GLOBAL_REACTOR
+        .lock()
+        .configure(|config| config.max_wakes_per_tick = max_wakes);
+}
+
+/// Returns how many timers the reactor used by this thread holds. A count
+/// that keeps growing while the number of live sleeps doesn't points to a
+/// timer leak.
+///
+/// # Example
+///
+/// ```
+/// use runtime::timer::reactor_stats;
+/// use runtime::{Runtime, sleep};
+/// use std::time::Duration;
+///
+/// let runtime = Runtime::new();
+/// let before = reactor_stats().pending_timers;
+///
+/// runtime.block_on(sleep(Duration::from_millis(5)));
+///
+/// // Other threads may share the reactor, so compare rather than expect 0.
+/// assert!(reactor_stats().pending_timers <= before + 1);
+/// ```
+pub fn reactor_stats() -> ReactorStats {
+    current_reactor().stats()
+}
+
+/// Timer registered straight with the current reactor rather than through a
+/// future. Its waker is woken once the delay elapses; dropping the entry
+/// deregisters it.
+pub(crate) struct TimerEntry {
+    reactor: Arc<Reactor>,
+    id: TimerId,
+}
+
+impl TimerEntry {
+    pub(crate) fn new(delay: Duration, waker: Waker) -> Self {
+        let reactor = current_reactor();
+        let deadline = reactor.now() + delay;
+        let id = next_timer_id();
+        reactor.register_timer(deadline, id, waker);
+        TimerEntry { reactor, id }
+    }
+}
+
+impl Drop for TimerEntry {
+    fn drop(&mut self) {
+        self.reactor.cancel_timer(self.id);
+    }
+}
 
-pub(super) fn get_reactor() -> &'static Arc<Reactor> {
-    GLOBAL_REACTOR.get_or_init(initialize_reactor)
+/// Keeps a runtime's reactor alive for the lifetime of the runtime.
+///
+/// By default that is the global reactor, whose thread is started lazily by
+/// the first timer. When the last runtime holding a `ReactorRef` to it is
+/// dropped, the reactor is shut down and its thread exits, unless timers are
+/// still registered with it; timers created after that start a fresh one. A
+/// [dedicated](ReactorRef::dedicated) reactor belongs to one runtime and is
+/// shut down with it, handing its pending timers to the global reactor. A
+/// [borrowed](ReactorRef::borrowed) one is left running.
+pub(crate) struct ReactorRef {
+    /// The runtime's own reactor, or `None` for the global one.
+    reactor: Option<Arc<Reactor>>,
+    /// Whether `reactor` is shut down along with the runtime.
+    owns_reactor: bool,
 }
 
-fn initialize_reactor() -> Arc<Reactor> {
-    let reactor = Reactor::new();
+impl ReactorRef {
+    pub(crate) fn acquire() -> Self {
+        GLOBAL_REACTOR.lock().runtimes += 1;
+        ReactorRef {
+            reactor: None,
+            owns_reactor: false,
+        }
+    }
+
+    /// Starts a reactor and thread of its own rather than sharing the
+    /// global one. It takes the process-wide timer settings.
+    pub(crate) fn dedicated() -> Self {
+        ReactorRef {
+            reactor: Some(GLOBAL_REACTOR.lock().start_dedicated()),
+            owns_reactor: true,
+        }
+    }
+
+    /// Routes the runtime's timers to `reactor`, which outlives the runtime,
+    /// such as a [`TestClock`](super::TestClock)'s.
+    pub(crate) fn borrowed(reactor: Arc<Reactor>) -> Self {
+        ReactorRef {
+            reactor: Some(reactor),
+            owns_reactor: false,
+        }
+    }
+
+    /// Returns a handle for routing timers created on a thread to this
+    /// runtime's reactor.
+    pub(crate) fn entry(&self) -> ReactorEntry {
+        ReactorEntry {
+            reactor: self.reactor.clone(),
+        }
+    }
+}
+
+impl Drop for ReactorRef {
+    fn drop(&mut self) {
+        if let Some(reactor) = &self.reactor {
+            if self.owns_reactor {
+                reactor.shutdown();
+            }
+            return;
+        }
+
+        let mut global = GLOBAL_REACTOR.lock();
+        global.runtimes -= 1;
+        let reactor = match global.runtimes {
+            0 => global.reactor.take_if(|reactor| reactor.is_idle()),
+            _ => None,
+        };
+        drop(global);
+
+        if let Some(reactor) = reactor {
+            reactor.shutdown();
+        }
+    }
+}
+
+/// Handle for entering a runtime's reactor, obtained from
+/// [`ReactorRef::entry`]. Can be moved to the runtime's worker threads.
+pub(crate) struct ReactorEntry {
+    reactor: Option<Arc<Reactor>>,
+}
+
+impl ReactorEntry {
+    /// Routes timers created on the current thread to the runtime's own
+    /// reactor until the guard is dropped. Does nothing for the global
+    /// reactor, which timers fall back to anyway.
+    pub(crate) fn enter(self) -> Option<ReactorGuard> {
+        self.reactor.map(enter)
+    }
+}
+
+fn get_reactor() -> Arc<Reactor> {
+    GLOBAL_REACTOR.lock().get_or_start()
+}
+
+fn initialize_reactor(config: ReactorConfig) -> Arc<Reactor> {
+    let reactor = Reactor::with_clock(Arc::new(SystemClock));
+    reactor.configure(config);
     spawn_reactor_thread(reactor.clone());
     reactor
 }
 
-fn spawn_reactor_thread(reactor: Arc<Reactor>) {
+fn spawn_reactor_thread(reactor: Arc<Reactor>) -> thread::JoinHandle<()> {
     thread::Builder::new()
         .name(REACTOR_THREAD_NAME.to_string())
         .spawn(move || reactor.run())
-        .expect("failed to spawn reactor thread");
+        .expect("failed to spawn reactor thread")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::task::Wake;
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn create_counting_waker() -> (Waker, Arc<CountingWaker>) {
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        (Waker::from(counter.clone()), counter)
+    }
+
+    #[test]
+    fn stats_track_registered_and_dropped_sleeps() {
+        use crate::timer::{sleep, sleep_until};
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::task::Context;
+
+        let reactor = Reactor::with_clock(Arc::new(SystemClock));
+        let _guard = enter(reactor.clone());
+        let (waker, _) = create_counting_waker();
+        let mut context = Context::from_waker(&waker);
+        let shared_deadline = reactor.now() + Duration::from_secs(60);
+        let mut sleeps = vec![
+            sleep_until(shared_deadline),
+            sleep_until(shared_deadline),
+            sleep(Duration::from_secs(120)),
+        ];
+
+        for sleep in &mut sleeps {
+            assert!(Pin::new(sleep).poll(&mut context).is_pending());
+        }
+        assert_eq!(
+            reactor_stats(),
+            ReactorStats {
+                pending_timers: 3,
+                distinct_deadlines: 2,
+            }
+        );
+
+        drop(sleeps);
+        assert_eq!(reactor_stats(), ReactorStats::default());
+    }
+
+    #[test]
+    fn shutdown_hands_pending_timers_to_the_global_reactor() {
+        let reactor = Reactor::with_clock(Arc::new(SystemClock));
+        let thread = spawn_reactor_thread(reactor.clone());
+        let (waker, counter) = create_counting_waker();
+        let deadline = reactor.now() + Duration::from_secs(60);
+        let id = next_timer_id();
+
+        reactor.register_timer(deadline, id, waker);
+        reactor.shutdown();
+
+        let give_up = Instant::now() + Duration::from_secs(5);
+        while !thread.is_finished() && Instant::now() < give_up {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(thread.is_finished());
+        thread.join().unwrap();
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 0);
+        assert!(!reactor.has_timer(deadline, id));
+        assert!(get_reactor().has_timer(deadline, id));
+
+        reactor.cancel_timer(id);
+        assert!(!get_reactor().has_timer(deadline, id));
+    }
+
+    #[test]
+    fn capped_reactor_wakes_same_deadline_timers_in_batches() {
+        let reactor = Reactor::with_clock(Arc::new(SystemClock));
+        reactor.set_max_wakes_per_tick(1_000);
+        let (waker, counter) = create_counting_waker();
+        let deadline = reactor.now();
+        for id in 0..10_000 {
+            reactor.register_timer(deadline, id, waker.clone());
+        }
+
+        for batch in 1..=10 {
+            reactor.fire_ready_timers();
+            assert_eq!(counter.wake_count.load(Ordering::SeqCst), batch * 1_000);
+        }
+        assert!(!reactor.has_backlog());
+        assert_eq!(reactor.stats().pending_timers, 0);
+    }
+
+    #[test]
+    fn capped_reactor_thread_drains_its_backlog() {
+        let reactor = Reactor::with_clock(Arc::new(SystemClock));
+        reactor.set_max_wakes_per_tick(100);
+        let (waker, counter) = create_counting_waker();
+        let deadline = reactor.now() + Duration::from_millis(10);
+        for id in 0..10_000 {
+            reactor.register_timer(deadline, id, waker.clone());
+        }
+        let thread = spawn_reactor_thread(reactor.clone());
+
+        let give_up = Instant::now() + Duration::from_secs(5);
+        while counter.wake_count.load(Ordering::SeqCst) < 10_000 && Instant::now() < give_up {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 10_000);
+
+        reactor.shutdown();
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn shutdown_wakes_backlogged_timers() {
+        let reactor = Reactor::with_clock(Arc::new(SystemClock));
+        reactor.set_max_wakes_per_tick(1);
+        let (waker, counter) = create_counting_waker();
+        for id in 0..3 {
+            reactor.register_timer(reactor.now(), id, waker.clone());
+        }
+
+        reactor.fire_ready_timers();
+        reactor.shutdown();
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn register_after_shutdown_goes_to_the_global_reactor() {
+        let reactor = Reactor::with_clock(Arc::new(SystemClock));
+        let (waker, counter) = create_counting_waker();
+        let deadline = reactor.now() + Duration::from_secs(60);
+        let id = next_timer_id();
+
+        reactor.shutdown();
+        reactor.register_timer(deadline, id, waker);
+
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 0);
+        assert!(!reactor.has_timer(deadline, id));
+        assert!(get_reactor().has_timer(deadline, id));
+        reactor.cancel_timer(id);
+    }
+
+    #[test]
+    fn reactor_with_timers_is_not_idle() {
+        let reactor = Reactor::with_clock(Arc::new(SystemClock));
+        let (waker, _) = create_counting_waker();
+        assert!(reactor.is_idle());
+
+        reactor.register_timer(reactor.now() + Duration::from_secs(60), 0, waker);
+        assert!(!reactor.is_idle());
+
+        reactor.cancel_timer(0);
+        assert!(reactor.is_idle());
+    }
+
+    #[test]
+    fn started_reactors_take_the_configured_settings() {
+        let reactor = initialize_reactor(ReactorConfig {
+            granularity: Duration::from_millis(10),
+            max_wakes_per_tick: 3,
+        });
+        let (waker, _) = create_counting_waker();
+        let base = reactor.now() + Duration::from_secs(60);
+        for id in 0..5 {
+            reactor.register_timer(base + Duration::from_micros(id), id, waker.clone());
+        }
+
+        assert_eq!(reactor.stats().distinct_deadlines, 1);
+        assert_eq!(reactor.max_wakes_per_tick.load(Ordering::Relaxed), 3);
+        for id in 0..5 {
+            reactor.cancel_timer(id);
+        }
+        reactor.shutdown();
+    }
+
+    #[test]
+    fn settings_reach_running_and_later_dedicated_reactors() {
+        let mut global = GlobalReactor::new();
+        let running = global.start_dedicated();
+
+        global.configure(|config| config.max_wakes_per_tick = 7);
+        let started_later = global.start_dedicated();
+
+        assert_eq!(running.max_wakes_per_tick.load(Ordering::Relaxed), 7);
+        assert_eq!(started_later.max_wakes_per_tick.load(Ordering::Relaxed), 7);
+        running.shutdown();
+        started_later.shutdown();
+    }
+
+    #[test]
+    fn restarted_global_reactor_keeps_its_settings() {
+        let mut global = GlobalReactor::new();
+        global.configure(|config| config.max_wakes_per_tick = 5);
+        global.get_or_start().shutdown();
+        global.reactor = None;
+
+        let restarted = global.get_or_start();
+
+        assert_eq!(restarted.max_wakes_per_tick.load(Ordering::Relaxed), 5);
+        restarted.shutdown();
+    }
 }
@@ -1,29 +1,349 @@
-use std::collections::BTreeMap;
-use std::mem;
+use std::collections::{BTreeMap, HashMap};
 use std::task::Waker;
 use std::time::{Duration, Instant};
 
-#[derive(Default)]
+pub(super) type TimerId = u64;
+
+/// Resolution of the wheel. Deadlines keep their exact value; the tick only
+/// decides which slot a timer lives in.
+const TICK: Duration = Duration::from_millis(1);
+const SLOT_BITS: u32 = 6;
+const SLOTS_PER_LEVEL: usize = 1 << SLOT_BITS;
+/// Six levels of 64 slots cover 64^6 ticks, a little over two years. Timers
+/// further out wait in `overflow` until they come into range.
+const LEVELS: usize = 6;
+
+/// Timers in one slot, ordered by deadline so the earliest is cheap to find
+/// even when many timers share a tick.
+type Slot = BTreeMap<(Instant, TimerId), Waker>;
+
+struct Level {
+    slots: [Slot; SLOTS_PER_LEVEL],
+    /// Bit `i` is set when `slots[i]` is non-empty.
+    occupied: u64,
+}
+
+impl Level {
+    fn new() -> Self {
+        Level {
+            slots: std::array::from_fn(|_| Slot::new()),
+            occupied: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Location {
+    deadline: Instant,
+    /// `(level, slot)` in the wheel, or `None` for the overflow list.
+    slot: Option<(usize, usize)>,
+}
+
+#[derive(Clone, Copy)]
+struct Expiration {
+    level: usize,
+    slot: usize,
+    start_tick: u64,
+}
+
+/// Hierarchical timer wheel.
+///
+/// Level `n` slots span `64^n` ticks. A timer is placed on the lowest level
+/// whose current window contains its tick and cascades to lower levels as
+/// time advances, so insertion and removal are constant time apart from the
+/// ordered insert within a slot.
 pub(super) struct TimerRegistry {
-    timers: BTreeMap<Instant, Vec<Waker>>,
+    levels: Box<[Level]>,
+    overflow: Slot,
+    index: HashMap<TimerId, Location>,
+    origin: Instant,
+    /// Tick up to which the wheel has been advanced.
+    elapsed: u64,
+    granularity: Duration,
+}
+
+impl Default for TimerRegistry {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
 }
 
 impl TimerRegistry {
-    pub fn register(&mut self, deadline: Instant, waker: Waker) {
-        self.timers.entry(deadline).or_default().push(waker);
+    pub fn new(origin: Instant) -> Self {
+        TimerRegistry {
+            levels: (0..LEVELS).map(|_| Level::new()).collect(),
+            overflow: Slot::new(),
+            index: HashMap::new(),
+            origin,
+            elapsed: 0,
+            granularity: Duration::ZERO,
+        }
+    }
+
+    /// Rounds deadlines up to a multiple of `granularity` so timers that are
+    /// due at nearly the same moment share one deadline and fire together.
+    /// `Duration::ZERO` keeps deadlines exact.
+    ///
+    /// Only affects timers registered afterwards; callers should set it
+    /// before timers are in flight.
+    pub fn set_granularity(&mut self, granularity: Duration) {
+        self.granularity = granularity;
+    }
+
+    fn bucket(&self, deadline: Instant) -> Instant {
+        let granularity = self.granularity.as_nanos();
+        if granularity == 0 || deadline <= self.origin {
+            return deadline;
+        }
+
+        let ticks = (deadline - self.origin).as_nanos().div_ceil(granularity);
+        self.origin + Duration::from_nanos((ticks * granularity) as u64)
+    }
+
+    fn tick_of(&self, instant: Instant) -> u64 {
+        (instant.saturating_duration_since(self.origin).as_nanos() / TICK.as_nanos()) as u64
+    }
+
+    /// Registers `waker` for the timer `id`, replacing the waker stored by an
+    /// earlier registration of the same timer.
+    pub fn register(&mut self, deadline: Instant, id: TimerId, waker: Waker) {
+        let deadline = self.bucket(deadline);
+
+        if let Some(location) = self.index.get(&id).copied() {
+            if location.deadline == deadline {
+                *self
+                    .slot_mut(location.slot)
+                    .get_mut(&(deadline, id))
+                    .expect("indexed timer") = waker;
+                return;
+            }
+            self.remove_entry(id, location);
+        }
+
+        self.insert(deadline, id, waker);
+    }
+
+    /// Removes the timer `id`, if it hasn't fired. The timer is found
+    /// through the index rather than by recomputing its slot, so this works
+    /// even if the granularity changed since it was registered.
+    pub fn remove(&mut self, id: TimerId) {
+        if let Some(location) = self.index.get(&id).copied() {
+            self.remove_entry(id, location);
+        }
+    }
+
+    /// Removes every timer and returns them as `(deadline, id, waker)`.
+    pub fn drain(&mut self) -> Vec<(Instant, TimerId, Waker)> {
+        self.index.clear();
+        let mut timers: Vec<_> = std::mem::take(&mut self.overflow)
+            .into_iter()
+            .map(|((deadline, id), waker)| (deadline, id, waker))
+            .collect();
+        for level in self.levels.iter_mut() {
+            level.occupied = 0;
+            for slot in level.slots.iter_mut() {
+                timers.extend(
+                    std::mem::take(slot)
+                        .into_iter()
+                        .map(|((deadline, id), waker)| (deadline, id, waker)),
+                );
+            }
+        }
+        timers
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, deadline: Instant, id: TimerId) -> bool {
+        let deadline = self.bucket(deadline);
+        self.index
+            .get(&id)
+            .is_some_and(|location| location.deadline == deadline)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Number of registered timers.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Number of different deadlines among the registered timers, after
+    /// rounding to the granularity.
+    pub fn distinct_deadlines(&self) -> usize {
+        let deadlines: std::collections::HashSet<Instant> = self
+            .index
+            .values()
+            .map(|location| location.deadline)
+            .collect();
+        deadlines.len()
     }
 
     pub fn next_deadline(&self) -> Option<Instant> {
-        self.timers.keys().next().copied()
+        let wheel = (0..LEVELS).filter_map(|level| {
+            let slot = self.next_occupied_slot(level)?;
+            let ((deadline, _), _) = self.levels[level].slots[slot].first_key_value()?;
+            Some(*deadline)
+        });
+        let overflow = self
+            .overflow
+            .first_key_value()
+            .map(|((deadline, _), _)| *deadline);
+
+        wheel.chain(overflow).min()
     }
 
     pub fn pop_ready_wakers(&mut self, now: Instant) -> Vec<Waker> {
-        let pending = self.timers.split_off(&(now + Duration::from_nanos(1)));
-        let ready = mem::replace(&mut self.timers, pending);
-        ready.into_values().flatten().collect()
+        let now_tick = self.tick_of(now);
+        let mut ready = Vec::new();
+
+        loop {
+            match self.next_expiration() {
+                // Slots for past ticks, and higher-level slots that need to
+                // cascade, are drained entirely.
+                Some(expiration)
+                    if expiration.start_tick < now_tick
+                        || (expiration.start_tick == now_tick && expiration.level > 0) =>
+                {
+                    self.process_expiration(expiration, now, &mut ready);
+                }
+                _ => {
+                    self.elapsed = self.elapsed.max(now_tick);
+                    if !self.migrate_overflow() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // The current tick may hold timers due later within the same tick.
+        let current = (self.elapsed & (SLOTS_PER_LEVEL as u64 - 1)) as usize;
+        let slot = &mut self.levels[0].slots[current];
+        while let Some(entry) = slot.first_entry()
+            && entry.key().0 <= now
+        {
+            let ((_, id), waker) = entry.remove_entry();
+            self.index.remove(&id);
+            ready.push(waker);
+        }
+        if slot.is_empty() {
+            self.levels[0].occupied &= !(1 << current);
+        }
+
+        ready
+    }
+
+    fn insert(&mut self, deadline: Instant, id: TimerId, waker: Waker) {
+        let tick = self.tick_of(deadline).max(self.elapsed);
+        let level = level_for(self.elapsed, tick);
+
+        let slot = if level < LEVELS {
+            let slot = slot_for(tick, level);
+            self.levels[level].slots[slot].insert((deadline, id), waker);
+            self.levels[level].occupied |= 1 << slot;
+            Some((level, slot))
+        } else {
+            self.overflow.insert((deadline, id), waker);
+            None
+        };
+
+        self.index.insert(id, Location { deadline, slot });
+    }
+
+    fn remove_entry(&mut self, id: TimerId, location: Location) {
+        self.index.remove(&id);
+        let slot = self.slot_mut(location.slot);
+        slot.remove(&(location.deadline, id));
+
+        if slot.is_empty()
+            && let Some((level, slot)) = location.slot
+        {
+            self.levels[level].occupied &= !(1 << slot);
+        }
+    }
+
+    fn slot_mut(&mut self, slot: Option<(usize, usize)>) -> &mut Slot {
+        match slot {
+            Some((level, slot)) => &mut self.levels[level].slots[slot],
+            None => &mut self.overflow,
+        }
+    }
+
+    /// First occupied slot of `level` at or after the wheel's current
+    /// position. Slots behind it are always empty, since a slot is drained
+    /// before `elapsed` moves past it.
+    fn next_occupied_slot(&self, level: usize) -> Option<usize> {
+        let current = slot_for(self.elapsed, level);
+        let ahead = self.levels[level].occupied & (u64::MAX << current);
+        (ahead != 0).then(|| ahead.trailing_zeros() as usize)
+    }
+
+    /// The slot that expires first. On ties the higher level wins so its
+    /// timers cascade before the lower level is drained.
+    fn next_expiration(&self) -> Option<Expiration> {
+        (0..LEVELS)
+            .rev()
+            .filter_map(|level| {
+                let slot = self.next_occupied_slot(level)?;
+                let level_span_bits = SLOT_BITS * (level as u32 + 1);
+                let window_start = self.elapsed & !((1_u64 << level_span_bits) - 1);
+                let start_tick = window_start + ((slot as u64) << (SLOT_BITS * level as u32));
+                Some(Expiration {
+                    level,
+                    slot,
+                    start_tick,
+                })
+            })
+            .min_by_key(|expiration| expiration.start_tick)
+    }
+
+    fn process_expiration(&mut self, expiration: Expiration, now: Instant, ready: &mut Vec<Waker>) {
+        let level = &mut self.levels[expiration.level];
+        let entries = std::mem::take(&mut level.slots[expiration.slot]);
+        level.occupied &= !(1 << expiration.slot);
+        self.elapsed = self.elapsed.max(expiration.start_tick);
+
+        for ((deadline, id), waker) in entries {
+            self.index.remove(&id);
+            if deadline <= now {
+                ready.push(waker);
+            } else {
+                self.insert(deadline, id, waker);
+            }
+        }
+    }
+
+    /// Moves overflow timers that now fit in the wheel. Returns whether any
+    /// timer was moved.
+    fn migrate_overflow(&mut self) -> bool {
+        let mut moved = false;
+        while let Some(((deadline, _), _)) = self.overflow.first_key_value()
+            && level_for(self.elapsed, self.tick_of(*deadline)) < LEVELS
+        {
+            let ((deadline, id), waker) = self.overflow.pop_first().expect("first entry checked");
+            self.index.remove(&id);
+            self.insert(deadline, id, waker);
+            moved = true;
+        }
+        moved
     }
 }
 
+/// Level whose current window holds `tick`: determined by the highest group
+/// of slot bits in which `tick` differs from `elapsed`.
+fn level_for(elapsed: u64, tick: u64) -> usize {
+    let differing = elapsed ^ tick;
+    if differing == 0 {
+        return 0;
+    }
+    ((u64::BITS - 1 - differing.leading_zeros()) / SLOT_BITS) as usize
+}
+
+fn slot_for(tick: u64, level: usize) -> usize {
+    ((tick >> (SLOT_BITS * level as u32)) & (SLOTS_PER_LEVEL as u64 - 1)) as usize
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,7 +381,7 @@ mod tests {
         let (waker, _) = create_test_waker();
         let deadline = Instant::now() + Duration::from_secs(1);
 
-        registry.register(deadline, waker);
+        registry.register(deadline, 1, waker);
 
         assert_eq!(registry.next_deadline(), Some(deadline));
     }
@@ -75,8 +395,8 @@ mod tests {
         let early = Instant::now() + Duration::from_millis(100);
         let late = Instant::now() + Duration::from_secs(1);
 
-        registry.register(late, waker1);
-        registry.register(early, waker2);
+        registry.register(late, 1, waker1);
+        registry.register(early, 2, waker2);
 
         assert_eq!(registry.next_deadline(), Some(early));
     }
@@ -87,7 +407,7 @@ mod tests {
         let (waker, count) = create_test_waker();
 
         let past = Instant::now() - Duration::from_millis(100);
-        registry.register(past, waker);
+        registry.register(past, 1, waker);
 
         let wakers = registry.pop_ready_wakers(Instant::now());
 
@@ -105,11 +425,230 @@ mod tests {
         let past = Instant::now() - Duration::from_millis(100);
         let future = Instant::now() + Duration::from_secs(10);
 
-        registry.register(past, waker1);
-        registry.register(future, waker2);
+        registry.register(past, 1, waker1);
+        registry.register(future, 2, waker2);
 
         let _ = registry.pop_ready_wakers(Instant::now());
 
         assert_eq!(registry.next_deadline(), Some(future));
     }
+
+    #[test]
+    fn registry_reregistering_replaces_waker() {
+        let mut registry = TimerRegistry::default();
+        let (stale, stale_count) = create_test_waker();
+        let (fresh, fresh_count) = create_test_waker();
+        let past = Instant::now() - Duration::from_millis(100);
+
+        registry.register(past, 1, stale);
+        registry.register(past, 1, fresh);
+
+        let wakers = registry.pop_ready_wakers(Instant::now());
+        assert_eq!(wakers.len(), 1);
+        wakers.into_iter().for_each(Waker::wake);
+
+        assert_eq!(stale_count.load(Ordering::SeqCst), 0);
+        assert_eq!(fresh_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn registry_remove_keeps_other_timers_at_same_deadline() {
+        let mut registry = TimerRegistry::default();
+        let (waker1, _) = create_test_waker();
+        let (waker2, _) = create_test_waker();
+        let deadline = Instant::now() + Duration::from_secs(1);
+
+        registry.register(deadline, 1, waker1);
+        registry.register(deadline, 2, waker2);
+        registry.remove(1);
+
+        assert!(!registry.contains(deadline, 1));
+        assert!(registry.contains(deadline, 2));
+    }
+
+    #[test]
+    fn registry_is_empty_after_removing_million_timers() {
+        let mut registry = TimerRegistry::default();
+        let (waker, _) = create_test_waker();
+        let base = Instant::now() + Duration::from_secs(60);
+
+        let timers: Vec<(Instant, TimerId)> = (0..1_000_000_u64)
+            .map(|id| (base + Duration::from_nanos(id), id))
+            .collect();
+
+        for &(deadline, id) in &timers {
+            registry.register(deadline, id, waker.clone());
+        }
+        for &(_, id) in &timers {
+            registry.remove(id);
+        }
+
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn granularity_coalesces_jittered_deadlines() {
+        let mut registry = TimerRegistry::default();
+        registry.set_granularity(Duration::from_millis(1));
+        let (waker, _) = create_test_waker();
+        let base = Instant::now() + Duration::from_secs(1);
+
+        for id in 0..1000_u64 {
+            registry.register(base + Duration::from_nanos(id * 500), id, waker.clone());
+        }
+
+        assert!(registry.distinct_deadlines() <= 2);
+        assert!(registry.next_deadline().unwrap() >= base);
+    }
+
+    #[test]
+    fn granularity_rounds_deadlines_up() {
+        let mut registry = TimerRegistry::default();
+        registry.set_granularity(Duration::from_millis(10));
+        let (waker, _) = create_test_waker();
+        let deadline = Instant::now() + Duration::from_millis(3);
+
+        registry.register(deadline, 1, waker);
+
+        let bucket = registry.next_deadline().unwrap();
+        assert!(bucket >= deadline);
+        assert!(bucket - deadline < Duration::from_millis(10));
+        assert!(registry.contains(deadline, 1));
+
+        registry.remove(1);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn remove_finds_timers_registered_under_an_older_granularity() {
+        let mut registry = TimerRegistry::default();
+        registry.set_granularity(Duration::from_millis(10));
+        let (waker, _) = create_test_waker();
+        registry.register(Instant::now() + Duration::from_millis(3), 1, waker);
+
+        registry.set_granularity(Duration::from_millis(7));
+        registry.remove(1);
+
+        assert!(registry.is_empty());
+        assert_eq!(registry.next_deadline(), None);
+    }
+
+    #[test]
+    fn wheel_handles_hundred_thousand_timers_quickly() {
+        let origin = Instant::now();
+        let mut registry = TimerRegistry::new(origin);
+        let (waker, count) = create_test_waker();
+        let start = Instant::now();
+
+        for id in 0..100_000_u64 {
+            let deadline = origin + Duration::from_micros(id * 37 % 5_000_000);
+            registry.register(deadline, id, waker.clone());
+        }
+        let wakers = registry.pop_ready_wakers(origin + Duration::from_secs(10));
+        wakers.into_iter().for_each(Waker::wake);
+
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(count.load(Ordering::SeqCst), 100_000);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn wheel_fires_deadlines_in_order() {
+        let origin = Instant::now();
+        let mut registry = TimerRegistry::new(origin);
+        let offsets_ms = [1, 3, 63, 64, 65, 250, 4_095, 4_096, 70_000, 300_000];
+        let counters: Vec<_> = offsets_ms
+            .iter()
+            .enumerate()
+            .map(|(id, &offset)| {
+                let (waker, count) = create_test_waker();
+                registry.register(origin + Duration::from_millis(offset), id as u64, waker);
+                count
+            })
+            .collect();
+
+        for (index, &offset) in offsets_ms.iter().enumerate() {
+            let deadline = origin + Duration::from_millis(offset);
+            assert_eq!(registry.next_deadline(), Some(deadline));
+
+            let just_before = registry.pop_ready_wakers(deadline - Duration::from_micros(1));
+            assert!(just_before.is_empty());
+
+            let fired = registry.pop_ready_wakers(deadline);
+            assert_eq!(fired.len(), 1);
+            fired.into_iter().for_each(Waker::wake);
+            assert_eq!(counters[index].load(Ordering::SeqCst), 1);
+        }
+
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn wheel_keeps_sub_tick_deadlines_until_due() {
+        let origin = Instant::now();
+        let mut registry = TimerRegistry::new(origin);
+        let (waker, _) = create_test_waker();
+        let deadline = origin + Duration::from_micros(1_700);
+
+        registry.register(deadline, 1, waker);
+
+        assert!(
+            registry
+                .pop_ready_wakers(origin + Duration::from_micros(1_200))
+                .is_empty()
+        );
+        assert_eq!(registry.next_deadline(), Some(deadline));
+        assert_eq!(registry.pop_ready_wakers(deadline).len(), 1);
+    }
+
+    #[test]
+    fn wheel_handles_far_future_deadlines() {
+        let origin = Instant::now();
+        let mut registry = TimerRegistry::new(origin);
+        let (waker, _) = create_test_waker();
+        let far = origin + Duration::from_secs(5 * 365 * 24 * 60 * 60);
+
+        registry.register(far, 1, waker);
+
+        assert_eq!(registry.next_deadline(), Some(far));
+        assert!(
+            registry
+                .pop_ready_wakers(far - Duration::from_secs(1))
+                .is_empty()
+        );
+        assert_eq!(registry.pop_ready_wakers(far).len(), 1);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn reregistering_at_new_deadline_moves_timer() {
+        let mut registry = TimerRegistry::default();
+        let (waker, _) = create_test_waker();
+        let first = Instant::now() + Duration::from_secs(1);
+        let second = first + Duration::from_secs(1);
+
+        registry.register(first, 1, waker.clone());
+        registry.register(second, 1, waker);
+
+        assert!(!registry.contains(first, 1));
+        assert!(registry.contains(second, 1));
+        assert_eq!(registry.next_deadline(), Some(second));
+    }
+
+    #[test]
+    fn drain_returns_every_timer_and_empties_registry() {
+        let origin = Instant::now();
+        let mut registry = TimerRegistry::new(origin);
+        let (waker, _) = create_test_waker();
+
+        registry.register(origin + Duration::from_millis(5), 0, waker.clone());
+        registry.register(origin + Duration::from_secs(3600), 1, waker.clone());
+        registry.register(origin + Duration::from_secs(10 * 365 * 86_400), 2, waker);
+
+        let mut ids: Vec<TimerId> = registry.drain().into_iter().map(|(_, id, _)| id).collect();
+        ids.sort();
+        assert_eq!(ids, [0, 1, 2]);
+        assert!(registry.is_empty());
+        assert_eq!(registry.next_deadline(), None);
+    }
 }
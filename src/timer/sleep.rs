@@ -1,24 +1,93 @@
 use std::future::Future;
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::task::{Context, Poll, Waker};
 use std::time::{Duration, Instant};
 
-use super::reactor::get_reactor;
+use std::sync::Arc;
+
+use super::reactor::{Reactor, current_reactor, next_timer_id};
+use super::registry::TimerId;
 
 pub struct SleepFuture {
+    reactor: Arc<Reactor>,
     deadline: Instant,
-    is_registered: bool,
+    timer_id: TimerId,
+    registered_waker: Option<Waker>,
 }
 
 impl SleepFuture {
-    fn is_ready(&self) -> bool {
-        Instant::now() >= self.deadline
+    pub(crate) fn new(reactor: Arc<Reactor>, deadline: Instant) -> Self {
+        SleepFuture {
+            reactor,
+            deadline,
+            timer_id: next_timer_id(),
+            registered_waker: None,
+        }
+    }
+
+    pub(super) fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Moves the deadline to `deadline`, reusing this sleep instead of
+    /// creating a new one. The old timer is cancelled, and the new deadline
+    /// is registered the next time the sleep is polled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime::{Runtime, sleep};
+    /// use std::pin::pin;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let runtime = Runtime::new();
+    /// let mut idle = pin!(sleep(Duration::from_secs(60)));
+    ///
+    /// // Activity was seen: push the idle timeout out, here to almost now.
+    /// idle.as_mut().reset(Instant::now() + Duration::from_millis(5));
+    /// runtime.block_on(idle);
+    /// ```
+    pub fn reset(self: Pin<&mut Self>, deadline: Instant) {
+        let this = self.get_mut();
+        if this.registered_waker.take().is_some() {
+            this.reactor.cancel_timer(this.timer_id);
+        }
+        this.deadline = deadline;
+    }
+
+    #[cfg(test)]
+    pub(super) fn reactor(&self) -> &Arc<Reactor> {
+        &self.reactor
+    }
+
+    /// Returns the current time if the deadline has passed. A reactor that
+    /// shuts down mid-sleep hands the timer on rather than firing it early.
+    fn is_ready(&self) -> Option<Instant> {
+        let now = self.reactor.now();
+        (now >= self.deadline).then_some(now)
     }
 
+    fn poll_ready_at(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        if let Some(now) = self.is_ready() {
+            return Poll::Ready(now);
+        }
+
+        self.ensure_registered(cx);
+        Poll::Pending
+    }
+
+    /// Registers the current waker with the reactor, refreshing the stored
+    /// one if the task was re-polled with a different waker.
     fn ensure_registered(&mut self, cx: &mut Context<'_>) {
-        if !self.is_registered {
-            get_reactor().register_timer(self.deadline, cx.waker().clone());
-            self.is_registered = true;
+        let is_current = self
+            .registered_waker
+            .as_ref()
+            .is_some_and(|waker| waker.will_wake(cx.waker()));
+
+        if !is_current {
+            self.reactor
+                .register_timer(self.deadline, self.timer_id, cx.waker().clone());
+            self.registered_waker = Some(cx.waker().clone());
         }
     }
 }
@@ -27,12 +96,15 @@ impl Future for SleepFuture {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if self.is_ready() {
-            return Poll::Ready(());
-        }
+        self.poll_ready_at(cx).map(|_| ())
+    }
+}
 
-        self.ensure_registered(cx);
-        Poll::Pending
+impl Drop for SleepFuture {
+    fn drop(&mut self) {
+        if self.registered_waker.is_some() {
+            self.reactor.cancel_timer(self.timer_id);
+        }
     }
 }
 
@@ -50,8 +122,218 @@ impl Future for SleepFuture {
 /// }
 /// ```
 pub fn sleep(duration: Duration) -> SleepFuture {
-    SleepFuture {
-        deadline: Instant::now() + duration,
-        is_registered: false,
+    let reactor = current_reactor();
+    let deadline = reactor.now() + duration;
+    SleepFuture::new(reactor, deadline)
+}
+
+/// Future returned by [`sleep_timed`].
+pub struct SleepTimed {
+    sleep: SleepFuture,
+}
+
+impl Future for SleepTimed {
+    type Output = Instant;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Instant> {
+        self.sleep.poll_ready_at(cx)
+    }
+}
+
+/// Like [`sleep`], but resolves to the time the sleep was found to be over.
+/// Comparing it with the deadline shows how much the timer overshot; it is
+/// never earlier than the deadline.
+///
+/// # Example
+///
+/// ```
+/// use runtime::Runtime;
+/// use runtime::timer::sleep_timed;
+/// use std::time::{Duration, Instant};
+///
+/// let runtime = Runtime::new();
+/// let deadline = Instant::now() + Duration::from_millis(10);
+///
+/// let fired_at = runtime.block_on(sleep_timed(Duration::from_millis(10)));
+/// assert!(fired_at >= deadline);
+/// ```
+pub fn sleep_timed(duration: Duration) -> SleepTimed {
+    SleepTimed {
+        sleep: sleep(duration),
+    }
+}
+
+/// Suspends the current task until `deadline` is reached.
+///
+/// # Example
+///
+/// ```no_run
+/// use runtime::sleep_until;
+/// use std::time::{Duration, Instant};
+///
+/// async fn tick_every_second() {
+///     let mut next = Instant::now();
+///     loop {
+///         next += Duration::from_secs(1);
+///         sleep_until(next).await;
+///         println!("tick");
+///     }
+/// }
+/// ```
+pub fn sleep_until(deadline: Instant) -> SleepFuture {
+    SleepFuture::new(current_reactor(), deadline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+    use std::thread;
+
+    use crate::timer::ReactorRef;
+
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn create_counting_waker() -> (Waker, Arc<CountingWaker>) {
+        let counter = Arc::new(CountingWaker {
+            wake_count: AtomicUsize::new(0),
+        });
+        (Waker::from(counter.clone()), counter)
+    }
+
+    #[test]
+    fn sleep_is_ready_after_deadline() {
+        let (waker, _) = create_counting_waker();
+        let mut context = Context::from_waker(&waker);
+        let mut future = sleep(Duration::ZERO);
+
+        assert!(Pin::new(&mut future).poll(&mut context).is_ready());
+    }
+
+    #[test]
+    fn sleep_until_past_deadline_is_ready() {
+        let (waker, _) = create_counting_waker();
+        let mut future = sleep_until(Instant::now() - Duration::from_millis(1));
+
+        assert!(
+            Pin::new(&mut future)
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+        );
+    }
+
+    #[test]
+    fn sleep_timed_resolves_at_or_after_deadline() {
+        let _reactor = ReactorRef::acquire();
+        let (waker, _) = create_counting_waker();
+        let mut future = sleep_timed(Duration::from_millis(20));
+        let deadline = future.sleep.deadline;
+
+        assert!(
+            Pin::new(&mut future)
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending()
+        );
+        thread::sleep(Duration::from_millis(30));
+
+        match Pin::new(&mut future).poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(fired_at) => assert!(fired_at >= deadline),
+            Poll::Pending => panic!("sleep not ready after its deadline"),
+        }
+    }
+
+    #[test]
+    fn reset_moves_deadline_of_pending_sleep() {
+        let _reactor = ReactorRef::acquire();
+        let (waker, counter) = create_counting_waker();
+        let mut context = Context::from_waker(&waker);
+        let mut future = sleep(Duration::from_millis(20));
+        let (old_deadline, timer_id) = (future.deadline, future.timer_id);
+
+        assert!(Pin::new(&mut future).poll(&mut context).is_pending());
+        let new_deadline = Instant::now() + Duration::from_millis(120);
+        Pin::new(&mut future).reset(new_deadline);
+        assert!(!future.reactor().has_timer(old_deadline, timer_id));
+        assert!(Pin::new(&mut future).poll(&mut context).is_pending());
+
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 0);
+        assert!(Pin::new(&mut future).poll(&mut context).is_pending());
+
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+        assert!(Pin::new(&mut future).poll(&mut context).is_ready());
+        assert!(Instant::now() >= new_deadline);
+    }
+
+    #[test]
+    fn sleep_wakes_most_recent_waker() {
+        let _reactor = ReactorRef::acquire();
+        let (first, first_counter) = create_counting_waker();
+        let (second, second_counter) = create_counting_waker();
+        let mut future = sleep(Duration::from_millis(20));
+
+        let _ = Pin::new(&mut future).poll(&mut Context::from_waker(&first));
+        let _ = Pin::new(&mut future).poll(&mut Context::from_waker(&second));
+        thread::sleep(Duration::from_millis(150));
+
+        assert_eq!(first_counter.wake_count.load(Ordering::SeqCst), 0);
+        assert_eq!(second_counter.wake_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn sleep_outlives_the_reactor_it_started_on() {
+        let _global = ReactorRef::acquire();
+        let dedicated = ReactorRef::dedicated();
+        let guard = dedicated.entry().enter();
+        let (waker, counter) = create_counting_waker();
+        let mut future = sleep(Duration::from_millis(40));
+        drop(guard);
+
+        assert!(
+            Pin::new(&mut future)
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending()
+        );
+        drop(dedicated);
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 0);
+        assert!(
+            Pin::new(&mut future)
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending()
+        );
+
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(counter.wake_count.load(Ordering::SeqCst), 1);
+        assert!(
+            Pin::new(&mut future)
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+        );
+    }
+
+    #[test]
+    fn dropping_sleep_deregisters_timer() {
+        let _reactor = ReactorRef::acquire();
+        let (waker, _) = create_counting_waker();
+        let mut future = sleep(Duration::from_secs(60));
+        let (deadline, timer_id) = (future.deadline, future.timer_id);
+
+        let _ = Pin::new(&mut future).poll(&mut Context::from_waker(&waker));
+        let reactor = future.reactor().clone();
+        assert!(reactor.has_timer(deadline, timer_id));
+
+        drop(future);
+        assert!(!reactor.has_timer(deadline, timer_id));
     }
 }
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use super::sleep::{SleepFuture, sleep, sleep_until};
+
+/// Error returned by [`Timeout`] when the deadline passes before the inner
+/// future completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed;
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+/// Future returned by [`timeout`] and [`timeout_at`].
+pub struct Timeout<F> {
+    future: Option<Pin<Box<F>>>,
+    sleep: SleepFuture,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Some(future) = self.future.as_mut() else {
+            return Poll::Ready(Err(Elapsed));
+        };
+
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            self.future = None;
+            return Poll::Ready(Ok(output));
+        }
+
+        if Pin::new(&mut self.sleep).poll(cx).is_ready() {
+            // Drop the inner future right away so it releases its resources.
+            self.future = None;
+            return Poll::Ready(Err(Elapsed));
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Requires `future` to complete within `duration`.
+///
+/// Resolves to `Err(Elapsed)` if the duration passes first, in which case the
+/// inner future is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// use runtime::{sleep, timeout};
+/// use std::time::Duration;
+///
+/// async fn example() {
+///     let result = timeout(Duration::from_millis(10), sleep(Duration::from_secs(1))).await;
+///     assert!(result.is_err());
+/// }
+/// ```
+pub fn timeout<F: Future>(duration: Duration, future: F) -> Timeout<F> {
+    Timeout {
+        future: Some(Box::pin(future)),
+        sleep: sleep(duration),
+    }
+}
+
+/// Like [`timeout`], but with an absolute `deadline`. Handy when one
+/// deadline bounds several operations run one after another.
+///
+/// # Example
+///
+/// ```
+/// use runtime::timer::timeout_at;
+/// use runtime::{Runtime, sleep};
+/// use std::time::{Duration, Instant};
+///
+/// let runtime = Runtime::new();
+/// let deadline = Instant::now() + Duration::from_millis(50);
+///
+/// runtime.block_on(async {
+///     let connect = timeout_at(deadline, sleep(Duration::from_millis(5))).await;
+///     let handshake = timeout_at(deadline, sleep(Duration::from_secs(1))).await;
+///     assert!(connect.is_ok());
+///     assert!(handshake.is_err());
+/// });
+/// ```
+pub fn timeout_at<F: Future>(deadline: Instant, future: F) -> Timeout<F> {
+    Timeout {
+        future: Some(Box::pin(future)),
+        sleep: sleep_until(deadline),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::{Clock, TestClock};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Waker;
+
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn ready_future_completes_before_timeout() {
+        let waker = Waker::noop();
+        let mut future = timeout(Duration::ZERO, async { 42 });
+
+        let result = Pin::new(&mut future).poll(&mut Context::from_waker(waker));
+
+        assert_eq!(result, Poll::Ready(Ok(42)));
+    }
+
+    #[test]
+    fn elapsed_timeout_drops_inner_future() {
+        let waker = Waker::noop();
+        let dropped = Arc::new(AtomicBool::new(false));
+        let guard = DropFlag(dropped.clone());
+        let mut future = timeout(Duration::ZERO, async move {
+            let _guard = guard;
+            std::future::pending::<()>().await;
+        });
+
+        let result = Pin::new(&mut future).poll(&mut Context::from_waker(waker));
+
+        assert_eq!(result, Poll::Ready(Err(Elapsed)));
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn chained_timeout_at_share_one_deadline() {
+        let waker = Waker::noop();
+        let mut context = Context::from_waker(waker);
+        let clock = TestClock::new();
+        let _guard = clock.enter();
+        let deadline = clock.now() + Duration::from_secs(10);
+
+        let mut first = timeout_at(deadline, sleep(Duration::from_secs(6)));
+        assert!(Pin::new(&mut first).poll(&mut context).is_pending());
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(Pin::new(&mut first).poll(&mut context), Poll::Ready(Ok(())));
+
+        // Another 6s would end past the deadline, which only has 4s left.
+        let mut second = timeout_at(deadline, sleep(Duration::from_secs(6)));
+        assert!(Pin::new(&mut second).poll(&mut context).is_pending());
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(
+            Pin::new(&mut second).poll(&mut context),
+            Poll::Ready(Err(Elapsed))
+        );
+    }
+
+    #[test]
+    fn elapsed_displays_message() {
+        assert_eq!(Elapsed.to_string(), "deadline has elapsed");
+    }
+}
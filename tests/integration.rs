@@ -1,9 +1,14 @@
+use std::cell::Cell;
+use std::rc::Rc;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use runtime::{CancellationToken, Runtime, sleep};
+use runtime::{
+    CancellationToken, IdleStrategy, JoinError, JoinSet, LocalRuntime, Priority, Runtime,
+    RuntimeBuilder, SpawnError, interval, sleep, sleep_until, timeout, yield_now,
+};
 
 const TEST_WORKER_COUNT: usize = 2;
 const TASK_EXECUTION_WAIT: Duration = Duration::from_millis(100);
@@ -108,32 +113,25 @@ fn cancellation_token_cancels_task() {
     let runtime = Runtime::new();
     let spawner = runtime.spawner();
 
-    let was_cancelled = Arc::new(AtomicUsize::new(0));
-    let was_cancelled_clone = was_cancelled.clone();
-
     let token = CancellationToken::new();
     let token_clone = token.clone();
 
-    spawner
+    let worker = spawner
         .spawn(async move {
             while !token_clone.is_cancelled() {
-                sleep(Duration::from_millis(10)).await;
+                yield_now().await;
             }
-            was_cancelled_clone.store(1, Ordering::SeqCst);
-        })
-        .unwrap();
-
-    spawner
-        .spawn(async move {
-            sleep(Duration::from_millis(50)).await;
-            token.cancel();
         })
         .unwrap();
+    let canceller = spawner.spawn(token.cancel_after_polls(5)).unwrap();
 
-    let _handle = runtime.run(TEST_WORKER_COUNT);
-    thread::sleep(Duration::from_millis(200));
+    let workers = runtime.run(TEST_WORKER_COUNT);
 
-    assert_eq!(was_cancelled.load(Ordering::SeqCst), 1);
+    canceller.join().unwrap();
+    assert!(token.is_cancelled());
+    worker.join().unwrap();
+    runtime.shutdown();
+    workers.wait();
 }
 
 #[test]
@@ -161,3 +159,2289 @@ fn nested_spawn_executes_both_tasks() {
 
     assert_eq!(counter.load(Ordering::SeqCst), 2);
 }
+
+#[test]
+fn block_on_returns_future_output() {
+    let runtime = Runtime::new();
+
+    let result = runtime.block_on(async { 42 });
+
+    assert_eq!(result, 42);
+}
+
+#[test]
+fn block_on_drives_spawned_tasks_without_workers() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handle = spawner.spawn(async { 42_usize }).unwrap();
+
+    let result = runtime.block_on(handle);
+
+    assert_eq!(result.unwrap(), 42);
+}
+
+#[test]
+fn block_on_fires_timers() {
+    let runtime = Runtime::new();
+    let start = Instant::now();
+
+    runtime.block_on(sleep(TIMER_DURATION));
+
+    assert!(start.elapsed() >= TIMER_DURATION);
+}
+
+#[test]
+fn block_on_returns_while_other_tasks_pending() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    spawner
+        .spawn(async {
+            sleep(Duration::from_secs(60)).await;
+        })
+        .unwrap();
+
+    let result = runtime.block_on(async { 7 });
+
+    assert_eq!(result, 7);
+}
+
+#[test]
+fn sleep_chains_complete_across_workers() {
+    let chain_count = 20;
+    let chain_length = 5;
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..chain_count {
+        let counter_clone = counter.clone();
+        spawner
+            .spawn(async move {
+                for _ in 0..chain_length {
+                    sleep(Duration::from_millis(5)).await;
+                    counter_clone.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+            .unwrap();
+    }
+
+    let _handle = runtime.run(TEST_WORKER_COUNT);
+    thread::sleep(TIMER_WAIT);
+
+    assert_eq!(counter.load(Ordering::SeqCst), chain_count * chain_length);
+}
+
+#[test]
+fn builder_runtime_executes_tasks_on_named_threads() {
+    let runtime = RuntimeBuilder::new()
+        .worker_threads(TEST_WORKER_COUNT)
+        .thread_name_prefix("integration-worker")
+        .thread_stack_size(8 * 1024 * 1024)
+        .build();
+    let spawner = runtime.spawner();
+
+    let thread_name = Arc::new(parking_lot::Mutex::new(None));
+    let thread_name_clone = thread_name.clone();
+
+    spawner
+        .spawn(async move {
+            *thread_name_clone.lock() = thread::current().name().map(str::to_string);
+        })
+        .unwrap();
+
+    let handle = runtime.start();
+    thread::sleep(TASK_EXECUTION_WAIT);
+    runtime.shutdown();
+    handle.wait();
+
+    let name = thread_name.lock().clone().unwrap();
+    assert!(name.starts_with("integration-worker-"));
+}
+
+#[test]
+fn spawn_blocking_does_not_stall_async_tasks() {
+    let runtime = RuntimeBuilder::new().max_blocking_threads(2).build();
+    let spawner = runtime.spawner();
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let ticks_clone = ticks.clone();
+
+    let blocking = spawner.spawn_blocking(|| {
+        thread::sleep(Duration::from_millis(200));
+        7
+    });
+
+    spawner
+        .spawn(async move {
+            for _ in 0..5 {
+                sleep(Duration::from_millis(10)).await;
+                ticks_clone.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .unwrap();
+
+    let _handle = runtime.run(1);
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    assert!(!blocking.is_finished());
+    assert_eq!(ticks.load(Ordering::SeqCst), 5);
+    assert_eq!(runtime.block_on(blocking).unwrap(), 7);
+}
+
+#[test]
+fn panicking_task_completes_handle_with_panicked() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handle = spawner
+        .spawn(async {
+            panic!("boom");
+        })
+        .unwrap();
+
+    let _workers = runtime.run(1);
+    let result: Result<(), JoinError> = runtime.block_on(handle);
+
+    assert!(matches!(result, Err(JoinError::Panicked { .. })));
+}
+
+#[test]
+fn panic_message_is_recoverable_from_join_error() {
+    let runtime = Runtime::new();
+    let handle = runtime
+        .spawner()
+        .spawn(async {
+            let code = 17;
+            panic!("request {} failed", code);
+        })
+        .unwrap();
+
+    let _workers = runtime.run(1);
+    let error = handle.join().unwrap_err();
+
+    assert_eq!(error.panic_message(), Some("request 17 failed"));
+}
+
+#[test]
+fn abort_queued_task_cancels_it() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let executed = Arc::new(AtomicUsize::new(0));
+    let executed_clone = executed.clone();
+
+    let handle = spawner
+        .spawn(async move {
+            executed_clone.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    handle.abort();
+
+    let _workers = runtime.run(1);
+    let result = runtime.block_on(handle);
+
+    assert!(matches!(result, Err(JoinError::Cancelled)));
+    assert_eq!(executed.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn abort_task_pending_on_timer_cancels_it() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handle = spawner
+        .spawn(async {
+            sleep(Duration::from_secs(60)).await;
+        })
+        .unwrap();
+
+    let _workers = runtime.run(1);
+    thread::sleep(TASK_EXECUTION_WAIT);
+    assert!(!handle.is_finished());
+
+    let start = Instant::now();
+    handle.abort();
+    let result = runtime.block_on(handle);
+
+    assert!(matches!(result, Err(JoinError::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn abort_completes_handle_while_worker_is_busy() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let sleeper = spawner
+        .spawn(async {
+            sleep(Duration::from_secs(60)).await;
+        })
+        .unwrap();
+
+    let _workers = runtime.run(1);
+    thread::sleep(TASK_EXECUTION_WAIT);
+    spawner
+        .spawn(async {
+            thread::sleep(Duration::from_millis(500));
+        })
+        .unwrap();
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    let start = Instant::now();
+    sleeper.abort();
+    let result = sleeper.join();
+
+    assert!(matches!(result, Err(JoinError::Cancelled)));
+    assert!(start.elapsed() < Duration::from_millis(250));
+}
+
+#[test]
+fn abort_completed_task_is_noop() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handle = spawner.spawn(async { 42_usize }).unwrap();
+
+    let _workers = runtime.run(1);
+    thread::sleep(TASK_EXECUTION_WAIT);
+    assert!(handle.is_finished());
+
+    handle.abort();
+
+    assert_eq!(runtime.block_on(handle).unwrap(), 42);
+}
+
+#[test]
+fn abort_handle_clones_abort_concurrently() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handle = spawner
+        .spawn(async {
+            sleep(Duration::from_secs(60)).await;
+        })
+        .unwrap();
+    let abort_handle = handle.abort_handle();
+
+    let _workers = runtime.run(TEST_WORKER_COUNT);
+    let aborters: Vec<_> = (0..4)
+        .map(|_| {
+            let abort_handle = abort_handle.clone();
+            thread::spawn(move || abort_handle.abort())
+        })
+        .collect();
+    for aborter in aborters {
+        aborter.join().unwrap();
+    }
+
+    assert!(matches!(
+        runtime.block_on(handle),
+        Err(JoinError::Cancelled)
+    ));
+}
+
+#[test]
+fn yielding_tasks_interleave_on_single_worker() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let log = Arc::new(parking_lot::Mutex::new(Vec::new()));
+
+    for name in ["a", "b"] {
+        let log = log.clone();
+        spawner
+            .spawn(async move {
+                for _ in 0..3 {
+                    log.lock().push(name);
+                    yield_now().await;
+                }
+            })
+            .unwrap();
+    }
+
+    let _workers = runtime.run(1);
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    assert_eq!(*log.lock(), ["a", "b", "a", "b", "a", "b"]);
+}
+
+#[test]
+fn local_runtime_runs_rc_future_with_timer() {
+    let runtime = LocalRuntime::new();
+    let value = Rc::new(Cell::new(0_u32));
+
+    let value_clone = value.clone();
+    let handle = runtime.spawn_local(async move {
+        sleep(Duration::from_millis(10)).await;
+        value_clone.set(value_clone.get() + 1);
+    });
+
+    runtime.block_on(handle).unwrap();
+
+    assert_eq!(value.get(), 1);
+}
+
+#[test]
+fn free_spawn_inside_task_uses_current_runtime() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handle = spawner
+        .spawn(async {
+            let child = runtime::spawn(async {
+                let grandchild = runtime::spawn(async { 21_usize }).unwrap();
+                grandchild.await.unwrap() * 2
+            })
+            .unwrap();
+            child.await.unwrap()
+        })
+        .unwrap();
+
+    let _workers = runtime.run(TEST_WORKER_COUNT);
+
+    assert_eq!(runtime.block_on(handle).unwrap(), 42);
+}
+
+#[test]
+fn free_spawn_outside_runtime_fails() {
+    let result = runtime::spawn(async {});
+
+    assert!(matches!(result, Err(SpawnError::NoRuntime)));
+}
+
+#[test]
+fn spawn_blocking_thread_has_no_current_runtime() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handle = spawner
+        .spawn(async {
+            runtime::Runtime::current()
+                .spawn_blocking(|| runtime::spawn(async {}).is_err())
+                .await
+                .unwrap()
+        })
+        .unwrap();
+
+    let _workers = runtime.run(1);
+
+    assert!(runtime.block_on(handle).unwrap());
+}
+
+#[test]
+fn shutdown_graceful_runs_all_queued_tasks() {
+    let task_count = 100;
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..task_count {
+        let counter_clone = counter.clone();
+        spawner
+            .spawn(async move {
+                counter_clone.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+    }
+
+    let handle = runtime.run(TEST_WORKER_COUNT);
+    runtime.shutdown_graceful();
+    handle.wait();
+
+    assert_eq!(counter.load(Ordering::SeqCst), task_count);
+}
+
+#[test]
+fn shutdown_graceful_waits_for_pending_timers() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let completed = Arc::new(AtomicUsize::new(0));
+    let completed_clone = completed.clone();
+
+    spawner
+        .spawn(async move {
+            sleep(TIMER_DURATION).await;
+            completed_clone.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+
+    let handle = runtime.run(TEST_WORKER_COUNT);
+    runtime.shutdown_graceful();
+    handle.wait();
+
+    assert_eq!(completed.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn metrics_count_spawned_and_completed_tasks() {
+    let task_count = 25;
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    for _ in 0..task_count {
+        spawner.spawn(async {}).unwrap();
+    }
+    assert_eq!(runtime.metrics().global_queue_depth, task_count);
+
+    let _handle = runtime.run(TEST_WORKER_COUNT);
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    let metrics = runtime.metrics();
+    assert_eq!(metrics.tasks_spawned, task_count as u64);
+    assert_eq!(metrics.tasks_completed, task_count as u64);
+    assert_eq!(metrics.tasks_panicked, 0);
+    assert_eq!(metrics.active_workers, TEST_WORKER_COUNT);
+    assert_eq!(metrics.global_queue_depth, 0);
+}
+
+#[test]
+fn metrics_count_panicked_tasks() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handle = spawner.spawn(async { panic!("metrics boom") }).unwrap();
+    let _: Result<(), JoinError> = runtime.block_on(handle);
+
+    let metrics = runtime.metrics();
+    assert_eq!(metrics.tasks_panicked, 1);
+    assert_eq!(metrics.tasks_completed, 0);
+}
+
+struct PingPong {
+    turn: AtomicUsize,
+    wakers: parking_lot::Mutex<[Option<std::task::Waker>; 2]>,
+}
+
+impl PingPong {
+    async fn wait_turn(&self, player: usize) {
+        std::future::poll_fn(|cx| {
+            if self.turn.load(Ordering::SeqCst) == player {
+                return std::task::Poll::Ready(());
+            }
+            self.wakers.lock()[player] = Some(cx.waker().clone());
+            if self.turn.load(Ordering::SeqCst) == player {
+                std::task::Poll::Ready(())
+            } else {
+                std::task::Poll::Pending
+            }
+        })
+        .await
+    }
+
+    fn pass_turn(&self, player: usize) {
+        let other = 1 - player;
+        self.turn.store(other, Ordering::SeqCst);
+        if let Some(waker) = self.wakers.lock()[other].take() {
+            waker.wake();
+        }
+    }
+}
+
+#[test]
+fn poll_budget_lets_global_tasks_interleave_with_local_ping_pong() {
+    let runtime = RuntimeBuilder::new().task_poll_budget(8).build();
+    let spawner = runtime.spawner();
+    let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let ping_pong = Arc::new(PingPong {
+        turn: AtomicUsize::new(0),
+        wakers: parking_lot::Mutex::new([None, None]),
+    });
+
+    let players: Vec<_> = (0..2)
+        .map(|player| {
+            let ping_pong = ping_pong.clone();
+            let stop = stop.clone();
+            spawner
+                .spawn(async move {
+                    while !stop.load(Ordering::SeqCst) {
+                        ping_pong.wait_turn(player).await;
+                        ping_pong.pass_turn(player);
+                    }
+                    ping_pong.pass_turn(player);
+                })
+                .unwrap()
+        })
+        .collect();
+
+    let _workers = runtime.run(1);
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    let stop_clone = stop.clone();
+    spawner
+        .spawn(async move { stop_clone.store(true, Ordering::SeqCst) })
+        .unwrap();
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    assert!(stop.load(Ordering::SeqCst));
+    assert!(players.iter().all(|player| player.is_finished()));
+}
+
+#[test]
+fn sleep_until_waits_for_deadline() {
+    let runtime = Runtime::new();
+    let start = Instant::now();
+    let deadline = start + TIMER_DURATION;
+
+    runtime.block_on(sleep_until(deadline));
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= TIMER_DURATION);
+    assert!(elapsed < TIMER_WAIT);
+}
+
+#[test]
+fn interval_ticks_on_schedule() {
+    let runtime = Runtime::new();
+    let period = Duration::from_millis(20);
+    let start = Instant::now();
+
+    let scheduled = runtime.block_on(async move {
+        let mut ticker = interval(period);
+        let mut scheduled = Vec::new();
+        for _ in 0..5 {
+            scheduled.push(ticker.tick().await);
+        }
+        scheduled
+    });
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= period * 5);
+    assert!(elapsed < period * 10);
+    for pair in scheduled.windows(2) {
+        assert_eq!(pair[1] - pair[0], period);
+    }
+}
+
+#[test]
+fn timeout_returns_output_when_future_completes_in_time() {
+    let runtime = Runtime::new();
+
+    let result = runtime.block_on(timeout(Duration::from_millis(200), async {
+        sleep(Duration::from_millis(10)).await;
+        7
+    }));
+
+    assert_eq!(result, Ok(7));
+}
+
+#[test]
+fn timeout_elapses_when_future_is_too_slow() {
+    let runtime = Runtime::new();
+    let finished = Arc::new(AtomicBool::new(false));
+    let flag = finished.clone();
+    let start = Instant::now();
+
+    let result = runtime.block_on(timeout(Duration::from_millis(20), async move {
+        sleep(Duration::from_millis(500)).await;
+        flag.store(true, Ordering::SeqCst);
+    }));
+
+    assert_eq!(result, Err(runtime::timer::Elapsed));
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert!(!finished.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_clock_completes_sleep_without_real_time() {
+    let runtime = Runtime::new();
+    let clock = runtime::timer::TestClock::new();
+    let _guard = clock.enter();
+    let start = Instant::now();
+
+    let result = runtime.block_on(async {
+        let sleeper = runtime::spawn(async {
+            sleep(Duration::from_secs(3600)).await;
+            "woke"
+        })
+        .unwrap();
+        yield_now().await;
+        clock.advance(Duration::from_secs(3600));
+        sleeper.await
+    });
+
+    assert_eq!(result.unwrap(), "woke");
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn builder_test_clock_drives_timers_on_workers() {
+    let clock = runtime::timer::TestClock::new();
+    let runtime = RuntimeBuilder::new().test_clock(&clock).build();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let start = Instant::now();
+
+    let sleeper = runtime
+        .spawner()
+        .spawn(async {
+            sleep(Duration::from_secs(3600)).await;
+            "woke"
+        })
+        .unwrap();
+    while runtime.block_on(async { runtime::timer::reactor_stats().pending_timers }) == 0 {
+        thread::yield_now();
+    }
+    clock.advance(Duration::from_secs(3600));
+
+    assert_eq!(sleeper.join().unwrap(), "woke");
+    assert!(start.elapsed() < Duration::from_secs(1));
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn builder_test_clock_takes_precedence_over_dedicated_reactor() {
+    let clock = runtime::timer::TestClock::new();
+    let runtime = RuntimeBuilder::new()
+        .dedicated_reactor(true)
+        .test_clock(&clock)
+        .build();
+    let start = Instant::now();
+
+    let result = runtime.block_on(async {
+        let sleeper = runtime::spawn(async {
+            sleep(Duration::from_secs(3600)).await;
+            "woke"
+        })
+        .unwrap();
+        yield_now().await;
+        clock.advance(Duration::from_secs(3600));
+        sleeper.await
+    });
+
+    assert_eq!(result.unwrap(), "woke");
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn child_token_cancellation_is_hierarchical() {
+    let runtime = Runtime::new();
+    let parent = CancellationToken::new();
+    let child = parent.child_token();
+    let sibling = parent.child_token();
+
+    child.cancel();
+    assert!(!parent.is_cancelled());
+    assert!(!sibling.is_cancelled());
+
+    let waiter = sibling.clone();
+    let handle = runtime
+        .spawner()
+        .spawn(async move { waiter.cancelled().await })
+        .unwrap();
+    parent.cancel();
+
+    assert!(runtime.block_on(handle).is_ok());
+    assert!(sibling.is_cancelled());
+}
+
+#[test]
+fn run_until_cancelled_returns_output_when_future_wins() {
+    let runtime = Runtime::new();
+    let token = CancellationToken::new();
+
+    let result = runtime.block_on(token.run_until_cancelled(async { 5 }));
+
+    assert_eq!(result, Some(5));
+}
+
+#[test]
+fn run_until_cancelled_returns_none_when_token_wins() {
+    let runtime = Runtime::new();
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    let finished = Arc::new(AtomicBool::new(false));
+    let flag = finished.clone();
+
+    runtime
+        .spawner()
+        .spawn(async move {
+            sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        })
+        .unwrap();
+
+    let result = runtime.block_on(token.run_until_cancelled(async move {
+        sleep(Duration::from_secs(60)).await;
+        flag.store(true, Ordering::SeqCst);
+    }));
+
+    assert_eq!(result, None);
+    assert!(!finished.load(Ordering::SeqCst));
+}
+
+#[test]
+fn token_with_timeout_cancels_without_explicit_cancel() {
+    let runtime = Runtime::new();
+    let token = CancellationToken::with_timeout(Duration::from_millis(20));
+    let start = Instant::now();
+
+    let result = runtime.block_on(token.run_until_cancelled(sleep(Duration::from_secs(60))));
+
+    assert_eq!(result, None);
+    assert!(token.is_cancelled());
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn join_blocks_main_thread_until_task_finishes() {
+    let runtime = Runtime::new();
+    let handle = runtime
+        .spawner()
+        .spawn(async {
+            sleep(Duration::from_millis(20)).await;
+            42
+        })
+        .unwrap();
+
+    let _workers = runtime.run(TEST_WORKER_COUNT);
+
+    assert_eq!(handle.join().unwrap(), 42);
+}
+
+#[test]
+fn join_panics_inside_worker() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let inner = spawner.spawn(async { 1 }).unwrap();
+    let outer = spawner.spawn(async move { inner.join().unwrap() }).unwrap();
+
+    let _workers = runtime.run(1);
+
+    assert!(matches!(outer.join(), Err(JoinError::Panicked { .. })));
+}
+
+#[test]
+fn join_set_returns_results_in_completion_order() {
+    let runtime = Runtime::new();
+    let delays = [50_u64, 10, 40, 20, 30];
+
+    let order = runtime.block_on(async move {
+        let mut set = JoinSet::new();
+        for delay in delays {
+            set.spawn(async move {
+                sleep(Duration::from_millis(delay * 2)).await;
+                delay
+            })
+            .unwrap();
+        }
+
+        let mut order = Vec::new();
+        while let Some(result) = set.join_next().await {
+            order.push(result.unwrap());
+        }
+        order
+    });
+
+    assert_eq!(order, vec![10, 20, 30, 40, 50]);
+}
+
+#[test]
+fn mpsc_applies_backpressure_between_tasks() {
+    let runtime = Runtime::new();
+    let (sender, mut receiver) = runtime::sync::mpsc::channel(2);
+    let sent = Arc::new(AtomicUsize::new(0));
+    let sent_clone = sent.clone();
+
+    runtime
+        .spawner()
+        .spawn(async move {
+            for i in 0..10 {
+                sender.send(i).await.unwrap();
+                sent_clone.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .unwrap();
+    let _workers = runtime.run(TEST_WORKER_COUNT);
+
+    thread::sleep(TASK_EXECUTION_WAIT);
+    assert_eq!(sent.load(Ordering::SeqCst), 2);
+
+    let received = runtime.block_on(async move {
+        let mut received = Vec::new();
+        while let Some(value) = receiver.recv().await {
+            received.push(value);
+        }
+        received
+    });
+
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+    assert_eq!(sent.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn oneshot_carries_response_between_tasks() {
+    let runtime = Runtime::new();
+    let (sender, receiver) = runtime::sync::oneshot::channel();
+
+    runtime
+        .spawner()
+        .spawn(async move {
+            sleep(Duration::from_millis(10)).await;
+            sender.send(99).unwrap();
+        })
+        .unwrap();
+    let _workers = runtime.run(1);
+
+    assert_eq!(runtime.block_on(receiver), Ok(99));
+}
+
+#[test]
+fn semaphore_limits_concurrent_tasks() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let semaphore = Arc::new(runtime::sync::Semaphore::new(2));
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..5)
+        .map(|_| {
+            let semaphore = semaphore.clone();
+            let running = running.clone();
+            let max_running = max_running.clone();
+            spawner
+                .spawn(async move {
+                    let _permit = semaphore.acquire().await;
+                    let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now_running, Ordering::SeqCst);
+                    sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+                .unwrap()
+        })
+        .collect();
+    let _workers = runtime.run(TEST_WORKER_COUNT);
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    assert_eq!(semaphore.available_permits(), 2);
+}
+
+#[test]
+fn async_mutex_serializes_contending_tasks() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let counter = Arc::new(runtime::sync::Mutex::new(0));
+
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let counter = counter.clone();
+            spawner
+                .spawn(async move {
+                    for _ in 0..10 {
+                        let mut value = counter.lock().await;
+                        let current = *value;
+                        yield_now().await;
+                        *value = current + 1;
+                    }
+                })
+                .unwrap()
+        })
+        .collect();
+    let _workers = runtime.run(TEST_WORKER_COUNT);
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(*counter.try_lock().unwrap(), 20);
+}
+
+#[test]
+fn notify_signals_waiting_task() {
+    let runtime = Runtime::new();
+    let notify = Arc::new(runtime::sync::Notify::new());
+    let waiter_notify = notify.clone();
+    let woke = Arc::new(AtomicBool::new(false));
+    let woke_clone = woke.clone();
+
+    let handle = runtime
+        .spawner()
+        .spawn(async move {
+            waiter_notify.notified().await;
+            woke_clone.store(true, Ordering::SeqCst);
+        })
+        .unwrap();
+    let _workers = runtime.run(1);
+
+    thread::sleep(Duration::from_millis(20));
+    assert!(!woke.load(Ordering::SeqCst));
+
+    notify.notify_one();
+    handle.join().unwrap();
+    assert!(woke.load(Ordering::SeqCst));
+}
+
+#[test]
+fn select2_returns_faster_sleep() {
+    let runtime = Runtime::new();
+    let start = Instant::now();
+
+    let result = runtime.block_on(runtime::select2(
+        async {
+            sleep(Duration::from_millis(300)).await;
+            "slow"
+        },
+        async {
+            sleep(Duration::from_millis(20)).await;
+            "fast"
+        },
+    ));
+
+    assert_eq!(result, runtime::Either::Right("fast"));
+    assert!(start.elapsed() < Duration::from_millis(300));
+}
+
+#[test]
+fn join2_runs_sleeps_concurrently() {
+    let runtime = Runtime::new();
+    let start = Instant::now();
+
+    runtime.block_on(runtime::join2(
+        sleep(Duration::from_millis(50)),
+        sleep(Duration::from_millis(50)),
+    ));
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(50));
+    assert!(elapsed < Duration::from_millis(100));
+}
+
+#[test]
+fn spawn_fn_builds_future_on_worker_thread() {
+    let runtime = RuntimeBuilder::new()
+        .thread_name_prefix("lazy-worker")
+        .build();
+    let spawning_thread = thread::current().id();
+
+    let handle = runtime
+        .spawner()
+        .spawn_fn(|| {
+            let thread = thread::current();
+            let built_on = (thread.id(), thread.name().map(str::to_string));
+            async move { built_on }
+        })
+        .unwrap();
+    let _workers = runtime.run(1);
+
+    let (thread_id, thread_name) = handle.join().unwrap();
+    assert_ne!(thread_id, spawning_thread);
+    assert!(thread_name.unwrap().starts_with("lazy-worker-"));
+}
+
+#[derive(Default)]
+struct CountingObserver {
+    workers_started: AtomicUsize,
+    workers_stopped: AtomicUsize,
+    tasks_panicked: AtomicUsize,
+    slow_polls: AtomicUsize,
+}
+
+impl runtime::RuntimeObserver for CountingObserver {
+    fn on_worker_started(&self, _worker_id: usize) {
+        self.workers_started.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_worker_stopped(&self, _worker_id: usize) {
+        self.workers_stopped.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_task_panicked(&self, _task_name: &str) {
+        self.tasks_panicked.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_slow_poll(&self, _task_name: &str, elapsed: Duration) {
+        assert!(elapsed >= Duration::from_millis(20));
+        self.slow_polls.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn observer_receives_worker_and_panic_events() {
+    let observer = Arc::new(CountingObserver::default());
+    let runtime = RuntimeBuilder::new()
+        .worker_threads(TEST_WORKER_COUNT)
+        .observer(observer.clone())
+        .build();
+
+    let handle = runtime
+        .spawner()
+        .spawn(async { panic!("observed") })
+        .unwrap();
+    let workers = runtime.start();
+
+    assert!(matches!(handle.join(), Err(JoinError::Panicked { .. })));
+    runtime.shutdown();
+    workers.wait();
+
+    assert_eq!(observer.tasks_panicked.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        observer.workers_started.load(Ordering::SeqCst),
+        TEST_WORKER_COUNT
+    );
+    assert_eq!(
+        observer.workers_stopped.load(Ordering::SeqCst),
+        TEST_WORKER_COUNT
+    );
+}
+
+#[test]
+fn shutdown_background_keeps_runtime_usable_for_metrics() {
+    let runtime = Runtime::new();
+    let handle = runtime.spawner().spawn(async { 3 }).unwrap();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    handle.join().unwrap();
+
+    runtime.shutdown_background();
+    let metrics = runtime.metrics();
+    workers.wait();
+
+    assert_eq!(metrics.tasks_spawned, 1);
+    assert_eq!(runtime.metrics().tasks_completed, 1);
+    assert_eq!(runtime.metrics().active_workers, 0);
+    assert!(matches!(
+        runtime.spawner().spawn(async {}),
+        Err(SpawnError::RuntimeStopped)
+    ));
+}
+
+#[test]
+fn try_spawn_rejects_tasks_when_queue_is_full() {
+    let runtime = RuntimeBuilder::new().global_queue_capacity(2).build();
+    let spawner = runtime.spawner();
+
+    spawner.try_spawn(async {}).unwrap();
+    spawner.try_spawn(async {}).unwrap();
+
+    assert!(matches!(
+        spawner.try_spawn(async {}),
+        Err(SpawnError::QueueFull)
+    ));
+    assert!(spawner.spawn(async {}).is_ok());
+}
+
+#[test]
+fn try_spawn_never_exceeds_capacity_under_contention() {
+    let runtime = RuntimeBuilder::new().global_queue_capacity(50).build();
+    let accepted = Arc::new(AtomicUsize::new(0));
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let spawner = runtime.spawner();
+            let accepted = accepted.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    if spawner.try_spawn(async {}).is_ok() {
+                        accepted.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(accepted.load(Ordering::SeqCst), 50);
+}
+
+#[test]
+fn try_spawn_accepts_again_once_queued_tasks_start() {
+    let runtime = RuntimeBuilder::new().global_queue_capacity(1).build();
+    let spawner = runtime.spawner();
+    let first = spawner.try_spawn(async {}).unwrap();
+    assert!(matches!(
+        spawner.try_spawn(async {}),
+        Err(SpawnError::QueueFull)
+    ));
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    runtime.block_on(first).unwrap();
+    let second = spawner.try_spawn(async {}).unwrap();
+    runtime.block_on(second).unwrap();
+
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn try_spawn_is_unbounded_by_default() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    for _ in 0..1_000 {
+        spawner.try_spawn(async {}).unwrap();
+    }
+}
+
+#[test]
+fn join_handle_reports_how_task_finished() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let succeeded = spawner.spawn(async { 1 }).unwrap();
+    let panicked = spawner.spawn(async { panic!("inspect me") }).unwrap();
+    let cancelled = spawner.spawn(std::future::pending::<()>()).unwrap();
+    cancelled.abort();
+
+    let _workers = runtime.run(1);
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    assert!(succeeded.is_finished());
+    assert!(!succeeded.is_panicked() && !succeeded.is_cancelled());
+    assert!(panicked.is_panicked() && !panicked.is_cancelled());
+    assert!(cancelled.is_cancelled() && !cancelled.is_panicked());
+}
+
+#[test]
+fn unordered_accepts_handles_pushed_mid_iteration() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let mut results = runtime.block_on(async move {
+        let mut pending = runtime::future::Unordered::new();
+        for delay in [30_u64, 10] {
+            pending.push(
+                spawner
+                    .spawn(async move {
+                        sleep(Duration::from_millis(delay)).await;
+                        delay
+                    })
+                    .unwrap(),
+            );
+        }
+
+        let mut results = Vec::new();
+        while let Some(result) = pending.next().await {
+            let value = result.unwrap();
+            if value == 10 {
+                pending.push(spawner.spawn(async { 5 }).unwrap());
+            }
+            results.push(value);
+        }
+        results
+    });
+
+    assert_eq!(results[0], 10);
+    results.sort();
+    assert_eq!(results, vec![5, 10, 30]);
+}
+
+#[test]
+fn high_priority_tasks_run_before_normal_ones() {
+    const TASKS_PER_TIER: usize = 8;
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let events = Arc::new(parking_lot::Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..TASKS_PER_TIER * 2)
+        .map(|index| {
+            let priority = match index % 2 {
+                0 => Priority::Normal,
+                _ => Priority::High,
+            };
+            let events = events.clone();
+            spawner
+                .spawn_with_priority(priority, async move {
+                    events.lock().push(priority);
+                    yield_now().await;
+                    events.lock().push(priority);
+                })
+                .unwrap()
+        })
+        .collect();
+
+    let workers = runtime.run(1);
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    runtime.shutdown();
+    workers.wait();
+
+    let events = events.lock();
+    let (high, normal) = events.split_at(TASKS_PER_TIER * 2);
+    assert!(high.iter().all(|priority| *priority == Priority::High));
+    assert!(normal.iter().all(|priority| *priority == Priority::Normal));
+}
+
+runtime::task_local! {
+    static TASK_ID: usize;
+}
+
+#[test]
+fn concurrent_tasks_see_their_own_task_local() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+
+    let handles: Vec<_> = (0..8)
+        .map(|id| {
+            spawner
+                .spawn(TASK_ID.scope(id, async move {
+                    for _ in 0..10 {
+                        assert_eq!(TASK_ID.get(), id);
+                        yield_now().await;
+                        sleep(Duration::from_millis(1)).await;
+                    }
+                    TASK_ID.get()
+                }))
+                .unwrap()
+        })
+        .collect();
+
+    let workers = runtime.run(2);
+    for (id, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.join().unwrap(), id);
+    }
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn blocking_poll_is_reported_to_observer() {
+    let observer = Arc::new(CountingObserver::default());
+    let runtime = RuntimeBuilder::new()
+        .observer(observer.clone())
+        .slow_poll_threshold(Duration::from_millis(20))
+        .build();
+
+    let fast = runtime.spawner().spawn(async {}).unwrap();
+    let blocking = runtime
+        .spawner()
+        .spawn(async { thread::sleep(Duration::from_millis(40)) })
+        .unwrap();
+    let workers = runtime.run(1);
+
+    fast.join().unwrap();
+    blocking.join().unwrap();
+    runtime.shutdown();
+    workers.wait();
+
+    assert_eq!(observer.slow_polls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn named_task_panic_reports_its_name() {
+    let runtime = Runtime::new();
+
+    let handle = runtime
+        .spawner()
+        .spawn_named("payment-processor", async { panic!("card declined") })
+        .unwrap();
+    let error = runtime.block_on(handle).unwrap_err();
+
+    assert_eq!(error.task_name(), Some("payment-processor"));
+    assert!(error.to_string().contains("payment-processor"));
+    assert_eq!(error.panic_message(), Some("card declined"));
+}
+
+#[test]
+fn wait_group_waits_for_all_guards() {
+    const TASKS: usize = 20;
+    let runtime = Runtime::new();
+    let group = runtime::sync::WaitGroup::new();
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    for index in 0..TASKS {
+        let guard = group.add();
+        let finished = finished.clone();
+        runtime
+            .spawner()
+            .spawn(async move {
+                sleep(Duration::from_millis((index % 5) as u64)).await;
+                finished.fetch_add(1, Ordering::SeqCst);
+                drop(guard);
+            })
+            .unwrap();
+    }
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    runtime.block_on(group.wait());
+
+    assert_eq!(finished.load(Ordering::SeqCst), TASKS);
+    assert_eq!(group.count(), 0);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn rwlock_readers_overlap_and_writer_is_exclusive() {
+    let runtime = Runtime::new();
+    let lock = Arc::new(runtime::sync::RwLock::new(0));
+    let active_readers = Arc::new(AtomicUsize::new(0));
+    let max_readers = Arc::new(AtomicUsize::new(0));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let lock = lock.clone();
+            let active_readers = active_readers.clone();
+            let max_readers = max_readers.clone();
+            runtime
+                .spawner()
+                .spawn(async move {
+                    let guard = lock.read().await;
+                    let active = active_readers.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_readers.fetch_max(active, Ordering::SeqCst);
+                    sleep(Duration::from_millis(30)).await;
+                    active_readers.fetch_sub(1, Ordering::SeqCst);
+                    *guard
+                })
+                .unwrap()
+        })
+        .collect();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    for reader in readers {
+        assert_eq!(reader.join().unwrap(), 0);
+    }
+
+    runtime.block_on(async {
+        let mut guard = lock.write().await;
+        assert!(lock.try_read().is_none());
+        *guard = 1;
+    });
+
+    assert!(max_readers.load(Ordering::SeqCst) > 1);
+    assert_eq!(*lock.try_read().unwrap(), 1);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn broadcast_delivers_to_every_receiver_task() {
+    use runtime::sync::broadcast;
+
+    let runtime = Runtime::new();
+    let (sender, receiver) = broadcast::channel(16);
+    let receivers: Vec<_> = std::iter::once(receiver)
+        .chain((0..2).map(|_| sender.subscribe()))
+        .map(|mut receiver| {
+            runtime
+                .spawner()
+                .spawn(async move {
+                    let mut received = Vec::new();
+                    while let Ok(value) = receiver.recv().await {
+                        received.push(value);
+                    }
+                    received
+                })
+                .unwrap()
+        })
+        .collect();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    for value in 0..5 {
+        sender.send(value).unwrap();
+    }
+    drop(sender);
+
+    for handle in receivers {
+        assert_eq!(handle.join().unwrap(), vec![0, 1, 2, 3, 4]);
+    }
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn watch_receivers_observe_sender_updates() {
+    use runtime::sync::watch;
+
+    let runtime = Runtime::new();
+    let (sender, receiver) = watch::channel(0);
+    let observers: Vec<_> = [receiver.clone(), receiver]
+        .into_iter()
+        .map(|mut receiver| {
+            runtime
+                .spawner()
+                .spawn(async move {
+                    let mut last = *receiver.borrow();
+                    while receiver.changed().await.is_ok() {
+                        let value = *receiver.borrow();
+                        assert!(value >= last);
+                        last = value;
+                    }
+                    last
+                })
+                .unwrap()
+        })
+        .collect();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    runtime.block_on(async {
+        for value in 1..=3 {
+            sender.send(value);
+            sleep(Duration::from_millis(5)).await;
+        }
+    });
+    drop(sender);
+
+    for observer in observers {
+        assert_eq!(observer.join().unwrap(), 3);
+    }
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn runtime_handle_wait_timeout_joins_stopped_workers() {
+    let runtime = Runtime::new();
+    let handle = runtime.spawner().spawn(async { 7 }).unwrap();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    assert_eq!(handle.join().unwrap(), 7);
+    runtime.shutdown();
+
+    assert!(workers.wait_timeout(Duration::from_secs(5)).is_ok());
+}
+
+#[test]
+fn mpsc_receiver_stream_collects_every_message() {
+    use runtime::future::StreamExt;
+    use runtime::sync::mpsc;
+
+    let runtime = Runtime::new();
+    let (sender, receiver) = mpsc::channel(2);
+    for producer in 0..2 {
+        let sender = sender.clone();
+        runtime
+            .spawner()
+            .spawn(async move {
+                for value in 0..5 {
+                    sender.send(producer * 10 + value).await.unwrap();
+                }
+            })
+            .unwrap();
+    }
+    drop(sender);
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let mut received = Vec::new();
+    runtime.block_on(receiver.for_each(|value| received.push(value)));
+    received.sort_unstable();
+
+    assert_eq!(received, vec![0, 1, 2, 3, 4, 10, 11, 12, 13, 14]);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn barrier_releases_tasks_together_after_last_arrival() {
+    use runtime::sync::Barrier;
+
+    let runtime = Runtime::new();
+    let barrier = Arc::new(Barrier::new(3));
+    let start = Instant::now();
+    let handles: Vec<_> = (0..3u64)
+        .map(|index| {
+            let barrier = barrier.clone();
+            runtime
+                .spawner()
+                .spawn(async move {
+                    sleep(Duration::from_millis(index * 20)).await;
+                    let arrived = start.elapsed();
+                    let result = barrier.wait().await;
+                    (arrived, start.elapsed(), result.is_leader())
+                })
+                .unwrap()
+        })
+        .collect();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let results: Vec<_> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+
+    let last_arrival = results.iter().map(|(arrived, ..)| *arrived).max().unwrap();
+    for (_, released, _) in &results {
+        assert!(*released >= last_arrival);
+    }
+    assert_eq!(results.iter().filter(|(.., leader)| *leader).count(), 1);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn aborting_scoped_parent_cancels_child_token() {
+    let runtime = Runtime::new();
+    let (child_sender, child_receiver) = std::sync::mpsc::channel();
+    let parent = runtime
+        .spawner()
+        .spawn_scoped(move |_token| async move {
+            let child = Runtime::current()
+                .spawn_scoped(|token| async move {
+                    token.cancelled().await;
+                    "cancelled"
+                })
+                .unwrap();
+            child_sender.send(child).unwrap();
+            std::future::pending::<()>().await;
+        })
+        .unwrap();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let child = child_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(!child.is_finished());
+
+    parent.abort();
+    assert_eq!(child.join().unwrap(), "cancelled");
+    assert!(matches!(parent.join(), Err(JoinError::Cancelled)));
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn runtime_with_core_affinity_executes_tasks() {
+    let runtime = RuntimeBuilder::new().core_affinity(true).build();
+    let handles: Vec<_> = (0..20)
+        .map(|value| runtime.spawner().spawn(async move { value * 2 }).unwrap())
+        .collect();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let total: i32 = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .sum();
+
+    assert_eq!(total, 380);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn worker_stats_account_for_every_spawned_task() {
+    let runtime = Runtime::new();
+    let handles: Vec<_> = (0..50)
+        .map(|_| runtime.spawner().spawn(async {}).unwrap())
+        .collect();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    runtime.shutdown();
+    let stats = workers.wait_with_stats();
+
+    assert_eq!(stats.len(), TEST_WORKER_COUNT);
+    for (worker_id, worker) in stats.iter().enumerate() {
+        assert_eq!(worker.worker_id, worker_id);
+    }
+    let executed: u64 = stats.iter().map(|worker| worker.tasks_executed).sum();
+    assert_eq!(executed, 50);
+}
+
+#[test]
+fn detached_task_runs_without_join_handle() {
+    let runtime = Runtime::new();
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    let () = runtime
+        .spawner()
+        .spawn_detached(async move {
+            sleep(Duration::from_millis(5)).await;
+            sender.send("ran").unwrap();
+        })
+        .unwrap();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok("ran"));
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn runtime_reports_workers_and_active_tasks() {
+    let runtime = Runtime::new();
+    assert_eq!(runtime.num_workers(), 0);
+
+    for _ in 0..3 {
+        runtime
+            .spawner()
+            .spawn(sleep(Duration::from_secs(60)))
+            .unwrap();
+    }
+    let quick = runtime.spawner().spawn(async {}).unwrap();
+    assert_eq!(runtime.active_tasks(), 4);
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    assert_eq!(runtime.num_workers(), TEST_WORKER_COUNT);
+    quick.join().unwrap();
+    thread::sleep(Duration::from_millis(20));
+
+    assert_eq!(runtime.active_tasks(), 3);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn every_idle_strategy_executes_tasks() {
+    for strategy in [
+        IdleStrategy::Spin,
+        IdleStrategy::Yield,
+        IdleStrategy::Park,
+        IdleStrategy::SpinThenPark { spins: 4 },
+    ] {
+        let runtime = RuntimeBuilder::new().idle_strategy(strategy).build();
+        let workers = runtime.run(TEST_WORKER_COUNT);
+
+        let handles: Vec<_> = (0..10u64)
+            .map(|value| {
+                runtime
+                    .spawner()
+                    .spawn(async move {
+                        sleep(Duration::from_millis(value)).await;
+                        value
+                    })
+                    .unwrap()
+            })
+            .collect();
+        let total: u64 = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum();
+
+        assert_eq!(total, 45, "{:?}", strategy);
+        runtime.shutdown();
+        workers.wait();
+    }
+}
+
+#[test]
+fn dropping_owned_task_aborts_it() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let finished = Arc::new(AtomicBool::new(false));
+    let finished_clone = finished.clone();
+
+    let task = runtime
+        .spawner()
+        .spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            finished_clone.store(true, Ordering::SeqCst);
+        })
+        .unwrap()
+        .abort_on_drop();
+    let abort_handle = task.abort_handle();
+    drop(task);
+    drop(abort_handle);
+    thread::sleep(Duration::from_millis(150));
+
+    assert!(!finished.load(Ordering::SeqCst));
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn detached_owned_task_keeps_running() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+
+    let task = runtime
+        .spawner()
+        .spawn(async {
+            sleep(Duration::from_millis(20)).await;
+            "done"
+        })
+        .unwrap()
+        .abort_on_drop();
+    let handle = task.detach();
+
+    assert_eq!(handle.join().unwrap(), "done");
+    let owned = runtime
+        .spawner()
+        .spawn(async { 5 })
+        .unwrap()
+        .abort_on_drop();
+    assert_eq!(runtime.block_on(owned).unwrap(), 5);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn scope_waits_for_every_child() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    let counter = finished.clone();
+    let children_done = runtime.block_on(runtime.spawner().scope(|scope| async move {
+        for delay in [30, 10, 20] {
+            let counter = counter.clone();
+            scope
+                .spawn(async move {
+                    sleep(Duration::from_millis(delay)).await;
+                    counter.fetch_add(1, Ordering::SeqCst);
+                })
+                .unwrap();
+        }
+        counter.load(Ordering::SeqCst)
+    }));
+
+    assert_eq!(children_done, 0);
+    assert_eq!(finished.load(Ordering::SeqCst), 3);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn rate_limiter_spaces_out_acquires() {
+    let runtime = Runtime::new();
+    let limiter = runtime::sync::RateLimiter::new(5, 1);
+
+    let start = Instant::now();
+    runtime.block_on(async {
+        for _ in 0..10 {
+            limiter.acquire().await;
+        }
+    });
+    let elapsed = start.elapsed();
+
+    // The first token is in the bucket; the other nine are minted 200ms apart.
+    assert!(elapsed >= Duration::from_millis(1750), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(2500), "{elapsed:?}");
+}
+
+#[test]
+fn borrowed_handle_stays_queryable_after_await() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let mut handle = runtime
+        .spawner()
+        .spawn(async {
+            sleep(Duration::from_millis(10)).await;
+            "done"
+        })
+        .unwrap();
+
+    let result = runtime.block_on(handle.wait_ref());
+
+    assert_eq!(result.unwrap(), "done");
+    assert!(handle.is_finished());
+    assert!(!handle.is_cancelled());
+    handle.abort();
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn spawn_after_delays_the_side_effect() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let ran_at = Arc::new(parking_lot::Mutex::new(None));
+
+    let start = Instant::now();
+    let recorded = ran_at.clone();
+    let handle = runtime
+        .spawner()
+        .spawn_after(TIMER_DURATION, async move {
+            *recorded.lock() = Some(Instant::now());
+        })
+        .unwrap();
+    assert!(ran_at.lock().is_none());
+
+    handle.join().unwrap();
+    let ran_at = ran_at.lock().expect("task should have run");
+    assert!(ran_at - start >= TIMER_DURATION);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn aborting_spawn_after_during_delay_prevents_the_task() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let ran = Arc::new(AtomicBool::new(false));
+
+    let flag = ran.clone();
+    let handle = runtime
+        .spawner()
+        .spawn_after(Duration::from_millis(50), async move {
+            flag.store(true, Ordering::SeqCst);
+        })
+        .unwrap();
+    handle.abort();
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    assert!(matches!(handle.join(), Err(JoinError::Cancelled)));
+    assert!(!ran.load(Ordering::SeqCst));
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn bursts_of_tasks_never_leave_workers_parked() {
+    let runtime = RuntimeBuilder::new()
+        .idle_strategy(IdleStrategy::Park)
+        .build();
+    let workers = runtime.run(4);
+    let (done, finished) = std::sync::mpsc::channel();
+
+    let spawner = runtime.spawner();
+    thread::spawn(move || {
+        for burst in 0..50 {
+            let handles: Vec<_> = (0..100)
+                .map(|i| {
+                    spawner
+                        .spawn(async move {
+                            if i % 2 == 0 {
+                                yield_now().await;
+                            }
+                            burst * i
+                        })
+                        .unwrap()
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            // Let every worker go back to sleep before the next burst.
+            thread::sleep(Duration::from_millis(1));
+        }
+        done.send(()).unwrap();
+    });
+
+    finished
+        .recv_timeout(Duration::from_secs(30))
+        .expect("runtime hung with tasks queued");
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn chained_timeout_at_respects_shared_deadline() {
+    let runtime = Runtime::new();
+    let start = Instant::now();
+    let deadline = start + TIMER_DURATION;
+
+    let (first, second) = runtime.block_on(async {
+        let first = runtime::timer::timeout_at(deadline, sleep(TIMER_DURATION / 2)).await;
+        let second = runtime::timer::timeout_at(deadline, sleep(TIMER_DURATION)).await;
+        (first, second)
+    });
+
+    assert!(first.is_ok());
+    assert!(second.is_err());
+    let elapsed = start.elapsed();
+    assert!(elapsed >= TIMER_DURATION);
+    assert!(elapsed < TIMER_DURATION * 2, "{elapsed:?}");
+}
+
+#[test]
+fn run_until_idle_returns_after_task_chain_finishes() {
+    let runtime = Runtime::new();
+    let log = Arc::new(parking_lot::Mutex::new(Vec::new()));
+
+    let first = log.clone();
+    runtime
+        .spawner()
+        .spawn(async move {
+            sleep(Duration::from_millis(20)).await;
+            first.lock().push(1);
+            let second = first.clone();
+            runtime::spawn(async move {
+                sleep(Duration::from_millis(20)).await;
+                second.lock().push(2);
+                let third = second.clone();
+                runtime::spawn(async move {
+                    sleep(Duration::from_millis(20)).await;
+                    third.lock().push(3);
+                })
+                .unwrap();
+            })
+            .unwrap();
+        })
+        .unwrap();
+
+    let start = Instant::now();
+    runtime.run_until_idle(TEST_WORKER_COUNT);
+
+    assert_eq!(*log.lock(), vec![1, 2, 3]);
+    assert!(start.elapsed() >= Duration::from_millis(60));
+    assert_eq!(runtime.active_tasks(), 0);
+}
+
+#[test]
+fn blocking_handle_runs_jobs_with_bounded_concurrency() {
+    let runtime = RuntimeBuilder::new().max_blocking_threads(2).build();
+    let blocking = runtime.blocking_handle();
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..4)
+        .map(|job| {
+            let running = running.clone();
+            let peak = peak.clone();
+            blocking.submit(move || {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now_running, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(30));
+                running.fetch_sub(1, Ordering::SeqCst);
+                job
+            })
+        })
+        .collect();
+
+    let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    assert_eq!(results, vec![0, 1, 2, 3]);
+    assert!(peak.load(Ordering::SeqCst) <= 2);
+    runtime.shutdown();
+}
+
+#[test]
+fn spawn_ordered_starts_tasks_in_spawn_order() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(4);
+    let started = Arc::new(parking_lot::Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..10)
+        .map(|i| {
+            let started = started.clone();
+            runtime
+                .spawner()
+                .spawn_ordered(async move {
+                    started.lock().push(i);
+                    yield_now().await;
+                })
+                .unwrap()
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(*started.lock(), (0..10).collect::<Vec<_>>());
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn aborted_ordered_task_does_not_block_later_ones() {
+    let runtime = Runtime::new();
+    let spawner = runtime.spawner();
+    let started = Arc::new(parking_lot::Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..3)
+        .map(|i| {
+            let started = started.clone();
+            spawner
+                .spawn_ordered(async move { started.lock().push(i) })
+                .unwrap()
+        })
+        .collect();
+    handles[0].abort();
+
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let results: Vec<_> = handles.into_iter().map(|handle| handle.join()).collect();
+
+    assert!(matches!(results[0], Err(JoinError::Cancelled)));
+    assert_eq!(*started.lock(), vec![1, 2]);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn runtimes_with_dedicated_reactors_do_not_share_timers() {
+    let first = RuntimeBuilder::new().dedicated_reactor(true).build();
+    let second = RuntimeBuilder::new().dedicated_reactor(true).build();
+    let first_workers = first.run(1);
+    let second_workers = second.run(1);
+
+    // The sleep is created on a worker, so it registers with that runtime's
+    // reactor.
+    let sleeper = first
+        .spawner()
+        .spawn(async { sleep(Duration::from_secs(60)).await })
+        .unwrap();
+    while first.block_on(async { runtime::timer::reactor_stats().pending_timers }) == 0 {
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let (pending, ()) = second.block_on(async {
+        let pending = runtime::timer::reactor_stats().pending_timers;
+        (pending, sleep(Duration::from_millis(10)).await)
+    });
+    assert_eq!(pending, 0);
+
+    // Shutting the first runtime down stops its reactor; the second
+    // runtime's timers keep working.
+    first.shutdown();
+    first_workers.wait();
+    drop(sleeper);
+    second.block_on(async { sleep(Duration::from_millis(10)).await });
+    second.shutdown();
+    second_workers.wait();
+}
+
+#[test]
+fn oneshot_producer_abandons_work_when_receiver_drops() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let (sender, receiver) = runtime::sync::oneshot::channel::<u64>();
+
+    let producer = runtime
+        .spawner()
+        .spawn(async move {
+            let work = std::pin::pin!(async {
+                sleep(Duration::from_secs(60)).await;
+                42
+            });
+            let closed = std::pin::pin!(sender.closed());
+            matches!(
+                runtime::select2(work, closed).await,
+                runtime::Either::Right(())
+            )
+        })
+        .unwrap();
+    thread::sleep(Duration::from_millis(20));
+    drop(receiver);
+
+    assert!(producer.join().unwrap());
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn mapped_join_handle_doubles_task_output() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+
+    let handle = runtime.spawner().spawn(async { 21 }).unwrap();
+    let doubled = runtime.block_on(handle.map(|value| value * 2));
+
+    assert_eq!(doubled.unwrap(), 42);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn is_in_runtime_distinguishes_tasks_from_main_thread() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+
+    let handle = runtime
+        .spawner()
+        .spawn(async { runtime::is_in_runtime() })
+        .unwrap();
+
+    assert!(handle.join().unwrap());
+    assert!(!runtime::is_in_runtime());
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn shutdown_forceful_cancels_long_running_tasks() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            runtime
+                .spawner()
+                .spawn(async {
+                    loop {
+                        sleep(Duration::from_secs(60)).await;
+                    }
+                })
+                .unwrap()
+        })
+        .collect();
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    let started = Instant::now();
+    runtime.shutdown_forceful();
+
+    for handle in handles {
+        assert!(matches!(handle.join(), Err(JoinError::Cancelled)));
+    }
+    workers.wait();
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn spawn_fallible_surfaces_task_error_directly() {
+    #[derive(Debug)]
+    enum JobError {
+        InvalidInput(&'static str),
+        Join(JoinError),
+    }
+
+    impl From<JoinError> for JobError {
+        fn from(error: JoinError) -> Self {
+            JobError::Join(error)
+        }
+    }
+
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let spawner = runtime.spawner();
+
+    let failing = spawner
+        .spawn_fallible(async { Err::<u32, _>(JobError::InvalidInput("negative size")) })
+        .unwrap();
+    let panicking = spawner
+        .spawn_fallible(async {
+            if true {
+                panic!("job crashed");
+            }
+            Ok::<u32, JobError>(0)
+        })
+        .unwrap();
+    let succeeding = spawner
+        .spawn_fallible(async { Ok::<_, JobError>(7) })
+        .unwrap();
+
+    let outcome = runtime.block_on(async {
+        let value = succeeding.await?;
+        failing.await?;
+        Ok::<u32, JobError>(value)
+    });
+
+    assert!(matches!(
+        outcome,
+        Err(JobError::InvalidInput("negative size"))
+    ));
+    assert!(matches!(
+        runtime.block_on(panicking),
+        Err(JobError::Join(JoinError::Panicked { .. }))
+    ));
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn global_queue_interval_runs_external_task_amid_local_flood() {
+    const CHILDREN: usize = 32;
+
+    struct Ring {
+        turn: AtomicUsize,
+        wakers: parking_lot::Mutex<Vec<Option<std::task::Waker>>>,
+    }
+
+    impl Ring {
+        async fn wait_turn(&self, child: usize) {
+            std::future::poll_fn(|cx| {
+                self.wakers.lock()[child] = Some(cx.waker().clone());
+                if self.turn.load(Ordering::SeqCst) % CHILDREN == child {
+                    std::task::Poll::Ready(())
+                } else {
+                    std::task::Poll::Pending
+                }
+            })
+            .await
+        }
+
+        fn pass_turn(&self) {
+            let next = (self.turn.fetch_add(1, Ordering::SeqCst) + 1) % CHILDREN;
+            if let Some(waker) = self.wakers.lock()[next].take() {
+                waker.wake();
+            }
+        }
+    }
+
+    // A poll budget this large never kicks in, leaving the interval as the
+    // only thing standing between the flood and the external task.
+    let runtime = RuntimeBuilder::new()
+        .task_poll_budget(usize::MAX)
+        .global_queue_interval(4)
+        .build();
+    let spawner = runtime.spawner();
+    let stop = Arc::new(AtomicBool::new(false));
+    let ring = Arc::new(Ring {
+        turn: AtomicUsize::new(0),
+        wakers: parking_lot::Mutex::new(vec![None; CHILDREN]),
+    });
+
+    let parent_ring = ring.clone();
+    let parent_stop = stop.clone();
+    spawner
+        .spawn(async move {
+            for child in 0..CHILDREN {
+                let ring = parent_ring.clone();
+                let stop = parent_stop.clone();
+                runtime::spawn(async move {
+                    while !stop.load(Ordering::SeqCst) {
+                        ring.wait_turn(child).await;
+                        ring.pass_turn();
+                    }
+                    ring.pass_turn();
+                })
+                .unwrap();
+            }
+        })
+        .unwrap();
+
+    let workers = runtime.run(1);
+    thread::sleep(TASK_EXECUTION_WAIT);
+    assert!(ring.turn.load(Ordering::SeqCst) > CHILDREN);
+
+    let external_stop = stop.clone();
+    let external = spawner
+        .spawn(async move { external_stop.store(true, Ordering::SeqCst) })
+        .unwrap();
+    thread::sleep(TASK_EXECUTION_WAIT);
+
+    assert!(external.is_finished());
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn acquire_many_serializes_requests_that_do_not_fit_together() {
+    let runtime = Runtime::new();
+    let workers = runtime.run(TEST_WORKER_COUNT);
+    let semaphore = Arc::new(runtime::sync::Semaphore::new(5));
+    let holders = Arc::new(AtomicUsize::new(0));
+    let max_holders = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let semaphore = semaphore.clone();
+            let holders = holders.clone();
+            let max_holders = max_holders.clone();
+            runtime
+                .spawner()
+                .spawn(async move {
+                    let _permits = semaphore.acquire_many(3).await;
+                    let now_holding = holders.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_holders.fetch_max(now_holding, Ordering::SeqCst);
+                    sleep(Duration::from_millis(20)).await;
+                    holders.fetch_sub(1, Ordering::SeqCst);
+                })
+                .unwrap()
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(max_holders.load(Ordering::SeqCst), 1);
+    assert_eq!(semaphore.available_permits(), 5);
+    runtime.shutdown();
+    workers.wait();
+}
+
+#[test]
+fn shutdown_reports_tasks_dropped_before_running() {
+    let runtime = Runtime::new();
+    let ran = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            let ran = ran.clone();
+            runtime
+                .spawner()
+                .spawn(async move {
+                    ran.fetch_add(1, Ordering::SeqCst);
+                })
+                .unwrap()
+        })
+        .collect();
+
+    let dropped = runtime.shutdown();
+
+    assert_eq!(dropped, 10);
+    assert_eq!(ran.load(Ordering::SeqCst), 0);
+    for handle in handles {
+        assert!(matches!(handle.join(), Err(JoinError::Cancelled)));
+    }
+}